@@ -8,6 +8,8 @@
 #define OLED_UPDATE_PROCESS_LIMIT 32
 
 #define SPLIT_TRANSACTION_IDS_USER HID_SYNC
+// so the right half knows when the left was last used, for idling
+#define SPLIT_ACTIVITY_ENABLE
 
 #define RGBLIGHT_ENABLE
 
@@ -43,3 +45,6 @@
 #endif
 // ### NULLPTR'S STUFF BEGINS HERE -- DO NOT TOUCH! DON'T EVEN MODIFY THIS COMMENT!
 #define EECONFIG_USER_DATA_SIZE 5
+#ifndef ENCODER_MAP_ENABLE
+#    error "keymap! has encoders, set ENCODER_MAP_ENABLE = yes in rules.mk"
+#endif
@@ -5,4 +5,7 @@ ENCODER_ENABLE = yes
 WPM_ENABLE = yes
 RAW_ENABLE = yes
 RGBLIGHT_ENABLE = yes
-DEFERRED_EXEC_ENABLE = yes
\ No newline at end of file
+DEFERRED_EXEC_ENABLE = yes
+OS_DETECTION_ENABLE = yes
+ENCODER_MAP_ENABLE = yes
+# ### NULLPTR'S STUFF BEGINS HERE -- DO NOT TOUCH! DON'T EVEN MODIFY THIS COMMENT!
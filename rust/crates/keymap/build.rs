@@ -1,10 +1,16 @@
 use std::env;
 
-use qmk_callback::write_glue_code;
+use qmk_callback::{write_glue_code, write_keymap_json, write_log_table};
 
 fn main() {
     let is_wasm_target = env::var("TARGET").unwrap_or_default() == "wasm32-unknown-unknown";
     if !is_wasm_target {
         write_glue_code("../../../keyboards/sofle/keymaps/nulls_keymap/rust_bindings.c");
+        write_log_table(
+            &["src/**/*.rs", "../qmk/src/**/*.rs"],
+            "../../../keyboards/sofle/keymaps/nulls_keymap/log_strings.tsv",
+        );
+        // not next to keymap.c, where QMK would build it too
+        write_keymap_json("keymap.json");
     };
 }
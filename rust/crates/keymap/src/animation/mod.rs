@@ -12,6 +12,9 @@ use micromath::F32Ext;
 #[derive(Default)]
 pub struct Spring {
     spring_inner: SpringInner,
+    delta_time: f32,
+    angular_frequency: f32,
+    damping_ratio: f32,
     current: f32,
     velocity: f32,
     target: f32,
@@ -23,10 +26,15 @@ impl Spring {
         angular_frequency: AngularFrequency,
         damping_ratio: DampingRatio,
     ) -> Self {
-        let spring_inner = SpringInner::new(delta_time, angular_frequency, damping_ratio);
+        let delta_time_secs = delta_time.0;
+        let spring_inner =
+            SpringInner::new(delta_time, angular_frequency.clone(), damping_ratio.clone());
 
         Self {
             spring_inner,
+            delta_time: delta_time_secs,
+            angular_frequency: angular_frequency.0,
+            damping_ratio: damping_ratio.0,
             current: 0.0,
             velocity: 0.0,
             target: 0.0,
@@ -49,6 +57,21 @@ impl Spring {
         self.velocity = velocity;
     }
 
+    /// Steps the spring forward by `delta` instead of the fixed time step it was created with.
+    pub fn update_by(&mut self, delta: Duration) {
+        let delta_time = delta.as_secs_f32();
+        if (delta_time - self.delta_time).abs() > f32::EPSILON {
+            self.spring_inner = SpringInner::new(
+                DeltaTime(delta_time),
+                AngularFrequency(self.angular_frequency),
+                DampingRatio(self.damping_ratio),
+            );
+            self.delta_time = delta_time;
+        }
+
+        self.update();
+    }
+
     pub fn update(&mut self) {
         let (pos, vel) = self
             .spring_inner
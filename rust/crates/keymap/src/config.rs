@@ -1,9 +1,16 @@
 #[cfg(target_arch = "wasm32")]
-use alloc::string::{String, ToString};
+use alloc::string::String;
 use core::cell::RefCell;
-use critical_section::Mutex;
+use critical_section::{Mutex, with};
 use once_cell::sync::Lazy;
-use qmk::eeconfig::EEConfig;
+use qmk::{
+    eeconfig::EEConfig,
+    rgb::Hsv,
+    timer::{DeferToken, Timer},
+};
+
+// writes wait this long after the last change, so flicking through options doesn't wear the flash
+const SAVE_DELAY_MS: u32 = 5000;
 
 pub static SETTINGS: Lazy<Mutex<RefCell<UserConfig>>> = Lazy::new(|| {
     let mut config = UserConfig::new();
@@ -12,6 +19,8 @@ pub static SETTINGS: Lazy<Mutex<RefCell<UserConfig>>> = Lazy::new(|| {
     Mutex::new(RefCell::new(config))
 });
 
+static PENDING_SAVE: Mutex<RefCell<Option<DeferToken>>> = Mutex::new(RefCell::new(None));
+
 #[derive(Debug, Default, Clone, Copy)]
 #[cfg_attr(
     target_arch = "wasm32",
@@ -40,10 +49,6 @@ impl PageTransition {
     }
 }
 
-#[derive(Debug, Default, Clone)]
-#[cfg_attr(target_arch = "wasm32", derive(serde::Serialize, serde::Deserialize))]
-pub struct Hsv(pub [u8; 3]);
-
 #[derive(Debug, Default, Clone)]
 #[cfg_attr(
     target_arch = "wasm32",
@@ -56,36 +61,12 @@ pub struct UserConfig {
     pub startup_skip: bool,
 }
 
-#[cfg(target_arch = "wasm32")]
-impl layout_inspect::Inspect for Hsv {
-    fn name() -> std::string::String {
-        "[u8; 3]".to_string()
-    }
-
-    fn align() -> Option<usize> {
-        Some(0x1)
-    }
-
-    fn def(collector: &mut layout_inspect::TypesCollector) -> layout_inspect::defs::DefType {
-        layout_inspect::defs::DefType::Vec(layout_inspect::defs::DefVec {
-            name: Self::name(),
-            size: Self::size().unwrap(),
-            align: Self::align().unwrap(),
-            value_type_id: collector.collect::<u8>(),
-        })
-    }
-
-    fn size() -> Option<usize> {
-        Some(core::mem::size_of::<Self>())
-    }
-}
-
 impl UserConfig {
     pub fn new() -> Self {
         let _ = EEConfig::<UserConfig>::new();
         Self {
             transition: PageTransition::Dither,
-            hsv: Hsv([0, 0, 0]),
+            hsv: Hsv::BLACK,
             startup_skip: false,
         }
     }
@@ -99,6 +80,31 @@ impl UserConfig {
         let eeconfig = EEConfig::new();
         eeconfig.save(self);
     }
+
+    /// Saves `SETTINGS` a few seconds from now, or once `flush` is called. Changing them again
+    /// before then pushes the write back.
+    pub fn save_later(&self) {
+        with(|cs| {
+            let mut pending = PENDING_SAVE.borrow_ref_mut(cs);
+            if pending.is_some_and(|token| token.extend(SAVE_DELAY_MS)) {
+                return;
+            }
+            *pending = Timer::defer(SAVE_DELAY_MS, flush);
+            if pending.is_none() {
+                // no free deferred executor, so don't risk losing the change
+                self.save();
+            }
+        });
+    }
+}
+
+/// Writes `SETTINGS` out now if a `save_later` is waiting.
+pub fn flush() {
+    let Some(token) = with(|cs| PENDING_SAVE.borrow_ref_mut(cs).take()) else {
+        return;
+    };
+    token.cancel();
+    with(|cs| SETTINGS.borrow_ref(cs).save());
 }
 
 #[cfg(target_arch = "wasm32")]
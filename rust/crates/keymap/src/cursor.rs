@@ -0,0 +1,160 @@
+use crate::{
+    image::CURSOR,
+    state::{INPUT_HANDLER, InputEvent},
+};
+use alloc::vec::Vec;
+use core::cell::RefCell;
+use critical_section::{Mutex, with};
+use qmk::{
+    framebuffer::Framebuffer,
+    pointing::{MouseButtons, MouseReport, Pointing},
+    rect::Rect,
+    screen::Screen,
+};
+
+// how far one encoder detent moves the cursor
+const ENCODER_STEP: i16 = 4;
+
+struct Cursor {
+    active: bool,
+    x: i16,
+    y: i16,
+    // buttons held in the last report, so a click is only sent on press
+    buttons: MouseButtons,
+    // clickable regions drawn so far this frame
+    regions: Vec<(Rect<i16>, u8)>,
+    // the regions of the last whole frame, which clicks are checked against
+    last_regions: Vec<(Rect<i16>, u8)>,
+}
+
+static STATE: Mutex<RefCell<Cursor>> = Mutex::new(RefCell::new(Cursor {
+    active: false,
+    x: Screen::OLED_DISPLAY_WIDTH as i16 / 2,
+    y: Screen::OLED_DISPLAY_HEIGHT as i16 / 2,
+    buttons: MouseButtons::empty(),
+    regions: Vec::new(),
+    last_regions: Vec::new(),
+}));
+
+pub fn init() {
+    Pointing::set_handler(Some(on_report));
+}
+
+/// Shows the cursor. While it's shown the trackball moves it instead of the host's pointer, or
+/// without one the left encoder moves it across and the right one up and down. Clicking sends
+/// `InputEvent::Click` with the tag of the region under it, and clicking anywhere else hides it.
+pub fn show() {
+    with(|cs| STATE.borrow_ref_mut(cs).active = true);
+}
+
+pub fn is_active() -> bool {
+    with(|cs| STATE.borrow_ref(cs).active)
+}
+
+/// Makes `rect` clickable for this frame. Call it while rendering a page.
+pub fn tag(rect: Rect<i16>, tag: u8) {
+    with(|cs| STATE.borrow_ref_mut(cs).regions.push((rect, tag)));
+}
+
+/// Draws the cursor over the finished frame, and keeps the frame's regions for clicks.
+pub fn draw(framebuffer: &mut Framebuffer) {
+    let position = with(|cs| {
+        let mut cursor = STATE.borrow_ref_mut(cs);
+        cursor.last_regions = core::mem::take(&mut cursor.regions);
+        cursor.active.then_some((cursor.x, cursor.y))
+    });
+
+    if let Some((x, y)) = position {
+        framebuffer.draw_image(x, y, &CURSOR);
+    }
+}
+
+/// Moves the cursor if it's shown. Returns false if the page should get the event instead.
+pub fn encoder_scroll(index: u8, clockwise: bool) -> bool {
+    if !is_active() {
+        return false;
+    }
+
+    let step = if clockwise {
+        ENCODER_STEP
+    } else {
+        -ENCODER_STEP
+    };
+    if index == 0 {
+        move_by(step, 0);
+    } else {
+        move_by(0, step);
+    }
+    true
+}
+
+/// Clicks if the cursor is shown. Returns false if the page should get the event instead.
+pub fn encoder_click() -> bool {
+    if !is_active() {
+        return false;
+    }
+
+    click();
+    true
+}
+
+fn move_by(dx: i16, dy: i16) {
+    with(|cs| {
+        let mut cursor = STATE.borrow_ref_mut(cs);
+        cursor.x = cursor
+            .x
+            .saturating_add(dx)
+            .clamp(0, Screen::OLED_DISPLAY_WIDTH as i16 - 1);
+        cursor.y = cursor
+            .y
+            .saturating_add(dy)
+            .clamp(0, Screen::OLED_DISPLAY_HEIGHT as i16 - 1);
+    });
+}
+
+fn click() {
+    let hit = with(|cs| {
+        let mut cursor = STATE.borrow_ref_mut(cs);
+        let (x, y) = (cursor.x, cursor.y);
+        let hit = cursor
+            .last_regions
+            .iter()
+            .rev()
+            .find(|(rect, _)| {
+                (rect.x..rect.x + rect.width).contains(&x)
+                    && (rect.y..rect.y + rect.height).contains(&y)
+            })
+            .map(|&(_, tag)| tag);
+        if hit.is_none() {
+            cursor.active = false;
+        }
+        hit
+    });
+
+    if let Some(tag) = hit {
+        with(|cs| {
+            if let Ok(mut input_handler) = INPUT_HANDLER.borrow(cs).try_borrow_mut() {
+                input_handler.handle_event(InputEvent::Click(tag));
+            }
+        });
+    }
+}
+
+// keeps trackball movement from the host while the cursor is shown
+fn on_report(report: MouseReport) -> Option<MouseReport> {
+    if !is_active() {
+        return Some(report);
+    }
+
+    move_by(report.x, report.y);
+    let pressed = with(|cs| {
+        let mut cursor = STATE.borrow_ref_mut(cs);
+        let pressed = report.buttons - cursor.buttons;
+        cursor.buttons = report.buttons;
+        pressed
+    });
+    if pressed.contains(MouseButtons::LEFT) {
+        click();
+    }
+    None
+}
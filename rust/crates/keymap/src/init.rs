@@ -1,8 +1,8 @@
 use core::sync::atomic::Ordering;
 
-use crate::{config::SETTINGS, pages::TRANSITION_TYPE, screen::marquee};
+use crate::{config::SETTINGS, cursor, pages::TRANSITION_TYPE, rgb, screen::marquee};
 use critical_section::with;
-use qmk::{qmk_callback, rgb::RGBLight};
+use qmk::{executor::Executor, indicator::Indicators, info, qmk_callback, rgb::RGBLight};
 
 #[cfg(not(target_arch = "wasm32"))]
 unsafe extern "C" {
@@ -16,10 +16,25 @@ fn keyboard_post_init_user() {
         #[allow(clippy::clone_on_copy)]
         settings.clone()
     });
-    RGBLight::set_hsv(settings.hsv.0[0], settings.hsv.0[1], settings.hsv.0[2]);
+    RGBLight::set_hsv(settings.hsv);
     TRANSITION_TYPE.store(settings.transition as u8, Ordering::SeqCst);
+    rgb::init();
+    cursor::init();
+    info!("Started, {:?} transition", settings.transition);
     #[cfg(not(target_arch = "wasm32"))]
     unsafe {
         do_that_stuff_man();
     };
 }
+
+#[qmk_callback(() -> void)]
+fn housekeeping_task_user() {
+    Executor::poll();
+    Indicators::task();
+}
+
+#[cfg(target_arch = "wasm32")]
+#[wasm_bindgen::prelude::wasm_bindgen]
+pub fn housekeeping_task_wasm() {
+    housekeeping_task_user();
+}
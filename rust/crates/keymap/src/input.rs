@@ -1,21 +1,39 @@
-use core::ffi::c_void;
-
+#[cfg(target_arch = "wasm32")]
+use crate::keymap::process_record_user;
 use crate::{
-    keymap::CS_RESET,
-    state::{INPUT_HANDLER, InputEvent},
+    cursor,
+    state::{INPUT_HANDLER, InputEvent, LEFT_ENCODER_CLICK, RIGHT_ENCODER_CLICK},
 };
 use critical_section::with;
 use qmk::{
-    KeyRecord, defer_exec,
-    keyboard::Keyboard,
-    keys::{KC_C, KC_DOWN, KC_ENTER, KC_F20, KC_F21},
+    KeyRecord,
+    custom_keycode::is_pressed,
+    executor::{EVENTS, Event},
+    info, keyevent_type_t,
+    keys::{KC_NO, Keycode},
+    os_detection::{HostOs, OsVariant},
+    pointing::Pointing,
     qmk_callback, qmk_log,
 };
 #[cfg(target_arch = "wasm32")]
+use qmk::{keyboard::Keyboard, timer::Timer};
+#[cfg(target_arch = "wasm32")]
 use wasm_bindgen::prelude::wasm_bindgen;
 
 #[qmk_callback((uint8_t, bool) -> bool)]
 fn encoder_update_user(index: u8, clockwise: bool) -> bool {
+    #[cfg(target_arch = "wasm32")]
+    Keyboard::simulate_activity();
+    encoder_turned(index, clockwise)
+}
+
+// with the encoder map QMK skips `encoder_update_user`, so the UI's turns come through
+// `process_record_user` instead
+fn encoder_turned(index: u8, clockwise: bool) -> bool {
+    EVENTS.send(Event::Encoder { index, clockwise });
+    if cursor::encoder_scroll(index, clockwise) {
+        return false;
+    }
     with(|cs| {
         if let Ok(mut input_handler) = INPUT_HANDLER.borrow(cs).try_borrow_mut() {
             input_handler.handle_event(InputEvent::EncoderScroll(index, clockwise));
@@ -24,33 +42,38 @@ fn encoder_update_user(index: u8, clockwise: bool) -> bool {
     false
 }
 
-#[unsafe(no_mangle)]
-unsafe extern "C" fn macro_callback(_: u32, cb_arg: *mut c_void) -> u32 {
-    let key = cb_arg as u32;
-    let key = key as u16;
-    Keyboard::send_key_delay(key, 50);
-    0
-}
-
-#[qmk_callback((uint16_t, keyrecord_t*) -> bool)]
-pub fn process_record_user(keycode: u16, record: *const KeyRecord) -> bool {
-    let keycode = keycode as u32;
-    let record = unsafe { *record };
-    if record.event.type_ == 257 {
-        let inputs = match keycode as u16 {
-            CS_RESET => Some(&[KC_ENTER, KC_DOWN, KC_C]),
-            _ => None,
-        };
+/// Everything but the custom keycodes, which `custom_keycodes!` runs first in the
+/// `process_record_user` it generates.
+pub fn process_record(keycode: u16, record: &KeyRecord) -> bool {
+    let keycode = Keycode::new(keycode);
 
-        if let Some(inputs) = inputs {
-            for (i, key) in inputs.iter().enumerate() {
-                let key = *key;
+    // the firmware packs `keyevent_type_t` into a byte, so `type_` also holds `pressed` above it
+    let event_type = record.event.type_ & 0xff;
+    if matches!(
+        event_type,
+        keyevent_type_t::ENCODER_CW_EVENT | keyevent_type_t::ENCODER_CCW_EVENT
+    ) {
+        // a turn from the encoder map: the layer's keycode is sent as usual, the UI gets KC_NO
+        if keycode.raw() != KC_NO as u16 {
+            return true;
+        }
+        if is_pressed(record) {
+            let clockwise = event_type == keyevent_type_t::ENCODER_CW_EVENT;
+            encoder_turned(record.event.key.col, clockwise);
+        }
+        return false;
+    }
+    if is_pressed(record) {
+        EVENTS.send(Event::KeyDown(keycode));
+    } else {
+        EVENTS.send(Event::KeyUp(keycode));
+    }
 
-                unsafe {
-                    defer_exec((i as u32 * 5) + 1, Some(macro_callback), key as *mut c_void);
-                }
-            }
-        };
+    if is_pressed(record)
+        && matches!(keycode, LEFT_ENCODER_CLICK | RIGHT_ENCODER_CLICK)
+        && cursor::encoder_click()
+    {
+        return false;
     }
 
     with(|cs| {
@@ -58,7 +81,7 @@ pub fn process_record_user(keycode: u16, record: *const KeyRecord) -> bool {
             return false;
         };
 
-        if record.event.type_ != 257 {
+        if !is_pressed(record) {
             input_handler.up(keycode);
             return true;
         }
@@ -66,8 +89,8 @@ pub fn process_record_user(keycode: u16, record: *const KeyRecord) -> bool {
         input_handler.down(keycode);
 
         let event = match keycode {
-            KC_F20 => InputEvent::EncoderClick(0),
-            KC_F21 => InputEvent::EncoderClick(1),
+            LEFT_ENCODER_CLICK => InputEvent::EncoderClick(0),
+            RIGHT_ENCODER_CLICK => InputEvent::EncoderClick(1),
             _ => InputEvent::KeyDown(keycode),
         };
 
@@ -81,11 +104,27 @@ pub fn process_record_user(keycode: u16, record: *const KeyRecord) -> bool {
     })
 }
 
+#[cfg(not(target_arch = "wasm32"))]
+#[qmk_callback((report_mouse_t) -> report_mouse_t)]
+fn pointing_device_task_user(
+    report: qmk::pointing::RawMouseReport,
+) -> qmk::pointing::RawMouseReport {
+    Pointing::task(report)
+}
+
+#[qmk_callback((os_variant_t) -> bool)]
+fn process_detected_host_os_user(os: OsVariant::Type) -> bool {
+    let os = HostOs::process(os);
+    info!("Host OS detected: {}", os.name());
+    true
+}
+
 #[cfg(target_arch = "wasm32")]
 #[wasm_bindgen]
 pub fn process_record_wasm(keycode: u16, time: u16, pressed: bool) -> bool {
     use qmk::sys::{keyevent_t, keypos_t, tap_t};
 
+    Keyboard::simulate_activity();
     let record = KeyRecord {
         tap: tap_t {
             _bitfield_1: Default::default(),
@@ -101,3 +140,43 @@ pub fn process_record_wasm(keycode: u16, time: u16, pressed: bool) -> bool {
 
     process_record_user(keycode, &record as *const KeyRecord)
 }
+
+#[cfg(target_arch = "wasm32")]
+#[wasm_bindgen]
+pub fn advance_timer_wasm(elapsed_ms: u32) {
+    Timer::advance(elapsed_ms);
+}
+
+#[cfg(target_arch = "wasm32")]
+#[wasm_bindgen]
+pub fn set_led_state_wasm(raw: u8) {
+    use qmk::keyboard::LedState;
+
+    Keyboard::simulate_led_state(LedState::from_bits_retain(raw));
+}
+
+#[cfg(target_arch = "wasm32")]
+#[wasm_bindgen]
+pub fn set_host_os_wasm(raw: OsVariant::Type) {
+    process_detected_host_os_user(raw);
+}
+
+#[cfg(target_arch = "wasm32")]
+#[wasm_bindgen]
+pub fn set_layer_state_wasm(state: u32) {
+    Keyboard::simulate_layer_state(state);
+}
+
+#[cfg(target_arch = "wasm32")]
+#[wasm_bindgen]
+pub fn pointing_report_wasm(x: i16, y: i16, buttons: u8) {
+    use qmk::pointing::{MouseButtons, MouseReport};
+
+    Keyboard::simulate_activity();
+    Pointing::process(MouseReport {
+        x,
+        y,
+        buttons: MouseButtons::from_bits_retain(buttons),
+        ..Default::default()
+    });
+}
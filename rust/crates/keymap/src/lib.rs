@@ -10,6 +10,7 @@ extern crate std;
 mod animation;
 mod cat;
 mod config;
+mod cursor;
 mod heap;
 mod image;
 mod init;
@@ -17,7 +18,9 @@ mod input;
 mod keymap;
 mod page;
 mod pages;
+mod power;
 mod random;
+mod rgb;
 mod screen;
 mod state;
 
@@ -1,6 +1,7 @@
 use alloc::{boxed::Box, vec::Vec};
 use critical_section::CriticalSection;
 use qmk::framebuffer::Framebuffer;
+use qmk::time::{Duration, Instant};
 
 use crate::state::InputHandler;
 
@@ -17,6 +18,11 @@ pub struct RenderInfo<'a> {
     pub framebuffer: &'a mut Framebuffer,
     pub cs: CriticalSection<'a>,
     pub tick: u32,
+    /// When this frame started.
+    pub now: Instant,
+    /// Time since the previous frame, for anything that should move at the same speed regardless
+    /// of frame rate.
+    pub delta: Duration,
     pub input: &'a mut InputHandler,
     pub actions: &'a mut Vec<Box<dyn FnOnce()>>,
 }
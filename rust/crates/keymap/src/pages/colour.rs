@@ -9,7 +9,7 @@ use alloc::boxed::Box;
 #[cfg(not(test))]
 #[cfg(not(target_arch = "wasm32"))]
 use micromath::F32Ext;
-use qmk::rgb::RGBLight;
+use qmk::rgb::{Hsv, RGBLight};
 
 fn map_color(value: u8) -> u8 {
     let fraction = value as f32 / 255.0;
@@ -19,17 +19,13 @@ fn map_color(value: u8) -> u8 {
 
 #[derive(Default)]
 pub struct ColourPage {
-    hue: u8,
-    sat: u8,
-    val: u8,
+    hsv: Hsv,
 }
 
 impl Page for ColourPage {
     fn init(&mut self, renderer: &mut RenderInfo) {
         let settings = SETTINGS.borrow_ref(renderer.cs);
-        self.hue = settings.hsv.0[0];
-        self.sat = settings.hsv.0[1];
-        self.val = settings.hsv.0[2];
+        self.hsv = settings.hsv;
     }
 
     fn render(&mut self, renderer: &mut RenderInfo) -> Option<Box<dyn Page>> {
@@ -38,10 +34,8 @@ impl Page for ColourPage {
                 InputEvent::EncoderClick(i) => {
                     if i == 0 {
                         let mut settings = SETTINGS.borrow_ref_mut(renderer.cs);
-                        settings.hsv.0[0] = self.hue;
-                        settings.hsv.0[1] = self.sat;
-                        settings.hsv.0[2] = self.val;
-                        settings.save();
+                        settings.hsv = self.hsv;
+                        settings.save_later();
                         return Some(Box::new(HomePage::default()));
                     }
                 }
@@ -50,20 +44,20 @@ impl Page for ColourPage {
                     if i == 0 {
                         if renderer.input.right_encoder_down() {
                             if clockwise {
-                                self.val = self.val.saturating_add(4);
+                                self.hsv.v = self.hsv.v.saturating_add(4);
                             } else {
-                                self.val = self.val.saturating_sub(4);
+                                self.hsv.v = self.hsv.v.saturating_sub(4);
                             }
                         } else if clockwise {
-                            self.hue = self.hue.saturating_add(4);
+                            self.hsv.h = self.hsv.h.saturating_add(4);
                         } else {
-                            self.hue = self.hue.saturating_sub(4);
+                            self.hsv.h = self.hsv.h.saturating_sub(4);
                         }
                     } else if i == 1 {
                         if clockwise {
-                            self.sat = self.sat.saturating_add(4);
+                            self.hsv.s = self.hsv.s.saturating_add(4);
                         } else {
-                            self.sat = self.sat.saturating_sub(4);
+                            self.hsv.s = self.hsv.s.saturating_sub(4);
                         }
                     }
                 }
@@ -78,28 +72,27 @@ impl Page for ColourPage {
         renderer.framebuffer.draw_image(31, 20, &COLOUR_GRADIENT);
         renderer.framebuffer.draw_image(47, 20, &COLOUR_GRADIENT);
 
-        let hue_pos = map_color(self.hue);
+        let hue_pos = map_color(self.hsv.h);
         renderer.framebuffer.draw_image(9, hue_pos, &RIGHT_ARROW);
         renderer
             .framebuffer
             .draw_image(9 + 12, hue_pos, &LEFT_ARROW);
 
-        let sat_pos = map_color(self.sat);
+        let sat_pos = map_color(self.hsv.s);
         renderer.framebuffer.draw_image(26, sat_pos, &RIGHT_ARROW);
         renderer
             .framebuffer
             .draw_image(26 + 12, sat_pos, &LEFT_ARROW);
 
-        let val_pos = map_color(self.val);
+        let val_pos = map_color(self.hsv.v);
         renderer.framebuffer.draw_image(42, val_pos, &RIGHT_ARROW);
         renderer
             .framebuffer
             .draw_image(42 + 12, val_pos, &LEFT_ARROW);
 
-        let (hue, sat, val) = (self.hue, self.sat, self.val);
-
+        let hsv = self.hsv;
         renderer.actions.push(Box::new(move || {
-            RGBLight::set_hsv(hue, sat, val);
+            RGBLight::set_hsv(hsv);
         }));
 
         None
@@ -1,6 +1,14 @@
-use crate::{page::RenderInfo, state::InputEvent};
+use crate::{cursor, page::RenderInfo, state::InputEvent};
 use alloc::vec::Vec;
-use qmk::screen::Screen;
+use qmk::{
+    audio::{Audio, Song},
+    rect::Rect,
+    screen::Screen,
+    song,
+};
+
+// played when an option is picked
+static CONFIRM: Song = song!("E6/s A6/e");
 
 pub struct ListConfig {
     pub x: u8,
@@ -61,6 +69,10 @@ impl SelectableList {
                         should_return = true;
                     }
                 }
+                InputEvent::Click(tag) if (*tag as usize) < options.len() => {
+                    self.selected = *tag as usize;
+                    should_return = true;
+                }
                 _ => {}
             }
         }
@@ -68,6 +80,15 @@ impl SelectableList {
             let gap = self.config.item_height + self.config.gap;
             let y = self.config.y + (i as u8 * gap);
             let hovered = i == self.selected;
+            cursor::tag(
+                Rect {
+                    x: self.config.x as i16,
+                    y: y as i16,
+                    width: self.config.item_width as i16,
+                    height: self.config.item_height as i16,
+                },
+                i as u8,
+            );
             if hovered {
                 renderer.framebuffer.fill_rect(
                     self.config.x,
@@ -83,6 +104,7 @@ impl SelectableList {
         }
 
         if should_return {
+            Audio::play_song(CONFIRM);
             Some(self.selected)
         } else {
             None
@@ -1,6 +1,6 @@
-use super::{BootPage, HomePage, components::SelectableList};
+use super::{BootPage, HomePage, LogPage, components::SelectableList};
 use crate::{
-    call_option, define_options,
+    call_option, cursor, define_options,
     page::{Page, RenderInfo},
     screen::{disable_marquee, marquee},
 };
@@ -11,6 +11,11 @@ define_options! {
         Some(HomePage::default())
     },
     "USB Boot", boot => |_| Some(BootPage::default()),
+    "Log", log => |_| Some(LogPage::default()),
+    "Cursor", cursor => |_| {
+        cursor::show();
+        None::<BootPage>
+    },
     "Marq On", marquee_on => |_| {
         marquee("Marquee text");
         None::<BootPage>
@@ -1,22 +1,21 @@
-use core::sync::atomic::Ordering;
-
 use alloc::format;
 use alloc::string::String;
 use include_image::include_image;
 
 use crate::page::{Page, RenderInfo};
-use crate::screen::TICK;
 use crate::state::InputEvent;
 use alloc::boxed::Box;
 use alloc::collections::VecDeque;
 use qmk::framebuffer::{CHAR_HEIGHT, CHAR_WIDTH};
+use qmk::os_detection::HostOs;
 use qmk::screen::Screen;
+use qmk::time::{self, Duration, Instant};
 
 use super::HomePage;
 
-const WINDOW_TICKS: u32 = 20 * 2;
+const WPM_WINDOW: Duration = Duration::from_secs(2);
 const WPM_HISTORY_SIZE: usize = 20;
-const IDLE_TICKS: u32 = 10;
+const IDLE_TIME: Duration = Duration::from_millis(500);
 
 #[derive(Default, PartialEq, Eq)]
 enum BongoCatImage {
@@ -29,15 +28,14 @@ enum BongoCatImage {
 #[derive(Default)]
 pub struct BongoCat {
     unprocessed_key: bool,
-    tick: u32,
     image: BongoCatImage,
-    last_key_tick: u32,
+    last_key: Instant,
 }
 
 impl BongoCat {
-    fn key_press(&mut self) {
+    fn key_press(&mut self, now: Instant) {
         self.unprocessed_key = true;
-        self.last_key_tick = self.tick;
+        self.last_key = now;
     }
 
     fn draw(&mut self, renderer: &mut RenderInfo) {
@@ -53,7 +51,7 @@ impl BongoCat {
             };
 
             self.unprocessed_key = false;
-        } else if self.tick - self.last_key_tick > IDLE_TICKS {
+        } else if renderer.now - self.last_key > IDLE_TIME {
             self.image = BongoCatImage::Idle;
         }
 
@@ -67,15 +65,12 @@ impl BongoCat {
         let y = 36;
 
         renderer.framebuffer.draw_image(x, y, image);
-
-        self.tick = self.tick.wrapping_add(1);
     }
 }
 
 pub struct InfoPage {
-    key_times: VecDeque<u32>,
+    key_times: VecDeque<Instant>,
     wpm_history: [f32; WPM_HISTORY_SIZE],
-    tick: u32,
     bongo_cat: BongoCat,
 }
 
@@ -84,7 +79,6 @@ impl Default for InfoPage {
         Self {
             key_times: VecDeque::new(),
             wpm_history: [0.0; WPM_HISTORY_SIZE],
-            tick: 0,
             bongo_cat: BongoCat::default(),
         }
     }
@@ -101,12 +95,12 @@ impl Page for InfoPage {
                 return Some(Box::new(HomePage::default()));
             };
 
-            self.bongo_cat.key_press();
-            self.key_times.push_back(self.tick);
+            self.bongo_cat.key_press(renderer.now);
+            self.key_times.push_back(renderer.now);
         }
 
         while let Some(&time) = self.key_times.front() {
-            if self.tick.saturating_sub(time) > WINDOW_TICKS {
+            if renderer.now - time > WPM_WINDOW {
                 self.key_times.pop_front();
             } else {
                 break;
@@ -116,7 +110,7 @@ impl Page for InfoPage {
         let keys_in_window = self.key_times.len();
         let wpm = if keys_in_window > 0 {
             let num_words = keys_in_window as f32 / 5.0;
-            let extrapolation_multiplier = 60.0 / (WINDOW_TICKS as f32 / 20.0);
+            let extrapolation_multiplier = 60.0 / WPM_WINDOW.as_secs_f32();
             num_words * extrapolation_multiplier
         } else {
             0.0
@@ -135,7 +129,13 @@ impl Page for InfoPage {
         self.draw_graph(renderer);
         self.bongo_cat.draw(renderer);
 
-        self.tick = self.tick.wrapping_add(1);
+        // below the graph's axis
+        renderer.framebuffer.draw_text_centered(
+            32,
+            Screen::OLED_DISPLAY_HEIGHT as u8 - CHAR_HEIGHT as u8 - 1,
+            HostOs::detected().name(),
+            false,
+        );
 
         None
     }
@@ -220,8 +220,7 @@ impl InfoPage {
     }
 
     fn uptime(&self) -> String {
-        let uptime_ticks = TICK.load(Ordering::SeqCst);
-        let uptime_seconds = uptime_ticks / 20;
+        let uptime_seconds = time::uptime().as_secs() as u32;
         if uptime_seconds < 60 {
             pluralize(uptime_seconds, "sec")
         } else if uptime_seconds < 60 * 60 {
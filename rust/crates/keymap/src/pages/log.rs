@@ -0,0 +1,91 @@
+use super::DebugPage;
+use crate::{
+    page::{Page, RenderInfo},
+    state::InputEvent,
+};
+use alloc::{boxed::Box, string::String, vec::Vec};
+use qmk::{
+    framebuffer::{CHAR_HEIGHT, CHAR_WIDTH},
+    logging::{Level, Logger},
+    screen::Screen,
+};
+
+const TOP: usize = 20;
+const VISIBLE_ROWS: usize = (Screen::OLED_DISPLAY_HEIGHT - TOP) / CHAR_HEIGHT;
+// one column goes to the level letter
+const TEXT_COLUMNS: usize = Screen::OLED_DISPLAY_WIDTH / CHAR_WIDTH - 1;
+
+/// Scrolls through the in-RAM log, newest at the bottom. The left encoder scrolls and any
+/// encoder click goes back. With `deferred_log` the keyboard keeps no lines, so this only points
+/// at the console.
+#[derive(Default)]
+pub struct LogPage {
+    // rows scrolled back from the newest line
+    scroll: usize,
+}
+
+impl Page for LogPage {
+    fn render(&mut self, renderer: &mut RenderInfo) -> Option<Box<dyn Page>> {
+        while let Some(event) = renderer.input.poll() {
+            match event {
+                InputEvent::EncoderClick(_) => return Some(Box::new(DebugPage::default())),
+                InputEvent::EncoderScroll(0, clockwise) => {
+                    if clockwise {
+                        self.scroll = self.scroll.saturating_sub(1);
+                    } else {
+                        self.scroll += 1;
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        renderer.framebuffer.draw_text_centered(32, 8, "Log", false);
+
+        let rows = wrap_records();
+        if rows.is_empty() {
+            // deferred lines only go to the console
+            let text = if cfg!(all(feature = "deferred_log", not(target_arch = "wasm32"))) {
+                "On console"
+            } else {
+                "Empty"
+            };
+            renderer
+                .framebuffer
+                .draw_text_centered(32, TOP + CHAR_HEIGHT, text, false);
+            return None;
+        }
+
+        self.scroll = self.scroll.min(rows.len().saturating_sub(VISIBLE_ROWS));
+        let end = rows.len() - self.scroll;
+        let start = end.saturating_sub(VISIBLE_ROWS);
+
+        for (i, (level, text)) in rows[start..end].iter().enumerate() {
+            let y = (TOP + i * CHAR_HEIGHT) as u8;
+            if let Some(level) = level {
+                renderer
+                    .framebuffer
+                    .draw_char(0, y, level.as_char(), true, false);
+            }
+            renderer.framebuffer.draw_text(CHAR_WIDTH, y, text, false);
+        }
+
+        None
+    }
+}
+
+// every record split into screen-width rows, with the level on the first row of each
+fn wrap_records() -> Vec<(Option<Level>, String)> {
+    let mut rows = Vec::new();
+    for record in Logger::records() {
+        let chars = record.message.chars().collect::<Vec<_>>();
+        if chars.is_empty() {
+            rows.push((Some(record.level), String::new()));
+        }
+        for (i, chunk) in chars.chunks(TEXT_COLUMNS).enumerate() {
+            let level = (i == 0).then_some(record.level);
+            rows.push((level, chunk.iter().collect()));
+        }
+    }
+    rows
+}
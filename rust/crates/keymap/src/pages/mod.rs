@@ -5,6 +5,7 @@ mod components;
 mod debug;
 mod home;
 mod info;
+mod log;
 mod mode_7;
 mod settings;
 mod startup;
@@ -16,6 +17,7 @@ pub use colour::*;
 pub use debug::*;
 pub use home::*;
 pub use info::*;
+pub use log::*;
 pub use mode_7::*;
 pub use settings::*;
 pub use startup::*;
@@ -20,7 +20,7 @@ define_options! {
         with(|cs| {
             let mut settings = SETTINGS.borrow_ref_mut(cs);
             settings.startup_skip = false;
-            settings.save();
+            settings.save_later();
         });
         None
     },
@@ -28,7 +28,7 @@ define_options! {
         with(|cs| {
             let mut settings = SETTINGS.borrow_ref_mut(cs);
             settings.startup_skip = true;
-            settings.save();
+            settings.save_later();
         });
         None
     },
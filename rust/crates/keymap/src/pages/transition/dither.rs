@@ -38,6 +38,8 @@ impl TransitionHandler for DitherTransition {
             framebuffer: &mut to_framebuffer,
             input: renderer.input,
             tick: renderer.tick,
+            now: renderer.now,
+            delta: renderer.delta,
             actions: renderer.actions,
         };
 
@@ -112,6 +112,8 @@ impl TransitionHandler for DoomTransition {
             framebuffer: &mut from_framebuffer,
             cs: renderer.cs,
             tick: renderer.tick,
+            now: renderer.now,
+            delta: renderer.delta,
             input: &mut InputHandler::new(),
             actions: renderer.actions,
         };
@@ -31,6 +31,8 @@ impl TransitionHandler for SlideTransition {
             framebuffer: &mut from_framebuffer,
             cs: renderer.cs,
             tick: renderer.tick,
+            now: renderer.now,
+            delta: renderer.delta,
             input: renderer.input,
             actions: renderer.actions,
         };
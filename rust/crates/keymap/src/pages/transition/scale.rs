@@ -57,6 +57,8 @@ impl TransitionHandler for ScaleTransition {
                 framebuffer: &mut to_framebuffer,
                 input: renderer.input,
                 tick: renderer.tick,
+                now: renderer.now,
+                delta: renderer.delta,
                 actions: renderer.actions,
             };
 
@@ -0,0 +1,111 @@
+use crate::config::{self, SETTINGS};
+use core::cell::Cell;
+use critical_section::{Mutex, with};
+use qmk::{
+    audio::Audio,
+    framebuffer::{CHAR_HEIGHT, CHAR_WIDTH, Framebuffer},
+    info,
+    keyboard::Keyboard,
+    qmk_callback,
+    rgb::{Hsv, RGBLight},
+    rgb_matrix::RgbMatrix,
+    screen::Screen,
+    time::{Duration, Instant},
+};
+
+// no keys, encoders or trackball for this long and the board goes idle
+const IDLE_AFTER: Duration = Duration::from_secs(60);
+// how much of their brightness the LEDs keep while idle
+const IDLE_BRIGHTNESS: u8 = 64;
+// the sleep screen's text moves this often, so it doesn't burn in
+const SLEEP_MOVE_EVERY: Duration = Duration::from_secs(4);
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum PowerState {
+    #[default]
+    Active,
+    /// Nothing has been touched for a while: the OLED shows the sleep screen and the LEDs dim.
+    Idle,
+    /// The host is asleep: the OLED and LEDs are off.
+    Suspended,
+}
+
+static STATE: Mutex<Cell<PowerState>> = Mutex::new(Cell::new(PowerState::Active));
+// when the host last woke the keyboard, until it's longer ago than `IDLE_AFTER`
+static WOKE: Mutex<Cell<Option<Instant>>> = Mutex::new(Cell::new(None));
+
+pub fn state() -> PowerState {
+    with(|cs| STATE.borrow(cs).get())
+}
+
+/// Moves between active and idle depending on how long the keyboard has gone unused. Called
+/// once per frame, the host's suspend and wake callbacks handle the rest.
+pub fn task() {
+    let state = state();
+    let mut unused = Keyboard::last_activity();
+    // waking up counts as activity, otherwise a long sleep would go straight to idle
+    if let Some(woke) = with(|cs| WOKE.borrow(cs).get()) {
+        let since_wake = woke.elapsed();
+        if since_wake >= IDLE_AFTER {
+            with(|cs| WOKE.borrow(cs).set(None));
+        }
+        unused = unused.min(since_wake);
+    }
+    match state {
+        PowerState::Active if unused >= IDLE_AFTER => enter(PowerState::Idle),
+        PowerState::Idle if unused < IDLE_AFTER => enter(PowerState::Active),
+        _ => {}
+    }
+}
+
+fn enter(next: PowerState) {
+    let previous = with(|cs| STATE.borrow(cs).replace(next));
+    if previous == next {
+        return;
+    }
+    info!("Power {:?} -> {:?}", previous, next);
+
+    let hsv = with(|cs| SETTINGS.borrow_ref(cs).hsv);
+    match next {
+        PowerState::Active => {
+            Screen::on();
+            RgbMatrix::set_brightness(u8::MAX);
+            RGBLight::set_hsv_noeeprom(hsv);
+        }
+        PowerState::Idle => {
+            config::flush();
+            RgbMatrix::set_brightness(IDLE_BRIGHTNESS);
+            RGBLight::set_hsv_noeeprom(hsv.scale(IDLE_BRIGHTNESS));
+        }
+        PowerState::Suspended => {
+            config::flush();
+            Audio::stop();
+            RgbMatrix::set_brightness(0);
+            RGBLight::set_hsv_noeeprom(Hsv::BLACK);
+            Screen::off();
+        }
+    }
+}
+
+/// Draws the idle screen, a "z z z" that hops somewhere new every few seconds.
+pub fn draw_sleep(framebuffer: &mut Framebuffer, now: Instant) {
+    const TEXT: &str = "z z z";
+    let step = (now.as_millis() / SLEEP_MOVE_EVERY.as_millis() as u32) as usize;
+    let max_x = Screen::OLED_DISPLAY_WIDTH - TEXT.len() * CHAR_WIDTH;
+    let max_y = Screen::OLED_DISPLAY_HEIGHT - CHAR_HEIGHT;
+    // a cheap scramble, it just has to look like it's wandering
+    let x = step.wrapping_mul(37) % max_x;
+    let y = step.wrapping_mul(71) % max_y;
+    framebuffer.draw_text(x as u8, y as u8, TEXT, false);
+}
+
+#[qmk_callback(() -> void)]
+fn suspend_power_down_user() {
+    enter(PowerState::Suspended);
+}
+
+#[qmk_callback(() -> void)]
+fn suspend_wakeup_init_user() {
+    with(|cs| WOKE.borrow(cs).set(Some(Instant::now())));
+    enter(PowerState::Active);
+}
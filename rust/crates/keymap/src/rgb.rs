@@ -0,0 +1,91 @@
+#[cfg(any(target_arch = "wasm32", feature = "rgb_matrix"))]
+use crate::config::SETTINGS;
+#[cfg(target_arch = "wasm32")]
+use alloc::vec::Vec;
+#[cfg(any(target_arch = "wasm32", feature = "rgb_matrix"))]
+use critical_section::with;
+use qmk::{
+    indicator::{Indicator, Indicators, When},
+    keyboard::LedState,
+    qmk_callback,
+    rgb::Rgb,
+    rgb_matrix::RgbMatrix,
+    time::Duration,
+};
+#[cfg(any(target_arch = "wasm32", feature = "rgb_matrix"))]
+use qmk::{
+    led_config,
+    rgb_matrix::{LedBuffer, LedConfig, RgbEffect},
+    time::Instant,
+};
+
+// the sofle's RGB matrix isn't enabled in rules.mk, so the effects only run in the browser unless
+// the `rgb_matrix` feature turns it on
+#[cfg(any(target_arch = "wasm32", feature = "rgb_matrix"))]
+static LED_CONFIG: LedConfig = led_config!("sofle/rev1");
+
+static INDICATORS: [Indicator; 2] = [
+    // game mode
+    Indicator::new(When::Layer(2), Rgb::RED).leds(0..6),
+    Indicator::new(When::Lock(LedState::CAPS_LOCK), Rgb::WHITE).pulse(Duration::from_millis(1000)),
+];
+
+/// A band of the user's colour sweeping left to right across the board.
+#[cfg(any(target_arch = "wasm32", feature = "rgb_matrix"))]
+struct Wave;
+
+#[cfg(any(target_arch = "wasm32", feature = "rgb_matrix"))]
+impl RgbEffect for Wave {
+    fn tick(&mut self, leds: &mut LedBuffer, time: Instant) {
+        let base = with(|cs| SETTINGS.borrow_ref(cs).hsv);
+        let offset = time.as_millis() / 8;
+        for (led, color) in leds.iter_mut() {
+            let phase = (offset + led.x as u32) % 256;
+            let value = if phase < 128 {
+                phase * 2
+            } else {
+                (255 - phase) * 2
+            } as u8;
+            *color = base.scale(value).into();
+        }
+    }
+}
+
+pub fn init() {
+    // without this `RgbMatrix::task` has no buffer and does nothing
+    #[cfg(any(target_arch = "wasm32", feature = "rgb_matrix"))]
+    {
+        RgbMatrix::init(&LED_CONFIG);
+        RgbMatrix::set_effect(Wave);
+    }
+    Indicators::set(&INDICATORS);
+}
+
+#[qmk_callback(() -> bool)]
+fn rgb_matrix_indicators_user() -> bool {
+    RgbMatrix::task();
+    true
+}
+
+/// Runs one RGB matrix frame and returns every LED as `[r, g, b, r, g, b, ..]`.
+#[cfg(target_arch = "wasm32")]
+#[wasm_bindgen::prelude::wasm_bindgen]
+pub fn rgb_matrix_task_wasm() -> Vec<u8> {
+    rgb_matrix_indicators_user();
+    RgbMatrix::frame()
+        .into_iter()
+        .flat_map(|color| [color.r, color.g, color.b])
+        .collect()
+}
+
+/// The LED layout for drawing the buffer, as `[x, y, flags, x, y, flags, ..]` on QMK's 224x64
+/// grid.
+#[cfg(target_arch = "wasm32")]
+#[wasm_bindgen::prelude::wasm_bindgen]
+pub fn rgb_matrix_layout_wasm() -> Vec<u8> {
+    LED_CONFIG
+        .leds
+        .iter()
+        .flat_map(|led| [led.x, led.y, led.flags.bits()])
+        .collect()
+}
@@ -7,11 +7,14 @@ use crate::{
     animation::{AngularFrequency, DampingRatio, DeltaTime, Spring, fps},
     cat::Cat,
     config::PageTransition,
+    cursor,
+    keymap::Layer,
     page::{Page as _, RenderInfo},
     pages::{
         Actions, ClockPage, DitherTransition, DoomTransition, NoneTransition, ScaleTransition,
         SlideTransition, TRANSITION_TYPE, TransitionHandler,
     },
+    power::{self, PowerState},
     state::{INPUT_HANDLER, PAGE},
 };
 use alloc::{boxed::Box, vec::Vec};
@@ -23,10 +26,13 @@ use qmk::{
     framebuffer::{Affine2, CHAR_WIDTH, FixedNumber, Framebuffer, FramebufferTransparency},
     keyboard::Keyboard,
     screen::Screen,
+    time::{Duration, Instant},
 };
 use qmk::{framebuffer::CHAR_HEIGHT, qmk_callback};
 
 pub static TICK: AtomicU32 = AtomicU32::new(0);
+/// When the current frame started and how long it had been since the previous one.
+static FRAME_TIME: Mutex<RefCell<Option<(Instant, Duration)>>> = Mutex::new(RefCell::new(None));
 pub static TRANSITION: Mutex<RefCell<Option<Box<dyn TransitionHandler>>>> =
     Mutex::new(RefCell::new(None));
 pub static IS_TRANSITIONING: AtomicBool = AtomicBool::new(false);
@@ -86,6 +92,19 @@ static CAT: Mutex<RefCell<Cat>> = Mutex::new(RefCell::new(Cat::new()));
 #[cfg(not(target_arch = "wasm32"))]
 #[qmk_callback(() -> bool)]
 fn oled_task_user() -> bool {
+    power::task();
+    match power::state() {
+        PowerState::Active => {}
+        PowerState::Idle => {
+            let mut fb = Framebuffer::default();
+            power::draw_sleep(&mut fb, Instant::now());
+            fb.render();
+            return false;
+        }
+        // drawing anything would turn the screen back on
+        PowerState::Suspended => return false,
+    }
+
     let mut handler = with(|cs| INPUT_HANDLER.borrow_ref(cs).clone());
 
     let actions = with(|cs| {
@@ -95,10 +114,13 @@ fn oled_task_user() -> bool {
             render_left()
         };
 
+        let (now, delta) = frame_time(cs);
         let mut info = RenderInfo {
             framebuffer: &mut fb,
             cs,
             tick: TICK.load(Ordering::SeqCst),
+            now,
+            delta,
             input: &mut handler,
             actions: &mut alloc::vec![],
         };
@@ -120,6 +142,16 @@ fn oled_task_user() -> bool {
 #[cfg(target_arch = "wasm32")]
 #[wasm_bindgen::prelude::wasm_bindgen]
 pub fn oled_task_user_wasm(canvas: web_sys::HtmlCanvasElement) {
+    power::task();
+    if power::state() != PowerState::Active {
+        let mut fb = Framebuffer::default();
+        if Screen::is_on() {
+            power::draw_sleep(&mut fb, Instant::now());
+        }
+        fb.render(canvas);
+        return;
+    }
+
     let mut handler = with(|cs| INPUT_HANDLER.borrow_ref(cs).clone());
 
     let (actions, mut fb) = if Keyboard::is_right() {
@@ -129,10 +161,13 @@ pub fn oled_task_user_wasm(canvas: web_sys::HtmlCanvasElement) {
     };
 
     with(|cs| {
+        let (now, delta) = frame_time(cs);
         let mut info = RenderInfo {
             framebuffer: &mut fb,
             cs,
             tick: TICK.load(Ordering::SeqCst),
+            now,
+            delta,
             input: &mut handler,
             actions: &mut alloc::vec![],
         };
@@ -147,10 +182,29 @@ pub fn oled_task_user_wasm(canvas: web_sys::HtmlCanvasElement) {
     }
 }
 
-fn render_left() -> (Actions, Framebuffer) {
+/// Starts a new frame, returning the tick it should be drawn with.
+fn advance_frame() -> u32 {
+    let now = Instant::now();
+    with(|cs| {
+        let mut frame_time = FRAME_TIME.borrow_ref_mut(cs);
+        let delta = frame_time.map_or(Duration::ZERO, |(last, _)| now - last);
+        *frame_time = Some((now, delta));
+    });
+
     let tick = TICK.load(Ordering::SeqCst);
     TICK.store(tick.wrapping_add(1), Ordering::SeqCst);
-    let is_game_mode = Keyboard::layer_state_is(2);
+    tick
+}
+
+fn frame_time(cs: CriticalSection) -> (Instant, Duration) {
+    FRAME_TIME
+        .borrow_ref(cs)
+        .unwrap_or_else(|| (Instant::now(), Duration::ZERO))
+}
+
+fn render_left() -> (Actions, Framebuffer) {
+    let tick = advance_frame();
+    let is_game_mode = Keyboard::layer_state_is(Layer::Game.index());
     with(|cs| {
         let mut framebuffer = Framebuffer::default();
         let (actions, should_draw_border) = draw_screen(&mut framebuffer, cs);
@@ -160,13 +214,14 @@ fn render_left() -> (Actions, Framebuffer) {
             disable_marquee("Game layer activated");
         }
         let mut spring = MARQUEE_HEIGHT_SPRING.borrow_ref_mut(cs);
-        spring.update();
+        spring.update_by(frame_time(cs).1);
         let current = spring.current();
         drop(spring);
         draw_marquee(&mut framebuffer, tick, current);
         if should_draw_border {
             draw_border(&mut framebuffer)
         };
+        cursor::draw(&mut framebuffer);
         (actions, framebuffer)
     })
 }
@@ -260,7 +315,9 @@ pub fn draw_marquee(framebuffer: &mut Framebuffer, tick: u32, marquee_spring_y:
 }
 
 fn render_right() -> (Actions, Framebuffer) {
+    let tick = advance_frame();
     with(|cs| {
+        let (now, delta) = frame_time(cs);
         if RIGHT_HAND_PAGE.borrow_ref(cs).is_none() {
             let mut page = RIGHT_HAND_PAGE.borrow_ref_mut(cs);
             *page = Some(ClockPage);
@@ -273,18 +330,15 @@ fn render_right() -> (Actions, Framebuffer) {
                 page.render(&mut RenderInfo {
                     framebuffer: &mut framebuffer,
                     cs,
-                    tick: TICK.load(Ordering::SeqCst),
+                    tick,
+                    now,
+                    delta,
                     input: &mut input_handler,
                     actions: &mut actions,
                 });
             };
         }
 
-        TICK.store(
-            TICK.load(Ordering::SeqCst).wrapping_add(1),
-            Ordering::SeqCst,
-        );
-
         draw_border(&mut framebuffer);
 
         (actions, framebuffer)
@@ -365,10 +419,13 @@ fn draw_screen(
         return (alloc::vec![], false);
     };
     let mut actions = alloc::vec![];
+    let (now, delta) = frame_time(cs);
     let mut info = RenderInfo {
         framebuffer,
         cs,
         tick,
+        now,
+        delta,
         input: &mut input,
         actions: &mut actions,
     };
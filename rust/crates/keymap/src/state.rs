@@ -1,6 +1,6 @@
 #![allow(dead_code)]
 
-use crate::keymap::CS_RESET;
+use crate::keymap::Layer;
 use crate::page::Page;
 use crate::pages::StartupPage;
 use alloc::vec;
@@ -11,12 +11,15 @@ use core::prelude::rust_2024::*;
 use critical_section::Mutex;
 use once_cell::sync::Lazy;
 use qmk::keyboard::Keyboard;
-use qmk::keys::{KC_C, KC_DOWN, KC_ENTER, KC_F20, KC_F21};
+use qmk::keys::{KC_C, KC_DOWN, KC_ENTER, KC_F20, KC_F21, Keycode};
+
+pub const LEFT_ENCODER_CLICK: Keycode = Keycode::new(KC_F20 as u16);
+pub const RIGHT_ENCODER_CLICK: Keycode = Keycode::new(KC_F21 as u16);
 
 #[derive(Clone)]
 pub struct InputHandler {
     events: Vec<InputEvent>,
-    keys: Vec<u32>,
+    keys: Vec<Keycode>,
     left_encoder_down: bool,
     right_encoder_down: bool,
 }
@@ -42,23 +45,23 @@ impl InputHandler {
     }
 
     #[inline(always)]
-    pub fn is_key_down(&self, key: u32) -> bool {
+    pub fn is_key_down(&self, key: Keycode) -> bool {
         self.keys.contains(&key)
     }
 
     #[inline(always)]
-    pub fn is_key_up(&self, key: u32) -> bool {
+    pub fn is_key_up(&self, key: Keycode) -> bool {
         !self.is_key_down(key)
     }
 
     #[inline(always)]
-    pub fn keys(&self) -> &[u32] {
+    pub fn keys(&self) -> &[Keycode] {
         &self.keys
     }
 
     #[inline(always)]
     pub fn handle_event(&mut self, event: InputEvent) {
-        let is_game_mode = Keyboard::layer_state_is(2);
+        let is_game_mode = Keyboard::layer_state_is(Layer::Game.index());
         if !is_game_mode {
             self.events.push(event);
         }
@@ -77,20 +80,20 @@ impl InputHandler {
         core::mem::take(&mut self.events)
     }
 
-    pub fn down(&mut self, key: u32) {
-        if key == KC_F20 {
+    pub fn down(&mut self, key: Keycode) {
+        if key == LEFT_ENCODER_CLICK {
             self.left_encoder_down = true;
-        } else if key == KC_F21 {
+        } else if key == RIGHT_ENCODER_CLICK {
             self.right_encoder_down = true;
-        } else if !self.keys.contains(&key) && !Keyboard::layer_state_is(2) {
+        } else if !self.keys.contains(&key) && !Keyboard::layer_state_is(Layer::Game.index()) {
             // self.keys.push(key);
         }
     }
 
-    pub fn up(&mut self, key: u32) {
-        if key == KC_F20 {
+    pub fn up(&mut self, key: Keycode) {
+        if key == LEFT_ENCODER_CLICK {
             self.left_encoder_down = false;
-        } else if key == KC_F21 {
+        } else if key == RIGHT_ENCODER_CLICK {
             self.right_encoder_down = false;
         } else if let Some(index) = self.keys.iter().position(|&k| k == key) {
             self.keys.remove(index);
@@ -102,7 +105,9 @@ impl InputHandler {
 pub enum InputEvent {
     EncoderScroll(u8, bool),
     EncoderClick(u8),
-    KeyDown(u32),
+    KeyDown(Keycode),
+    /// The cursor clicked a region tagged with this, see `cursor::tag`.
+    Click(u8),
 }
 
 pub static INPUT_HANDLER: Lazy<Mutex<RefCell<InputHandler>>> =
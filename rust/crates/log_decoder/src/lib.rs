@@ -0,0 +1,596 @@
+use std::{collections::HashMap, fmt, fs, io, path::Path};
+
+// argument tags, mirroring `qmk::logging::Frame`
+const TAG_UNSIGNED: u8 = 1;
+const TAG_SIGNED: u8 = 2;
+const TAG_F32: u8 = 3;
+const TAG_F64: u8 = 4;
+const TAG_BOOL: u8 = 5;
+const TAG_CHAR: u8 = 6;
+const TAG_STR: u8 = 7;
+const TAG_FORMATTED: u8 = 8;
+
+#[derive(Debug)]
+pub enum Error {
+    Io(io::Error),
+    /// A line of the string table couldn't be read.
+    Table {
+        line: usize,
+        message: &'static str,
+    },
+    /// A frame was cut off or malformed, usually because capture started mid-frame.
+    Frame(&'static str),
+    /// The id isn't in the table, which usually means the table is from a different build.
+    UnknownId(u32),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::Io(error) => write!(f, "{}", error),
+            Error::Table { line, message } => write!(f, "string table line {}: {}", line, message),
+            Error::Frame(message) => write!(f, "bad frame: {}", message),
+            Error::UnknownId(id) => write!(f, "unknown format string id {:08x}", id),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+impl From<io::Error> for Error {
+    fn from(error: io::Error) -> Self {
+        Error::Io(error)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Level {
+    Error = 1,
+    Warn,
+    Info,
+    Debug,
+    Trace,
+}
+
+impl Level {
+    fn from_u8(value: u8) -> Option<Level> {
+        match value {
+            1 => Some(Level::Error),
+            2 => Some(Level::Warn),
+            3 => Some(Level::Info),
+            4 => Some(Level::Debug),
+            5 => Some(Level::Trace),
+            _ => None,
+        }
+    }
+
+    pub fn as_char(self) -> char {
+        match self {
+            Level::Error => 'E',
+            Level::Warn => 'W',
+            Level::Info => 'I',
+            Level::Debug => 'D',
+            Level::Trace => 'T',
+        }
+    }
+}
+
+/// One format string from the table written by `qmk_callback::write_log_table`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Entry {
+    pub format: String,
+    /// Where the string is logged from, as `file:line`.
+    pub locations: Vec<String>,
+}
+
+/// Format strings by id, as written next to the keyboard's build output.
+#[derive(Debug, Default)]
+pub struct Table {
+    entries: HashMap<u32, Entry>,
+}
+
+impl Table {
+    pub fn parse(text: &str) -> Result<Self, Error> {
+        let mut entries = HashMap::new();
+        for (index, line) in text.lines().enumerate() {
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let error = |message| Error::Table {
+                line: index + 1,
+                message,
+            };
+
+            let mut columns = line.split('\t');
+            let id = columns.next().ok_or_else(|| error("missing id"))?;
+            let id = u32::from_str_radix(id, 16).map_err(|_| error("id isn't hex"))?;
+            let format = columns
+                .next()
+                .ok_or_else(|| error("missing format string"))?;
+            let locations = columns
+                .next()
+                .unwrap_or_default()
+                .split(',')
+                .filter(|location| !location.is_empty())
+                .map(String::from)
+                .collect();
+
+            entries.insert(
+                id,
+                Entry {
+                    format: unescape(format),
+                    locations,
+                },
+            );
+        }
+        Ok(Self { entries })
+    }
+
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, Error> {
+        Self::parse(&fs::read_to_string(path)?)
+    }
+
+    pub fn get(&self, id: u32) -> Option<&Entry> {
+        self.entries.get(&id)
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+}
+
+fn unescape(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut chars = text.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            out.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('t') => out.push('\t'),
+            Some('n') => out.push('\n'),
+            Some(other) => out.push(other),
+            None => out.push('\\'),
+        }
+    }
+    out
+}
+
+/// A decoded argument.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Value {
+    Unsigned(u64),
+    Signed(i64),
+    F32(f32),
+    F64(f64),
+    Bool(bool),
+    Char(char),
+    Str(String),
+    /// Already formatted on the keyboard, shown as-is.
+    Formatted(String),
+}
+
+/// A decoded log line.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Line {
+    pub level: Level,
+    /// Milliseconds since the keyboard booted.
+    pub time: u32,
+    pub id: u32,
+    pub locations: Vec<String>,
+    pub text: String,
+}
+
+impl fmt::Display for Line {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{:>4}.{:03} {} ",
+            self.time / 1000,
+            self.time % 1000,
+            self.level.as_char()
+        )?;
+        if !self.locations.is_empty() {
+            write!(f, "{}: ", self.locations.join(","))?;
+        }
+        f.write_str(&self.text)
+    }
+}
+
+/// Splits a captured console stream into frames and decodes them.
+pub struct Decoder<'a> {
+    table: &'a Table,
+    pending: Vec<u8>,
+}
+
+impl<'a> Decoder<'a> {
+    pub fn new(table: &'a Table) -> Self {
+        Self {
+            table,
+            pending: Vec::new(),
+        }
+    }
+
+    /// Feeds more captured bytes, returning every frame they complete. Partial frames are kept
+    /// for the next call.
+    pub fn push(&mut self, bytes: &[u8]) -> Vec<Result<Line, Error>> {
+        let mut lines = Vec::new();
+        for &byte in bytes {
+            if byte != 0 {
+                self.pending.push(byte);
+                continue;
+            }
+            if self.pending.is_empty() {
+                continue;
+            }
+            lines.push(
+                cobs_decode(&self.pending).and_then(|frame| decode_frame(self.table, &frame)),
+            );
+            self.pending.clear();
+        }
+        lines
+    }
+}
+
+/// Undoes the COBS encoding of one frame, without its terminating zero.
+pub fn cobs_decode(data: &[u8]) -> Result<Vec<u8>, Error> {
+    let mut out = Vec::with_capacity(data.len());
+    let mut i = 0;
+    while i < data.len() {
+        let code = data[i] as usize;
+        if code == 0 {
+            return Err(Error::Frame("zero inside COBS data"));
+        }
+        let end = i + code;
+        if end > data.len() {
+            return Err(Error::Frame("COBS block runs past the end"));
+        }
+        out.extend_from_slice(&data[i + 1..end]);
+        i = end;
+        if code != 0xff && i < data.len() {
+            out.push(0);
+        }
+    }
+    Ok(out)
+}
+
+struct Reader<'a> {
+    bytes: &'a [u8],
+}
+
+impl Reader<'_> {
+    fn take(&mut self, count: usize) -> Result<&[u8], Error> {
+        if self.bytes.len() < count {
+            return Err(Error::Frame("frame ends too early"));
+        }
+        let (taken, rest) = self.bytes.split_at(count);
+        self.bytes = rest;
+        Ok(taken)
+    }
+
+    fn byte(&mut self) -> Result<u8, Error> {
+        Ok(self.take(1)?[0])
+    }
+
+    fn varint(&mut self) -> Result<u64, Error> {
+        let mut value = 0u64;
+        for shift in (0..64).step_by(7) {
+            let byte = self.byte()?;
+            value |= ((byte & 0x7f) as u64) << shift;
+            if byte & 0x80 == 0 {
+                return Ok(value);
+            }
+        }
+        Err(Error::Frame("varint too long"))
+    }
+
+    fn string(&mut self) -> Result<String, Error> {
+        let length = self.varint()? as usize;
+        let bytes = self.take(length)?;
+        String::from_utf8(bytes.to_vec()).map_err(|_| Error::Frame("string isn't UTF-8"))
+    }
+
+    fn value(&mut self) -> Result<Value, Error> {
+        Ok(match self.byte()? {
+            TAG_UNSIGNED => Value::Unsigned(self.varint()?),
+            TAG_SIGNED => {
+                let zigzag = self.varint()?;
+                Value::Signed((zigzag >> 1) as i64 ^ -((zigzag & 1) as i64))
+            }
+            TAG_F32 => Value::F32(f32::from_le_bytes(self.take(4)?.try_into().unwrap())),
+            TAG_F64 => Value::F64(f64::from_le_bytes(self.take(8)?.try_into().unwrap())),
+            TAG_BOOL => Value::Bool(self.byte()? != 0),
+            TAG_CHAR => {
+                Value::Char(char::from_u32(self.varint()? as u32).ok_or(Error::Frame("bad char"))?)
+            }
+            TAG_STR => Value::Str(self.string()?),
+            TAG_FORMATTED => Value::Formatted(self.string()?),
+            _ => return Err(Error::Frame("unknown argument tag")),
+        })
+    }
+}
+
+/// Decodes one frame, already COBS-decoded, into a line of text.
+pub fn decode_frame(table: &Table, frame: &[u8]) -> Result<Line, Error> {
+    let mut reader = Reader { bytes: frame };
+    let level = Level::from_u8(reader.byte()?).ok_or(Error::Frame("unknown level"))?;
+    let id = u32::from_le_bytes(reader.take(4)?.try_into().unwrap());
+    let time = reader.varint()? as u32;
+
+    let mut args = Vec::new();
+    while !reader.bytes.is_empty() {
+        args.push(reader.value()?);
+    }
+
+    let entry = table.get(id).ok_or(Error::UnknownId(id))?;
+    Ok(Line {
+        level,
+        time,
+        id,
+        locations: entry.locations.clone(),
+        text: format(&entry.format, &args),
+    })
+}
+
+#[derive(Default)]
+struct Spec {
+    fill: Option<char>,
+    align: Option<char>,
+    plus: bool,
+    alternate: bool,
+    zero: bool,
+    width: usize,
+    precision: Option<usize>,
+    kind: String,
+}
+
+impl Spec {
+    fn parse(spec: &str) -> Self {
+        let mut out = Spec::default();
+        let chars = spec.chars().collect::<Vec<_>>();
+        let mut i = 0;
+
+        let is_align = |c: &char| matches!(c, '<' | '^' | '>');
+        if chars.get(1).is_some_and(is_align) {
+            out.fill = Some(chars[0]);
+            out.align = Some(chars[1]);
+            i = 2;
+        } else if chars.first().is_some_and(is_align) {
+            out.align = Some(chars[0]);
+            i = 1;
+        }
+        if chars.get(i) == Some(&'+') {
+            out.plus = true;
+            i += 1;
+        }
+        if chars.get(i) == Some(&'#') {
+            out.alternate = true;
+            i += 1;
+        }
+        if chars.get(i) == Some(&'0') {
+            out.zero = true;
+            i += 1;
+        }
+        while let Some(digit) = chars.get(i).and_then(|c| c.to_digit(10)) {
+            out.width = out.width * 10 + digit as usize;
+            i += 1;
+        }
+        if chars.get(i) == Some(&'.') {
+            i += 1;
+            let mut precision = 0;
+            while let Some(digit) = chars.get(i).and_then(|c| c.to_digit(10)) {
+                precision = precision * 10 + digit as usize;
+                i += 1;
+            }
+            out.precision = Some(precision);
+        }
+        out.kind = chars[i..].iter().collect();
+        out
+    }
+
+    fn radix(
+        &self,
+        value: impl fmt::LowerHex + fmt::UpperHex + fmt::Binary + fmt::Octal,
+    ) -> Option<(String, &'static str)> {
+        Some(match self.kind.as_str() {
+            "x" => (format!("{:x}", value), "0x"),
+            "X" => (format!("{:X}", value), "0x"),
+            "b" => (format!("{:b}", value), "0b"),
+            "o" => (format!("{:o}", value), "0o"),
+            _ => return None,
+        })
+    }
+
+    // the value as text, split into sign/prefix and digits so zero padding can go between them
+    fn render(&self, value: &Value) -> (String, String, bool) {
+        let debug = self.kind == "?";
+        let number = |sign: &str, digits: String| {
+            let sign = if sign.is_empty() && self.plus {
+                "+"
+            } else {
+                sign
+            };
+            (sign.to_string(), digits, true)
+        };
+        let float = |text: String| match text.strip_prefix('-') {
+            Some(digits) => number("-", digits.to_string()),
+            None => number("", text),
+        };
+
+        match value {
+            Value::Unsigned(value) => match self.radix(*value) {
+                Some((digits, prefix)) => number(if self.alternate { prefix } else { "" }, digits),
+                None => number("", value.to_string()),
+            },
+            Value::Signed(value) => match self.radix(*value) {
+                Some((digits, prefix)) => number(if self.alternate { prefix } else { "" }, digits),
+                None if *value < 0 => number("-", value.unsigned_abs().to_string()),
+                None => number("", value.to_string()),
+            },
+            Value::F32(value) => float(match (self.precision, self.kind.as_str()) {
+                (Some(precision), _) => format!("{:.*}", precision, value),
+                (None, "?") => format!("{:?}", value),
+                (None, "e") => format!("{:e}", value),
+                (None, _) => value.to_string(),
+            }),
+            Value::F64(value) => float(match (self.precision, self.kind.as_str()) {
+                (Some(precision), _) => format!("{:.*}", precision, value),
+                (None, "?") => format!("{:?}", value),
+                (None, "e") => format!("{:e}", value),
+                (None, _) => value.to_string(),
+            }),
+            Value::Bool(value) => (String::new(), value.to_string(), false),
+            Value::Char(value) if debug => (String::new(), format!("{:?}", value), false),
+            Value::Char(value) => (String::new(), value.to_string(), false),
+            Value::Str(value) if debug => (String::new(), format!("{:?}", value), false),
+            Value::Str(value) => {
+                let text = match self.precision {
+                    Some(precision) => value.chars().take(precision).collect(),
+                    None => value.clone(),
+                };
+                (String::new(), text, false)
+            }
+            Value::Formatted(value) => (String::new(), value.clone(), false),
+        }
+    }
+
+    fn write(&self, out: &mut String, value: &Value) {
+        let (prefix, body, numeric) = self.render(value);
+        let length = prefix.chars().count() + body.chars().count();
+        let padding = self.width.saturating_sub(length);
+
+        if numeric && self.zero && self.align.is_none() {
+            out.push_str(&prefix);
+            out.extend(std::iter::repeat_n('0', padding));
+            out.push_str(&body);
+            return;
+        }
+
+        let fill = self.fill.unwrap_or(' ');
+        let (before, after) = match self.align.unwrap_or(if numeric { '>' } else { '<' }) {
+            '>' => (padding, 0),
+            '^' => (padding / 2, padding - padding / 2),
+            _ => (0, padding),
+        };
+        out.extend(std::iter::repeat_n(fill, before));
+        out.push_str(&prefix);
+        out.push_str(&body);
+        out.extend(std::iter::repeat_n(fill, after));
+    }
+}
+
+/// Fills in a Rust format string with decoded arguments. Supports positional arguments and the
+/// usual fill, alignment, sign, `#`, zero padding, width, precision and `?`/`x`/`X`/`b`/`o`/`e`
+/// specs; anything the keyboard couldn't send shows up as `{?}`.
+pub fn format(format: &str, args: &[Value]) -> String {
+    let mut out = String::with_capacity(format.len());
+    let mut next = 0;
+    let mut chars = format.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '{' if chars.peek() == Some(&'{') => {
+                chars.next();
+                out.push('{');
+            }
+            '}' if chars.peek() == Some(&'}') => {
+                chars.next();
+                out.push('}');
+            }
+            '{' => {
+                let mut placeholder = String::new();
+                for c in chars.by_ref() {
+                    if c == '}' {
+                        break;
+                    }
+                    placeholder.push(c);
+                }
+                let (position, spec) = placeholder.split_once(':').unwrap_or((&placeholder, ""));
+                let index = match position.parse::<usize>() {
+                    Ok(index) => index,
+                    Err(_) => {
+                        next += 1;
+                        next - 1
+                    }
+                };
+                match args.get(index) {
+                    Some(value) => Spec::parse(spec).write(&mut out, value),
+                    None => out.push_str("{?}"),
+                }
+            }
+            c => out.push(c),
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn decodes_a_captured_frame() {
+        let table =
+            Table::parse("# id\tformat\tlocations\n00000100\tlayer {} is {:?}\\tnow\tsrc/a.rs:3\n")
+                .unwrap();
+
+        // info, id 0x100, 1234ms, unsigned 2, str "on", as `Frame::to_cobs` sends it (the qmk
+        // crate's `frames_are_cobs_encoded` checks the same bytes)
+        let frame = [
+            2,
+            3,
+            2,
+            1,
+            1,
+            9,
+            0xd2,
+            0x09,
+            TAG_UNSIGNED,
+            2,
+            TAG_STR,
+            2,
+            b'o',
+            b'n',
+            0,
+        ];
+        let mut stream = vec![0x42, 0x17]; // the tail of a frame from before capture started
+        stream.push(0);
+        stream.extend(frame);
+
+        let mut decoder = Decoder::new(&table);
+        let lines = decoder.push(&stream[..6]);
+        assert!(matches!(lines[..], [Err(Error::Frame(_))]));
+
+        let lines = decoder.push(&stream[6..]);
+        let line = lines[0].as_ref().unwrap();
+        assert_eq!(line.level, Level::Info);
+        assert_eq!(line.time, 1234);
+        assert_eq!(line.text, "layer 2 is \"on\"\tnow");
+        assert_eq!(
+            line.to_string(),
+            "   1.234 I src/a.rs:3: layer 2 is \"on\"\tnow"
+        );
+    }
+
+    #[test]
+    fn formats_specs_like_rust() {
+        let args = [
+            Value::Unsigned(255),
+            Value::Signed(-7),
+            Value::F32(1.5),
+            Value::Str("ab".into()),
+            Value::Str("ab".into()),
+        ];
+        assert_eq!(
+            format("{:#06x} {:04} {:.2} {:>4}|{:-^6}|{{}}", &args),
+            "0x00ff -007 1.50   ab|--ab--|{}"
+        );
+        assert_eq!(format("{1} {0} {}", &args[..2]), "-7 255 255");
+        assert_eq!(format("{} {}", &args[..1]), "255 {?}");
+    }
+}
@@ -0,0 +1,68 @@
+use std::{
+    env, fs,
+    io::{self, Read, Write},
+    process::ExitCode,
+};
+
+use log_decoder::{Decoder, Table};
+
+const USAGE: &str = "usage: log-decoder <log_strings.tsv> [capture]
+
+Decodes the console output of a keyboard built with the `deferred_log` feature, read from the
+capture file or stdin, using the string table written next to the build output.";
+
+fn main() -> ExitCode {
+    let args = env::args().skip(1).collect::<Vec<_>>();
+    let (table, capture) = match &args[..] {
+        [table] => (table, None),
+        [table, capture] => (table, Some(capture)),
+        _ => {
+            eprintln!("{}", USAGE);
+            return ExitCode::FAILURE;
+        }
+    };
+
+    let table = match Table::load(table) {
+        Ok(table) => table,
+        Err(error) => {
+            eprintln!("couldn't load {}: {}", table, error);
+            return ExitCode::FAILURE;
+        }
+    };
+
+    let mut input: Box<dyn Read> = match capture {
+        Some(path) => match fs::File::open(path) {
+            Ok(file) => Box::new(file),
+            Err(error) => {
+                eprintln!("couldn't open {}: {}", path, error);
+                return ExitCode::FAILURE;
+            }
+        },
+        None => Box::new(io::stdin()),
+    };
+
+    let mut decoder = Decoder::new(&table);
+    let mut stdout = io::stdout();
+    let mut buffer = [0; 256];
+    loop {
+        let read = match input.read(&mut buffer) {
+            Ok(0) => return ExitCode::SUCCESS,
+            Ok(read) => read,
+            Err(error) if error.kind() == io::ErrorKind::Interrupted => continue,
+            Err(error) => {
+                eprintln!("{}", error);
+                return ExitCode::FAILURE;
+            }
+        };
+
+        for line in decoder.push(&buffer[..read]) {
+            match line {
+                Ok(line) => {
+                    let _ = writeln!(stdout, "{}", line);
+                }
+                Err(error) => eprintln!("{}", error),
+            }
+        }
+        let _ = stdout.flush();
+    }
+}
@@ -0,0 +1,251 @@
+use crate::time::Duration;
+
+/// QMK's `TEMPO_DEFAULT`, in beats per minute.
+pub const TEMPO_DEFAULT: u8 = 120;
+
+/// A melody in QMK's `float[][2]` layout, usually made with `song!`. Each note is its frequency
+/// in Hz (0 for a rest) and its length, where 64 is a whole note.
+/// ```rust
+/// use qmk::{audio::Song, song};
+///
+/// static CONFIRM: Song = song!("E5/s A5/e.");
+/// static ALARM: Song = song!("A5/e R/e A5/e R/e A5/h R/q");
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Song {
+    notes: &'static [[f32; 2]],
+}
+
+impl Song {
+    pub const fn new(notes: &'static [[f32; 2]]) -> Self {
+        Self { notes }
+    }
+
+    pub const fn notes(self) -> &'static [[f32; 2]] {
+        self.notes
+    }
+
+    /// How long one play through takes at `tempo` beats per minute.
+    pub fn duration(self, tempo: u8) -> Duration {
+        let units = self.notes.iter().map(|note| note[1] as u32).sum::<u32>();
+        Duration::from_millis(units_to_ms(units, tempo) as u64)
+    }
+}
+
+// QMK's `audio_duration_to_ms`
+fn units_to_ms(units: u32, tempo: u8) -> u32 {
+    units * 60 * 1000 / (64 * tempo.max(1) as u32)
+}
+
+#[cfg(all(not(target_arch = "wasm32"), feature = "audio"))]
+mod bindings {
+    pub fn play_note(frequency: f32, duration_ms: u16) {
+        unsafe { qmk_sys::audio_play_note(frequency, duration_ms) }
+    }
+
+    pub fn play_melody(notes: &'static [[f32; 2]], repeat: bool) {
+        // QMK only reads the notes, and keeps the pointer until the melody is done
+        unsafe {
+            qmk_sys::audio_play_melody(notes.as_ptr() as *mut _, notes.len() as u16, repeat);
+        }
+    }
+
+    pub fn stop_all() {
+        unsafe { qmk_sys::audio_stop_all() }
+    }
+
+    pub fn is_playing() -> bool {
+        unsafe { qmk_sys::audio_is_playing_note() || qmk_sys::audio_is_playing_melody() }
+    }
+
+    pub fn set_tempo(tempo: u8) {
+        unsafe { qmk_sys::audio_set_tempo(tempo) }
+    }
+}
+
+// no speaker, so nothing to do
+#[cfg(all(not(target_arch = "wasm32"), not(feature = "audio")))]
+mod bindings {
+    pub fn play_note(_frequency: f32, _duration_ms: u16) {}
+
+    pub fn play_melody(_notes: &'static [[f32; 2]], _repeat: bool) {}
+
+    pub fn stop_all() {}
+
+    pub fn is_playing() -> bool {
+        false
+    }
+
+    pub fn set_tempo(_tempo: u8) {}
+}
+
+#[cfg(target_arch = "wasm32")]
+mod bindings {
+    use super::{TEMPO_DEFAULT, units_to_ms};
+    use crate::timer::{DeferToken, Timer};
+    use alloc::vec::Vec;
+    use core::{
+        cell::RefCell,
+        sync::atomic::{AtomicU8, Ordering},
+    };
+    use critical_section::{Mutex, with};
+    use web_sys::{AudioContext, AudioScheduledSourceNode, OscillatorNode, OscillatorType};
+
+    // quiet enough not to startle anyone, square waves are loud
+    const VOLUME: f32 = 0.05;
+
+    struct Player {
+        context: AudioContext,
+        // scheduled notes and when they end, in context time
+        oscillators: Vec<(OscillatorNode, f64)>,
+        // when the last scheduled note ends, so melodies queue up behind each other
+        until: f64,
+        repeat: Option<DeferToken>,
+    }
+
+    // JS objects can't be sent between threads, but the browser build only has the one
+    unsafe impl Send for Player {}
+
+    static PLAYER: Mutex<RefCell<Option<Player>>> = Mutex::new(RefCell::new(None));
+    static TEMPO: AtomicU8 = AtomicU8::new(TEMPO_DEFAULT);
+
+    fn with_player<R>(f: impl FnOnce(&mut Player) -> R) -> Option<R> {
+        with(|cs| {
+            let mut player = PLAYER.borrow_ref_mut(cs);
+            if player.is_none() {
+                *player = Some(Player {
+                    context: AudioContext::new().ok()?,
+                    oscillators: Vec::new(),
+                    until: 0.0,
+                    repeat: None,
+                });
+            }
+            player.as_mut().map(f)
+        })
+    }
+
+    impl Player {
+        fn schedule(&mut self, frequency: f32, start: f64, length: f64) -> Option<()> {
+            let now = self.context.current_time();
+            self.oscillators.retain(|(_, end)| *end > now);
+            self.until = self.until.max(start + length);
+            if frequency <= 0.0 {
+                return Some(());
+            }
+
+            let oscillator = self.context.create_oscillator().ok()?;
+            oscillator.set_type(OscillatorType::Square);
+            oscillator.frequency().set_value(frequency);
+            let gain = self.context.create_gain().ok()?;
+            gain.gain().set_value(VOLUME);
+            oscillator.connect_with_audio_node(&gain).ok()?;
+            gain.connect_with_audio_node(&self.context.destination())
+                .ok()?;
+
+            let source: &AudioScheduledSourceNode = &oscillator;
+            source.start_with_when(start).ok()?;
+            source.stop_with_when(start + length).ok()?;
+            self.oscillators.push((oscillator, start + length));
+            Some(())
+        }
+
+        fn schedule_melody(&mut self, notes: &[[f32; 2]]) {
+            // browsers keep the context suspended until the page has been interacted with
+            let _ = self.context.resume();
+            let tempo = TEMPO.load(Ordering::SeqCst);
+            let mut start = self.until.max(self.context.current_time());
+            for &[frequency, units] in notes {
+                let length = units_to_ms(units as u32, tempo) as f64 / 1000.0;
+                self.schedule(frequency, start, length);
+                start += length;
+            }
+        }
+    }
+
+    pub fn play_note(frequency: f32, duration_ms: u16) {
+        with_player(|player| {
+            let _ = player.context.resume();
+            let now = player.context.current_time();
+            player.schedule(frequency, now, duration_ms as f64 / 1000.0);
+        });
+    }
+
+    pub fn play_melody(notes: &'static [[f32; 2]], repeat: bool) {
+        stop_all();
+        with_player(|player| player.schedule_melody(notes));
+        if !repeat {
+            return;
+        }
+
+        let units = notes.iter().map(|note| note[1] as u32).sum::<u32>();
+        let length = units_to_ms(units, TEMPO.load(Ordering::SeqCst)).max(1);
+        // queue the next play through as each one starts
+        let token = Timer::defer_with(length, move || {
+            with_player(|player| player.schedule_melody(notes));
+            length
+        });
+        with_player(|player| player.repeat = token);
+    }
+
+    pub fn stop_all() {
+        let repeat = with_player(|player| {
+            for (oscillator, _) in player.oscillators.drain(..) {
+                let source: &AudioScheduledSourceNode = &oscillator;
+                let _ = source.stop();
+            }
+            player.until = 0.0;
+            player.repeat.take()
+        });
+        if let Some(Some(token)) = repeat {
+            token.cancel();
+        }
+    }
+
+    pub fn is_playing() -> bool {
+        with_player(|player| {
+            player.repeat.is_some() || player.until > player.context.current_time()
+        })
+        .unwrap_or(false)
+    }
+
+    pub fn set_tempo(tempo: u8) {
+        TEMPO.store(tempo, Ordering::SeqCst);
+    }
+}
+
+/// The speaker, through QMK's audio driver.
+///
+/// On the keyboard this needs the `audio` feature and `AUDIO_ENABLE = yes`, and does nothing
+/// otherwise. In the browser notes are played with WebAudio, once the page has been clicked.
+pub struct Audio;
+
+impl Audio {
+    /// Plays a single tone for `duration`, on top of anything already playing.
+    pub fn play_note(frequency: f32, duration: Duration) {
+        bindings::play_note(frequency, duration.as_millis().min(u16::MAX as u128) as u16);
+    }
+
+    /// Plays `song` once, replacing whatever melody was playing.
+    pub fn play_song(song: Song) {
+        bindings::play_melody(song.notes, false);
+    }
+
+    /// Plays `song` over and over until `Audio::stop`.
+    pub fn loop_song(song: Song) {
+        bindings::play_melody(song.notes, true);
+    }
+
+    /// Stops every note and melody.
+    pub fn stop() {
+        bindings::stop_all();
+    }
+
+    pub fn is_playing() -> bool {
+        bindings::is_playing()
+    }
+
+    /// Sets how fast songs play, in beats per minute.
+    pub fn set_tempo(tempo: u8) {
+        bindings::set_tempo(tempo);
+    }
+}
@@ -0,0 +1,22 @@
+use qmk_sys::combo_t;
+
+/// Ends every combo's key list, QMK's `COMBO_END`.
+pub const COMBO_END: u16 = 0;
+
+/// An entry in QMK's `key_combos`, which `combos!` generates.
+pub type Combo = combo_t;
+
+/// COMBO(keys, keycode): `keycode` is sent when all of `keys` are pressed together. `keys` must
+/// end with `COMBO_END` and live as long as the table, so `combos!` puts them in statics.
+pub const fn combo(keys: &'static [u16], keycode: u16) -> Combo {
+    assert!(
+        matches!(keys.last(), Some(&COMBO_END)),
+        "combo keys must end with COMBO_END"
+    );
+    Combo {
+        keys: keys.as_ptr(),
+        keycode,
+        // not disabled, not active, nothing pressed yet
+        ..unsafe { core::mem::zeroed() }
+    }
+}
@@ -0,0 +1,40 @@
+use crate::{
+    KeyRecord,
+    executor::{Executor, sleep},
+    keyboard::Keyboard,
+    time::Duration,
+};
+
+/// A key `keymap!` takes by path, like `CustomKeycode::Reset`: a keycode constant, or the
+/// `CustomKeycode` enum from `custom_keycodes!`. Other enums, like `Layer` or `TapDance`, aren't
+/// keycodes and are rejected rather than cast.
+#[diagnostic::on_unimplemented(
+    message = "`{Self}` isn't a keycode",
+    note = "name keys from `custom_keycodes!` as `CustomKeycode::Name`, and layers with `mo!` and friends"
+)]
+pub trait KeymapKey: Copy {}
+
+impl KeymapKey for u16 {}
+impl KeymapKey for u32 {}
+
+/// Passes `key` through if it's a [`KeymapKey`], for `keymap!` to check a key's type before
+/// casting it.
+pub const fn keymap_key<K: KeymapKey>(key: K) -> K {
+    key
+}
+
+/// Whether `record` is a key being pressed rather than released. The firmware packs
+/// `keyevent_type_t` into a byte, so bindgen's `type_` also holds `pressed` above it.
+pub fn is_pressed(record: &KeyRecord) -> bool {
+    record.event.type_ >> 8 != 0
+}
+
+/// Taps each of `keys` in turn, for a keycode from `custom_keycodes!` bound to a `sequence`.
+pub fn send_sequence(keys: &'static [u16]) {
+    Executor::spawn(async move {
+        for key in keys {
+            sleep(Duration::from_millis(5)).await;
+            Keyboard::send_key_delay(*key, 50);
+        }
+    });
+}
@@ -10,7 +10,7 @@ use web_sys::window;
 
 use crate::EEPROM_BYTES;
 #[cfg(target_arch = "wasm32")]
-use crate::qmk_log;
+use crate::{debug, warn};
 #[cfg(target_arch = "wasm32")]
 use alloc::format;
 #[cfg(target_arch = "wasm32")]
@@ -101,7 +101,7 @@ impl<T: Sized + Default + Debug> EEConfig<T, Checked> {
         local_storage
             .set_item("qmk_eeconfig", &hex_string)
             .unwrap_or_else(|_| {
-                qmk_log!("Failed to save eeconfig to local storage");
+                warn!("Failed to save eeconfig to local storage");
             });
     }
 
@@ -111,7 +111,7 @@ impl<T: Sized + Default + Debug> EEConfig<T, Checked> {
         let hex_string = local_storage
             .get_item("qmk_eeconfig")
             .unwrap_or_else(|_| {
-                qmk_log!("Failed to load eeconfig from local storage");
+                warn!("Failed to load eeconfig from local storage");
                 None
             })
             .unwrap_or_default();
@@ -134,7 +134,7 @@ impl<T: Sized + Default + Debug> EEConfig<T, Checked> {
             unsafe { core::slice::from_raw_parts_mut(object_ptr, core::mem::size_of::<T>()) };
 
         if object_slice.len() != bytes.len() {
-            qmk_log!(
+            warn!(
                 "EEConfig: Length mismatch, expected {} but got {}",
                 object_slice.len(),
                 bytes.len()
@@ -143,7 +143,7 @@ impl<T: Sized + Default + Debug> EEConfig<T, Checked> {
         }
 
         object_slice.copy_from_slice(&bytes);
-        qmk_log!("EEConfig: Loaded object: {:?}", object);
+        debug!("EEConfig: Loaded object: {:?}", object);
         object
     }
 
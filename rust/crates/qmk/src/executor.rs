@@ -0,0 +1,170 @@
+use alloc::{boxed::Box, collections::VecDeque, vec::Vec};
+use core::{
+    cell::RefCell,
+    future::Future,
+    pin::Pin,
+    task::{Context, Poll, Waker},
+};
+use critical_section::{Mutex, with};
+
+use crate::{
+    keys::Keycode,
+    time::{Duration, Instant},
+};
+
+type Task = Pin<Box<dyn Future<Output = ()> + Send>>;
+
+static TASKS: Mutex<RefCell<Vec<Task>>> = Mutex::new(RefCell::new(Vec::new()));
+// tasks spawned while the executor is polling land here first
+static SPAWNED: Mutex<RefCell<Vec<Task>>> = Mutex::new(RefCell::new(Vec::new()));
+
+/// Key and encoder input, as seen by `process_record_user` and `encoder_update_user`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Event {
+    KeyDown(Keycode),
+    KeyUp(Keycode),
+    Encoder { index: u8, clockwise: bool },
+}
+
+/// Input events for tasks to wait on. The keymap feeds this from its input callbacks.
+pub static EVENTS: Channel<Event> = Channel::new(16);
+
+/// A tiny single-threaded async executor.
+///
+/// Tasks are polled from `Executor::poll`, which the keymap calls from `housekeeping_task_user`
+/// (and the browser calls once per frame). There are no real wakers: every pending task is polled
+/// on every call, so the futures in here just check whether they are ready yet.
+pub struct Executor;
+
+impl Executor {
+    /// Runs `future` to completion in the background.
+    pub fn spawn<F>(future: F)
+    where
+        F: Future<Output = ()> + Send + 'static,
+    {
+        with(|cs| SPAWNED.borrow_ref_mut(cs).push(Box::pin(future)));
+    }
+
+    /// Polls every task once, dropping the ones that have finished.
+    pub fn poll() {
+        let mut tasks = with(|cs| {
+            let mut tasks = TASKS.borrow_ref_mut(cs);
+            tasks.append(&mut SPAWNED.borrow_ref_mut(cs));
+            core::mem::take(&mut *tasks)
+        });
+
+        let mut context = Context::from_waker(Waker::noop());
+        tasks.retain_mut(|task| task.as_mut().poll(&mut context).is_pending());
+
+        with(|cs| *TASKS.borrow_ref_mut(cs) = tasks);
+    }
+
+    /// The number of tasks that haven't finished yet.
+    pub fn task_count() -> usize {
+        with(|cs| TASKS.borrow_ref(cs).len() + SPAWNED.borrow_ref(cs).len())
+    }
+}
+
+/// Waits until `duration` has passed.
+pub fn sleep(duration: Duration) -> Sleep {
+    Sleep {
+        deadline: Instant::now() + duration,
+    }
+}
+
+/// Waits until `deadline`.
+pub fn sleep_until(deadline: Instant) -> Sleep {
+    Sleep { deadline }
+}
+
+pub struct Sleep {
+    deadline: Instant,
+}
+
+impl Future for Sleep {
+    type Output = ();
+
+    fn poll(self: Pin<&mut Self>, _: &mut Context<'_>) -> Poll<()> {
+        if Instant::now() >= self.deadline {
+            Poll::Ready(())
+        } else {
+            Poll::Pending
+        }
+    }
+}
+
+/// Gives up the rest of this poll, so the task continues on the next one.
+pub fn yield_now() -> YieldNow {
+    YieldNow { yielded: false }
+}
+
+pub struct YieldNow {
+    yielded: bool,
+}
+
+impl Future for YieldNow {
+    type Output = ();
+
+    fn poll(mut self: Pin<&mut Self>, _: &mut Context<'_>) -> Poll<()> {
+        if self.yielded {
+            Poll::Ready(())
+        } else {
+            self.yielded = true;
+            Poll::Pending
+        }
+    }
+}
+
+/// A bounded queue that tasks can wait on. When it is full the oldest value is dropped, so input
+/// nobody is listening for doesn't pile up.
+pub struct Channel<T> {
+    queue: Mutex<RefCell<VecDeque<T>>>,
+    capacity: usize,
+}
+
+impl<T> Channel<T> {
+    pub const fn new(capacity: usize) -> Self {
+        Self {
+            queue: Mutex::new(RefCell::new(VecDeque::new())),
+            capacity,
+        }
+    }
+
+    pub fn send(&self, value: T) {
+        with(|cs| {
+            let mut queue = self.queue.borrow_ref_mut(cs);
+            if queue.len() >= self.capacity {
+                queue.pop_front();
+            }
+            queue.push_back(value);
+        });
+    }
+
+    pub fn try_recv(&self) -> Option<T> {
+        with(|cs| self.queue.borrow_ref_mut(cs).pop_front())
+    }
+
+    /// Waits for the next value. If several tasks are waiting, the first one polled gets it.
+    pub fn recv(&self) -> Recv<'_, T> {
+        Recv { channel: self }
+    }
+
+    pub fn clear(&self) {
+        with(|cs| self.queue.borrow_ref_mut(cs).clear());
+    }
+}
+
+pub struct Recv<'a, T> {
+    channel: &'a Channel<T>,
+}
+
+impl<T> Future for Recv<'_, T> {
+    type Output = T;
+
+    fn poll(self: Pin<&mut Self>, _: &mut Context<'_>) -> Poll<T> {
+        match self.channel.try_recv() {
+            Some(value) => Poll::Ready(value),
+            None => Poll::Pending,
+        }
+    }
+}
@@ -0,0 +1,242 @@
+use alloc::{vec, vec::Vec};
+use core::{cell::RefCell, ops::Range};
+use critical_section::{Mutex, with};
+
+use crate::{
+    keyboard::{Keyboard, LedState},
+    mods::{Modifiers, Mods},
+    rgb::{RGBLight, Rgb},
+    rgb_matrix::{LedConfig, LedFlags},
+    time::{Duration, Instant},
+};
+
+/// When an indicator is shown.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum When {
+    Always,
+    /// The layer is active.
+    Layer(u8),
+    /// Any of these lock LEDs are on.
+    Lock(LedState),
+    /// Any of these modifiers are held.
+    Mods(Mods),
+}
+
+impl When {
+    fn is_active(self) -> bool {
+        match self {
+            When::Always => true,
+            When::Layer(layer) => Keyboard::layer_state_is(layer),
+            When::Lock(leds) => Keyboard::led_state().intersects(leds),
+            When::Mods(mods) => Modifiers::get().intersects(mods),
+        }
+    }
+}
+
+/// Which LEDs an indicator lights.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Target {
+    All,
+    /// LEDs `start..end`.
+    Range(u8, u8),
+    /// LEDs with any of these flags. Only works on the RGB matrix, which knows the layout.
+    Flags(LedFlags),
+}
+
+impl Target {
+    fn contains(self, index: usize, config: Option<&LedConfig>) -> bool {
+        match self {
+            Target::All => true,
+            Target::Range(start, end) => (start as usize..end as usize).contains(&index),
+            Target::Flags(flags) => config
+                .and_then(|config| config.get(index))
+                .is_some_and(|led| led.flags.intersects(flags)),
+        }
+    }
+}
+
+/// How an indicator's colour is drawn over what is underneath.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Style {
+    Solid,
+    /// Fades from the colour underneath to the indicator's colour and back, once per period.
+    Pulse(Duration),
+    /// Shows the indicator's colour for the first half of each period.
+    Blink(Duration),
+}
+
+impl Style {
+    fn draw(self, base: Rgb, color: Rgb, time: Instant) -> Rgb {
+        let phase = |period: Duration| {
+            let period = (period.as_millis() as u32).max(1);
+            (time.as_millis() % period) as f32 / period as f32
+        };
+
+        match self {
+            Style::Solid => color,
+            Style::Pulse(period) => {
+                let phase = phase(period);
+                let t = if phase < 0.5 { phase } else { 1.0 - phase };
+                base.lerp(color, t * 2.0)
+            }
+            Style::Blink(period) => {
+                if phase(period) < 0.5 {
+                    color
+                } else {
+                    base
+                }
+            }
+        }
+    }
+}
+
+/// A rule that lights some LEDs while the keyboard is in some state, e.g.
+/// ```rust
+/// use qmk::{indicator::{Indicator, When}, keyboard::LedState, rgb::Rgb, time::Duration};
+///
+/// static INDICATORS: [Indicator; 2] = [
+///     Indicator::new(When::Layer(2), Rgb::RED).leds(0..6),
+///     Indicator::new(When::Lock(LedState::CAPS_LOCK), Rgb::WHITE)
+///         .pulse(Duration::from_millis(1000)),
+/// ];
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Indicator {
+    pub when: When,
+    pub target: Target,
+    pub color: Rgb,
+    pub style: Style,
+}
+
+impl Indicator {
+    /// A solid `color` on every LED while `when` holds.
+    pub const fn new(when: When, color: Rgb) -> Self {
+        Self {
+            when,
+            target: Target::All,
+            color,
+            style: Style::Solid,
+        }
+    }
+
+    pub const fn leds(mut self, leds: Range<u8>) -> Self {
+        self.target = Target::Range(leds.start, leds.end);
+        self
+    }
+
+    pub const fn flags(mut self, flags: LedFlags) -> Self {
+        self.target = Target::Flags(flags);
+        self
+    }
+
+    pub const fn pulse(mut self, period: Duration) -> Self {
+        self.style = Style::Pulse(period);
+        self
+    }
+
+    pub const fn blink(mut self, period: Duration) -> Self {
+        self.style = Style::Blink(period);
+        self
+    }
+}
+
+// how often the RGBLight strip is redrawn, since every write pushes out the whole strip
+const RGBLIGHT_FRAME_TIME: Duration = Duration::from_millis(20);
+
+struct State {
+    rules: &'static [Indicator],
+    // what was last written to the RGBLight strip, `None` while no rule is lit
+    strip: Option<Vec<Rgb>>,
+    last_frame: Instant,
+}
+
+static STATE: Mutex<RefCell<State>> = Mutex::new(RefCell::new(State {
+    rules: &[],
+    strip: None,
+    last_frame: Instant::BOOT,
+}));
+
+/// Lights LEDs from keyboard state, on top of the user's colour or the current RGB effect.
+///
+/// Rules are checked every frame and drawn in order, so later rules win where they overlap.
+/// `RgbMatrix::task` draws them over the matrix, and `Indicators::task` (called from
+/// `housekeeping_task_user`) over the RGBLight strip. In the browser the RGBLight strip doesn't
+/// exist, but the rules still show up in the RGB matrix buffer, driven by the simulated layer and
+/// lock state.
+pub struct Indicators;
+
+impl Indicators {
+    pub fn set(rules: &'static [Indicator]) {
+        with(|cs| STATE.borrow_ref_mut(cs).rules = rules);
+    }
+
+    pub fn rules() -> &'static [Indicator] {
+        with(|cs| STATE.borrow_ref(cs).rules)
+    }
+
+    /// Draws every active rule over `colors`. Returns which LEDs were drawn on, or an empty
+    /// `Vec` if no rule is active.
+    pub fn apply(colors: &mut [Rgb], config: Option<&LedConfig>, time: Instant) -> Vec<bool> {
+        let mut lit = Vec::new();
+        for rule in Self::rules().iter().filter(|rule| rule.when.is_active()) {
+            if lit.is_empty() {
+                lit = vec![false; colors.len()];
+            }
+            for (index, color) in colors.iter_mut().enumerate() {
+                if rule.target.contains(index, config) {
+                    *color = rule.style.draw(*color, rule.color, time);
+                    lit[index] = true;
+                }
+            }
+        }
+        lit
+    }
+
+    /// Draws the rules over the RGBLight strip, restoring the user's colour when none are active.
+    pub fn task() {
+        let now = Instant::now();
+        let previous = with(|cs| {
+            let mut state = STATE.borrow_ref_mut(cs);
+            if now.duration_since(state.last_frame) < RGBLIGHT_FRAME_TIME {
+                return None;
+            }
+            state.last_frame = now;
+            Some(state.strip.take())
+        });
+        let Some(previous) = previous else {
+            return;
+        };
+
+        let base = RGBLight::hsv();
+        let mut strip = vec![Rgb::from(base); RGBLight::led_count() as usize];
+        let lit = Self::apply(&mut strip, None, now);
+
+        let strip = if lit.is_empty() {
+            if previous.is_some() {
+                RGBLight::set_hsv_noeeprom(base);
+            }
+            None
+        } else {
+            if previous.as_ref() != Some(&strip) {
+                write_strip(&strip);
+            }
+            Some(strip)
+        };
+
+        with(|cs| STATE.borrow_ref_mut(cs).strip = strip);
+    }
+}
+
+// writes runs of the same colour together, since each write pushes out the whole strip
+fn write_strip(strip: &[Rgb]) {
+    let mut start = 0;
+    while start < strip.len() {
+        let color = strip[start];
+        let end = strip[start..]
+            .iter()
+            .position(|&other| other != color)
+            .map_or(strip.len(), |length| start + length);
+        RGBLight::set_rgb_range(start as u8, end as u8, color);
+        start = end;
+    }
+}
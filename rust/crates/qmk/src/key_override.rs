@@ -0,0 +1,108 @@
+use bitflags::bitflags;
+use core::ffi::c_void;
+use qmk_sys::layer_state_t;
+
+use crate::mods::Mods;
+
+bitflags! {
+    /// `ko_option_t`, when a key override activates and how it behaves once it has.
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+    pub struct KeyOverrideOptions: u8 {
+        /// Activate when the trigger key is pressed down.
+        const ACTIVATION_TRIGGER_DOWN = 1 << 0;
+        /// Activate when a required modifier is pressed down.
+        const ACTIVATION_REQUIRED_MOD_DOWN = 1 << 1;
+        /// Activate when a negative modifier is released.
+        const ACTIVATION_NEGATIVE_MOD_UP = 1 << 2;
+        const ALL_ACTIVATIONS = Self::ACTIVATION_TRIGGER_DOWN.bits()
+            | Self::ACTIVATION_REQUIRED_MOD_DOWN.bits()
+            | Self::ACTIVATION_NEGATIVE_MOD_UP.bits();
+        /// Any one of the trigger modifiers is enough, rather than all of them.
+        const ONE_MOD = 1 << 3;
+        /// Don't register the trigger key again after the override deactivates.
+        const NO_REREGISTER_TRIGGER = 1 << 4;
+        /// Stay active when another key is pressed.
+        const NO_UNREGISTER_ON_OTHER_KEY_DOWN = 1 << 5;
+        const DEFAULT = Self::ALL_ACTIVATIONS.bits();
+    }
+}
+
+/// Called instead of sending the replacement, with whether the override just activated.
+pub type KeyOverrideAction = unsafe extern "C" fn(activated: bool, context: *mut c_void) -> bool;
+
+/// QMK's `key_override_t`, an entry in the `key_overrides` table `key_overrides!` generates.
+///
+/// This is written out by hand rather than taken from bindgen because `ko_option_t` is a byte on
+/// the keyboard, where enums are packed, but an `int` to bindgen.
+/// ```rust
+/// use qmk::{key, key_override::KeyOverride, keys::KC_DEL, lctl, mods::Mods};
+///
+/// // shift + ctrl + backspace deletes the next word, but only on layer 1
+/// static DELETE_WORD: KeyOverride =
+///     KeyOverride::new(Mods::CTRL.union(Mods::SHIFT), key!(KC_BSPC), lctl!(KC_DEL)).layers(1 << 1);
+/// ```
+#[derive(Debug, Clone, Copy)]
+#[repr(C)]
+pub struct KeyOverride {
+    pub trigger: u16,
+    pub trigger_mods: u8,
+    pub layers: layer_state_t,
+    pub negative_mod_mask: u8,
+    pub suppressed_mods: u8,
+    pub replacement: u16,
+    pub options: KeyOverrideOptions,
+    pub custom_action: Option<KeyOverrideAction>,
+    pub context: *mut c_void,
+    pub enabled: *mut bool,
+}
+
+// QMK only reads the table, `context` and `enabled` are whoever set them's to keep valid
+unsafe impl Sync for KeyOverride {}
+
+impl KeyOverride {
+    /// ko_make_basic(mods, trigger, replacement): `replacement` is sent instead of `trigger` while
+    /// `mods` are held, on every layer, and the modifiers are hidden from it.
+    pub const fn new(mods: Mods, trigger: u16, replacement: u16) -> Self {
+        Self {
+            trigger,
+            trigger_mods: mods.bits(),
+            layers: !0,
+            negative_mod_mask: 0,
+            suppressed_mods: mods.bits(),
+            replacement,
+            options: KeyOverrideOptions::DEFAULT,
+            custom_action: None,
+            context: core::ptr::null_mut(),
+            enabled: core::ptr::null_mut(),
+        }
+    }
+
+    /// Only applies while one of these layers is on, one bit per layer.
+    pub const fn layers(mut self, layers: layer_state_t) -> Self {
+        self.layers = layers;
+        self
+    }
+
+    /// Doesn't apply while any of `mods` are held.
+    pub const fn negative_mods(mut self, mods: Mods) -> Self {
+        self.negative_mod_mask = mods.bits();
+        self
+    }
+
+    /// The modifiers hidden from the replacement, the trigger modifiers by default.
+    pub const fn suppressed_mods(mut self, mods: Mods) -> Self {
+        self.suppressed_mods = mods.bits();
+        self
+    }
+
+    pub const fn options(mut self, options: KeyOverrideOptions) -> Self {
+        self.options = options;
+        self
+    }
+
+    /// Runs `action` instead of sending the replacement.
+    pub const fn action(mut self, action: KeyOverrideAction) -> Self {
+        self.custom_action = Some(action);
+        self
+    }
+}
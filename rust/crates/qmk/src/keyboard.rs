@@ -1,16 +1,65 @@
+use crate::time::Duration;
+use bitflags::bitflags;
+
 #[cfg(target_arch = "wasm32")]
 mod bindings {
+    use core::sync::atomic::{AtomicU8, AtomicU32, Ordering};
+
+    pub static LED_STATE: AtomicU8 = AtomicU8::new(0);
+    pub static LAYER_STATE: AtomicU32 = AtomicU32::new(0);
+    pub static LAST_ACTIVITY: AtomicU32 = AtomicU32::new(0);
+
     pub unsafe fn is_keyboard_left() -> bool {
         true
     }
 
+    pub unsafe fn host_keyboard_led_state() -> u8 {
+        LED_STATE.load(Ordering::SeqCst)
+    }
+
+    pub unsafe fn last_input_activity_elapsed() -> u32 {
+        crate::timer::Timer::elapsed32(LAST_ACTIVITY.load(Ordering::SeqCst))
+    }
+
     pub unsafe fn tap_code16(_key: u16) {}
     pub unsafe fn tap_code16_delay(_key: u16, _delay: u16) {}
 }
 
 #[cfg(not(target_arch = "wasm32"))]
 mod bindings {
-    pub use qmk_sys::{is_keyboard_left, tap_code16, tap_code16_delay};
+    pub use qmk_sys::{
+        is_keyboard_left, last_input_activity_elapsed, tap_code16, tap_code16_delay,
+    };
+
+    pub unsafe fn host_keyboard_led_state() -> u8 {
+        unsafe { qmk_sys::host_keyboard_led_state().raw }
+    }
+}
+
+bitflags! {
+    /// The lock LEDs reported by the host, matching QMK's `led_t`.
+    #[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+    pub struct LedState: u8 {
+        const NUM_LOCK = 0x01;
+        const CAPS_LOCK = 0x02;
+        const SCROLL_LOCK = 0x04;
+        const COMPOSE = 0x08;
+        const KANA = 0x10;
+    }
+}
+
+impl LedState {
+    pub const fn num_lock(&self) -> bool {
+        self.contains(Self::NUM_LOCK)
+    }
+
+    pub const fn caps_lock(&self) -> bool {
+        self.contains(Self::CAPS_LOCK)
+    }
+
+    pub const fn scroll_lock(&self) -> bool {
+        self.contains(Self::SCROLL_LOCK)
+    }
 }
 
 pub struct Keyboard;
@@ -42,13 +91,45 @@ impl Keyboard {
         }
 
         #[cfg(target_arch = "wasm32")]
-        unsafe fn layer_state_is(_layer: u8) -> bool {
-            false
+        unsafe fn layer_state_is(layer: u8) -> bool {
+            let state = bindings::LAYER_STATE.load(core::sync::atomic::Ordering::SeqCst);
+            layer < 32 && state & (1 << layer) != 0
         }
 
         unsafe { layer_state_is(layer) }
     }
 
+    /// Returns the lock LED state most recently sent by the host.
+    pub fn led_state() -> LedState {
+        LedState::from_bits_retain(unsafe { bindings::host_keyboard_led_state() })
+    }
+
+    /// Sets the lock LED state seen by `led_state`, since the simulator has no host to report it.
+    #[cfg(target_arch = "wasm32")]
+    pub fn simulate_led_state(state: LedState) {
+        bindings::LED_STATE.store(state.bits(), core::sync::atomic::Ordering::SeqCst);
+    }
+
+    /// Sets the layers seen by `layer_state_is`, one bit per layer, since the simulator has no
+    /// QMK layer state.
+    #[cfg(target_arch = "wasm32")]
+    pub fn simulate_layer_state(state: u32) {
+        bindings::LAYER_STATE.store(state, core::sync::atomic::Ordering::SeqCst);
+    }
+
+    /// How long it's been since a key, encoder or pointing device was last used. On a split
+    /// keyboard this covers both halves when `SPLIT_ACTIVITY_ENABLE` is defined.
+    pub fn last_activity() -> Duration {
+        Duration::from_millis(unsafe { bindings::last_input_activity_elapsed() } as u64)
+    }
+
+    /// Marks the simulated keyboard as just used, for `last_activity`.
+    #[cfg(target_arch = "wasm32")]
+    pub fn simulate_activity() {
+        let now = crate::timer::Timer::read32();
+        bindings::LAST_ACTIVITY.store(now, core::sync::atomic::Ordering::SeqCst);
+    }
+
     pub fn send_key(key: u16) {
         unsafe {
             bindings::tap_code16(key);
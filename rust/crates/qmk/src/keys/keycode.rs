@@ -0,0 +1,463 @@
+use core::fmt;
+use core::str::FromStr;
+
+use super::names::{KEYCODE_NAMES, KEYCODE_TABLE};
+use crate::mods::Mods;
+use qmk_sys::qk_keycode_ranges::*;
+
+const MOD_NAMES: [&str; 4] = ["CTL", "SFT", "ALT", "GUI"];
+
+/// A QMK keycode.
+///
+/// This is a plain `u16` underneath, so it can be placed in the keymap and passed to QMK as-is,
+/// but it knows how to decode itself and can be printed or parsed by name.
+#[derive(Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[repr(transparent)]
+pub struct Keycode(pub u16);
+
+impl Keycode {
+    pub const NO: Keycode = Keycode(0x0000);
+    pub const TRANSPARENT: Keycode = Keycode(0x0001);
+
+    pub const fn new(raw: u16) -> Self {
+        Self(raw)
+    }
+
+    pub const fn raw(self) -> u16 {
+        self.0
+    }
+
+    /// This key with the given modifiers held, eg. `LCTL(KC_A)`.
+    ///
+    /// # Panics
+    /// Keycodes can only hold modifiers from one side, so this panics if `mods` mixes left and
+    /// right modifiers or differs in side from modifiers already on the key. When used in a const
+    /// (such as the keymap) this is a compile error.
+    pub const fn with_mods(self, mods: Mods) -> Self {
+        assert!(
+            self.0 <= QK_MODS_MAX as u16,
+            "only basic keycodes can have modifiers"
+        );
+        let packed = packed_mods(mods);
+        let existing = ((self.0 >> 8) & 0x1F) as u8;
+        assert!(
+            existing == 0 || packed == 0 || (existing & 0x10) == (packed & 0x10),
+            "keycodes can't mix left and right modifiers"
+        );
+        Self(self.0 | ((packed as u16) << 8))
+    }
+
+    /// MO(layer): `layer` is active while held.
+    pub const fn momentary(layer: u8) -> Self {
+        Self::layer_function(QK_MOMENTARY, layer)
+    }
+
+    /// TG(layer): toggles `layer` on and off.
+    pub const fn toggle(layer: u8) -> Self {
+        Self::layer_function(QK_TOGGLE_LAYER, layer)
+    }
+
+    /// TO(layer): turns on `layer` and turns off every other layer except the default.
+    pub const fn to(layer: u8) -> Self {
+        Self::layer_function(QK_TO, layer)
+    }
+
+    /// DF(layer): sets the default layer until the keyboard is reset.
+    pub const fn default_layer(layer: u8) -> Self {
+        Self::layer_function(QK_DEF_LAYER, layer)
+    }
+
+    /// PDF(layer): sets the default layer and saves it to EEPROM.
+    pub const fn persistent_default_layer(layer: u8) -> Self {
+        Self::layer_function(QK_PERSISTENT_DEF_LAYER, layer)
+    }
+
+    /// OSL(layer): `layer` is active for the next key press only.
+    pub const fn one_shot_layer(layer: u8) -> Self {
+        Self::layer_function(QK_ONE_SHOT_LAYER, layer)
+    }
+
+    /// TT(layer): acts like `MO` while held, and toggles `layer` after `TAPPING_TOGGLE` taps.
+    pub const fn layer_tap_toggle(layer: u8) -> Self {
+        Self::layer_function(QK_LAYER_TAP_TOGGLE, layer)
+    }
+
+    /// LT(layer, key): `key` when tapped, `layer` while held. Only layers 0-15 and basic keys fit.
+    pub const fn layer_tap(layer: u8, key: Keycode) -> Self {
+        assert!(layer <= 15, "LT only supports layers 0-15");
+        assert!(key.is_basic(), "LT only supports basic keycodes");
+        Self(QK_LAYER_TAP as u16 | ((layer as u16) << 8) | key.0)
+    }
+
+    /// LM(layer, mods): `layer` is active and `mods` are held while held. Only layers 0-15 fit.
+    pub const fn layer_mod(layer: u8, mods: Mods) -> Self {
+        assert!(layer <= 15, "LM only supports layers 0-15");
+        Self(QK_LAYER_MOD as u16 | ((layer as u16) << 5) | packed_mods(mods) as u16)
+    }
+
+    /// MT(mods, key): `key` when tapped, `mods` while held. Only basic keys fit.
+    pub const fn mod_tap(mods: Mods, key: Keycode) -> Self {
+        assert!(key.is_basic(), "MT only supports basic keycodes");
+        Self(QK_MOD_TAP as u16 | ((packed_mods(mods) as u16) << 8) | key.0)
+    }
+
+    /// OSM(mods): `mods` are held for the next key press only.
+    pub const fn one_shot_mod(mods: Mods) -> Self {
+        Self(QK_ONE_SHOT_MOD as u16 | packed_mods(mods) as u16)
+    }
+
+    /// TD(index): the tap dance at `index` in `tap_dance_actions`, see `tap_dance!`.
+    pub const fn tap_dance(index: u8) -> Self {
+        Self(QK_TAP_DANCE as u16 | index as u16)
+    }
+
+    const fn layer_function(base: u32, layer: u8) -> Self {
+        assert!(layer <= 31, "layer keycodes only support layers 0-31");
+        Self(base as u16 | layer as u16)
+    }
+
+    const fn in_range(self, min: u32, max: u32) -> bool {
+        self.0 as u32 >= min && self.0 as u32 <= max
+    }
+
+    pub const fn is_no(self) -> bool {
+        self.0 == Self::NO.0
+    }
+
+    pub const fn is_transparent(self) -> bool {
+        self.0 == Self::TRANSPARENT.0
+    }
+
+    /// A key from the HID keyboard page, with no modifiers or behaviour attached.
+    pub const fn is_basic(self) -> bool {
+        self.in_range(QK_BASIC, QK_BASIC_MAX)
+    }
+
+    /// One of the eight modifier keys, `KC_LEFT_CTRL` to `KC_RIGHT_GUI`.
+    pub const fn is_modifier(self) -> bool {
+        self.0 >= 0xE0 && self.0 <= 0xE7
+    }
+
+    /// A basic key with modifiers attached, eg. `LCTL(KC_A)` or `KC_EXLM`.
+    pub const fn is_modded(self) -> bool {
+        self.in_range(QK_MODS, QK_MODS_MAX)
+    }
+
+    pub const fn is_mod_tap(self) -> bool {
+        self.in_range(QK_MOD_TAP, QK_MOD_TAP_MAX)
+    }
+
+    pub const fn is_layer_tap(self) -> bool {
+        self.in_range(QK_LAYER_TAP, QK_LAYER_TAP_MAX)
+    }
+
+    pub const fn is_one_shot_mod(self) -> bool {
+        self.in_range(QK_ONE_SHOT_MOD, QK_ONE_SHOT_MOD_MAX)
+    }
+
+    /// Any key that changes the layer state: `LT`, `LM`, `TO`, `MO`, `DF`, `TG`, `OSL`, `TT` and
+    /// `PDF`.
+    pub const fn is_layer_key(self) -> bool {
+        self.layer().is_some()
+    }
+
+    pub const fn is_user(self) -> bool {
+        self.in_range(QK_USER, QK_USER_MAX)
+    }
+
+    pub const fn is_kb(self) -> bool {
+        self.in_range(QK_KB, QK_KB_MAX)
+    }
+
+    /// The layer this key activates, if it is a layer key.
+    pub const fn layer(self) -> Option<u8> {
+        if self.is_layer_tap() {
+            Some(((self.0 >> 8) & 0xF) as u8)
+        } else if self.in_range(QK_LAYER_MOD, QK_LAYER_MOD_MAX) {
+            Some(((self.0 >> 5) & 0xF) as u8)
+        } else if self.in_range(QK_TO, QK_ONE_SHOT_LAYER_MAX)
+            || self.in_range(QK_LAYER_TAP_TOGGLE, QK_PERSISTENT_DEF_LAYER_MAX)
+        {
+            Some((self.0 & 0x1F) as u8)
+        } else {
+            None
+        }
+    }
+
+    /// The modifiers this key applies, including plain modifier keys like `KC_LSFT`.
+    pub const fn mods(self) -> Mods {
+        if self.is_modifier() {
+            Mods::from_bits_retain(1 << (self.0 - 0xE0))
+        } else if self.is_modded() || self.is_mod_tap() {
+            Mods::from_packed(((self.0 >> 8) & 0x1F) as u8)
+        } else if self.in_range(QK_LAYER_MOD, QK_LAYER_MOD_MAX) || self.is_one_shot_mod() {
+            Mods::from_packed((self.0 & 0x1F) as u8)
+        } else {
+            Mods::empty()
+        }
+    }
+
+    /// The basic key sent by modded, mod-tap and layer-tap keys.
+    pub const fn tap_keycode(self) -> Option<Keycode> {
+        if self.is_basic() {
+            Some(self)
+        } else if self.is_modded() || self.is_mod_tap() || self.is_layer_tap() {
+            Some(Self(self.0 & 0xFF))
+        } else {
+            None
+        }
+    }
+
+    /// The QMK name of this exact keycode, eg. `KC_ESCAPE`.
+    pub fn name(self) -> Option<&'static str> {
+        Self::entry(self).map(|(_, name, _)| name)
+    }
+
+    /// The human-readable label from QMK's keycode spec, eg. `Esc`.
+    pub fn label(self) -> Option<&'static str> {
+        Self::entry(self)
+            .map(|(_, _, label)| label)
+            .filter(|label| !label.is_empty())
+    }
+
+    /// Looks up a keycode by its QMK name or alias, eg. `KC_ESC`.
+    pub fn from_name(name: &str) -> Option<Self> {
+        KEYCODE_NAMES
+            .binary_search_by(|(n, _)| (*n).cmp(name))
+            .ok()
+            .map(|i| Self(KEYCODE_NAMES[i].1))
+    }
+
+    fn entry(self) -> Option<(u16, &'static str, &'static str)> {
+        KEYCODE_TABLE
+            .binary_search_by_key(&self.0, |(code, _, _)| *code)
+            .ok()
+            .map(|i| KEYCODE_TABLE[i])
+    }
+}
+
+/// Converts to the 5-bit form used inside keycodes, which can't mix left and right modifiers.
+const fn packed_mods(mods: Mods) -> u8 {
+    let bits = mods.bits();
+    assert!(
+        bits & 0x0F == 0 || bits & 0xF0 == 0,
+        "keycodes can't mix left and right modifiers"
+    );
+    mods.to_packed()
+}
+
+impl From<u16> for Keycode {
+    fn from(raw: u16) -> Self {
+        Self(raw)
+    }
+}
+
+impl From<Keycode> for u16 {
+    fn from(keycode: Keycode) -> Self {
+        keycode.0
+    }
+}
+
+/// Writes packed mods as `MOD_LCTL | MOD_LSFT`.
+fn write_mod_mask(f: &mut fmt::Formatter<'_>, packed: u8) -> fmt::Result {
+    let side = if packed & 0x10 != 0 { 'R' } else { 'L' };
+    let mut first = true;
+    for (bit, name) in MOD_NAMES.iter().enumerate() {
+        if packed & (1 << bit) == 0 {
+            continue;
+        }
+        if !first {
+            f.write_str(" | ")?;
+        }
+        write!(f, "MOD_{side}{name}")?;
+        first = false;
+    }
+    if first {
+        f.write_str("0")?;
+    }
+    Ok(())
+}
+
+impl fmt::Display for Keycode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let Some(name) = self.name() {
+            return f.write_str(name);
+        }
+
+        let kc = self.0 as u32;
+        let layer = self.layer().unwrap_or(0);
+        let tap = Self(self.0 & 0xFF);
+        let packed = match self.0 {
+            _ if self.is_modded() || self.is_mod_tap() => ((self.0 >> 8) & 0x1F) as u8,
+            _ => (self.0 & 0x1F) as u8,
+        };
+
+        match kc {
+            _ if self.is_modded() => {
+                let side = if packed & 0x10 != 0 { 'R' } else { 'L' };
+                let mut depth = 0;
+                for (bit, name) in MOD_NAMES.iter().enumerate() {
+                    if packed & (1 << bit) != 0 {
+                        write!(f, "{side}{name}(")?;
+                        depth += 1;
+                    }
+                }
+                write!(f, "{tap}")?;
+                for _ in 0..depth {
+                    f.write_str(")")?;
+                }
+                Ok(())
+            }
+            _ if self.is_mod_tap() => {
+                f.write_str("MT(")?;
+                write_mod_mask(f, packed)?;
+                write!(f, ", {tap})")
+            }
+            _ if self.is_layer_tap() => write!(f, "LT({layer}, {tap})"),
+            QK_LAYER_MOD..=QK_LAYER_MOD_MAX => {
+                write!(f, "LM({layer}, ")?;
+                write_mod_mask(f, packed)?;
+                f.write_str(")")
+            }
+            QK_ONE_SHOT_MOD..=QK_ONE_SHOT_MOD_MAX => {
+                f.write_str("OSM(")?;
+                write_mod_mask(f, packed)?;
+                f.write_str(")")
+            }
+            QK_TO..=QK_TO_MAX => write!(f, "TO({layer})"),
+            QK_MOMENTARY..=QK_MOMENTARY_MAX => write!(f, "MO({layer})"),
+            QK_DEF_LAYER..=QK_DEF_LAYER_MAX => write!(f, "DF({layer})"),
+            QK_TOGGLE_LAYER..=QK_TOGGLE_LAYER_MAX => write!(f, "TG({layer})"),
+            QK_ONE_SHOT_LAYER..=QK_ONE_SHOT_LAYER_MAX => write!(f, "OSL({layer})"),
+            QK_LAYER_TAP_TOGGLE..=QK_LAYER_TAP_TOGGLE_MAX => write!(f, "TT({layer})"),
+            QK_PERSISTENT_DEF_LAYER..=QK_PERSISTENT_DEF_LAYER_MAX => write!(f, "PDF({layer})"),
+            _ => write!(f, "0x{:04X}", self.0),
+        }
+    }
+}
+
+impl fmt::Debug for Keycode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Keycode({self})")
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ParseKeycodeError;
+
+impl fmt::Display for ParseKeycodeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("unknown keycode")
+    }
+}
+
+/// Parses a packed mod mask such as `MOD_LCTL | MOD_LSFT`.
+fn parse_mod_mask(s: &str) -> Result<u8, ParseKeycodeError> {
+    s.split('|').try_fold(0, |packed, part| {
+        let part = part.trim();
+        let name = part.strip_prefix("MOD_").ok_or(ParseKeycodeError)?;
+        let bits = match name {
+            "HYPR" => 0x0F,
+            "MEH" => 0x07,
+            _ => {
+                let (side, name) = name.split_at_checked(1).ok_or(ParseKeycodeError)?;
+                let bit = MOD_NAMES
+                    .iter()
+                    .position(|n| *n == name)
+                    .ok_or(ParseKeycodeError)?;
+                match side {
+                    "L" => 1 << bit,
+                    "R" => 0x10 | (1 << bit),
+                    _ => return Err(ParseKeycodeError),
+                }
+            }
+        };
+        Ok(packed | bits)
+    })
+}
+
+fn parse_layer(s: &str, max: u8) -> Result<u8, ParseKeycodeError> {
+    s.trim()
+        .parse()
+        .ok()
+        .filter(|layer| *layer <= max)
+        .ok_or(ParseKeycodeError)
+}
+
+fn basic_key(s: &str) -> Result<Keycode, ParseKeycodeError> {
+    Some(s.parse::<Keycode>()?)
+        .filter(|key| key.is_basic())
+        .ok_or(ParseKeycodeError)
+}
+
+impl FromStr for Keycode {
+    type Err = ParseKeycodeError;
+
+    /// Parses a keycode name or alias (`KC_ESC`), a hex value (`0x0029`) or one of the QMK
+    /// keycode functions (`LCTL(KC_A)`, `MO(1)`, `LT(1, KC_SPC)`, `MT(MOD_LSFT, KC_A)`, ...).
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        if let Some(keycode) = Self::from_name(s) {
+            return Ok(keycode);
+        }
+
+        if let Some(hex) = s.strip_prefix("0x").or_else(|| s.strip_prefix("0X")) {
+            return u16::from_str_radix(hex, 16)
+                .map(Self)
+                .map_err(|_| ParseKeycodeError);
+        }
+
+        let (function, args) = s
+            .strip_suffix(')')
+            .and_then(|s| s.split_once('('))
+            .ok_or(ParseKeycodeError)?;
+
+        let layer_key = |base: u32| parse_layer(args, 31).map(|l| Self::layer_function(base, l));
+        match function.trim() {
+            "TO" => layer_key(QK_TO),
+            "MO" => layer_key(QK_MOMENTARY),
+            "DF" => layer_key(QK_DEF_LAYER),
+            "TG" => layer_key(QK_TOGGLE_LAYER),
+            "OSL" => layer_key(QK_ONE_SHOT_LAYER),
+            "TT" => layer_key(QK_LAYER_TAP_TOGGLE),
+            "PDF" => layer_key(QK_PERSISTENT_DEF_LAYER),
+            "OSM" => Ok(Self::one_shot_mod(Mods::from_packed(parse_mod_mask(args)?))),
+            "LT" => {
+                let (layer, key) = args.split_once(',').ok_or(ParseKeycodeError)?;
+                let key = basic_key(key)?;
+                Ok(Self::layer_tap(parse_layer(layer, 15)?, key))
+            }
+            "LM" => {
+                let (layer, mods) = args.split_once(',').ok_or(ParseKeycodeError)?;
+                let mods = Mods::from_packed(parse_mod_mask(mods)?);
+                Ok(Self::layer_mod(parse_layer(layer, 15)?, mods))
+            }
+            "MT" => {
+                let (mods, key) = args.split_once(',').ok_or(ParseKeycodeError)?;
+                let mods = Mods::from_packed(parse_mod_mask(mods)?);
+                Ok(Self::mod_tap(mods, basic_key(key)?))
+            }
+            function => {
+                // modifier wrappers such as LCTL(..) or RSFT(..)
+                let (side, name) = function.split_at_checked(1).ok_or(ParseKeycodeError)?;
+                let bit = MOD_NAMES
+                    .iter()
+                    .position(|n| *n == name)
+                    .ok_or(ParseKeycodeError)?;
+                let mods = match side {
+                    "L" => Mods::from_bits_retain(1 << bit),
+                    "R" => Mods::from_bits_retain(1 << (bit + 4)),
+                    _ => return Err(ParseKeycodeError),
+                };
+                let key: Keycode = args.parse()?;
+                let existing = (key.0 >> 8) as u8;
+                if key.0 > QK_MODS_MAX as u16
+                    || (existing != 0 && (existing & 0x10 != 0) != (side == "R"))
+                {
+                    return Err(ParseKeycodeError);
+                }
+                Ok(key.with_mods(mods))
+            }
+        }
+    }
+}
@@ -0,0 +1,452 @@
+#[allow(ambiguous_glob_reexports)]
+pub use qmk_sys::qk_keycode_defines::*;
+pub use qmk_sys::qk_keycode_ranges::*;
+
+pub const QK_LCTL: u16 = 0x0100;
+pub const QK_LSFT: u16 = 0x0200;
+pub const QK_LALT: u16 = 0x0400;
+pub const QK_LGUI: u16 = 0x0800;
+pub const QK_RMODS_MIN: u16 = 0x1000;
+pub const QK_RCTL: u16 = 0x1100;
+pub const QK_RSFT: u16 = 0x1200;
+pub const QK_RALT: u16 = 0x1400;
+pub const QK_RGUI: u16 = 0x1800;
+
+// the 5-bit modifier form used inside keycodes, see `Mods::from_packed`
+pub const MOD_LCTL: u8 = 0x01;
+pub const MOD_LSFT: u8 = 0x02;
+pub const MOD_LALT: u8 = 0x04;
+pub const MOD_LGUI: u8 = 0x08;
+pub const MOD_RCTL: u8 = 0x11;
+pub const MOD_RSFT: u8 = 0x12;
+pub const MOD_RALT: u8 = 0x14;
+pub const MOD_RGUI: u8 = 0x18;
+pub const MOD_HYPR: u8 = MOD_LCTL | MOD_LSFT | MOD_LALT | MOD_LGUI;
+pub const MOD_MEH: u8 = MOD_LCTL | MOD_LSFT | MOD_LALT;
+
+pub use qmk_macro::keymap;
+
+mod keycode;
+mod names;
+pub use keycode::*;
+
+/// The layer or index given to `mo!` and friends, which take any integer or a `Layer` variant.
+/// It's widened first so values that don't fit a `u8` are rejected rather than wrapped.
+#[doc(hidden)]
+pub const fn layer_index(index: i128) -> u8 {
+    assert!(
+        index >= 0 && index <= u8::MAX as i128,
+        "layer and tap dance indexes must be 0-255"
+    );
+    index as u8
+}
+
+/// Gets a keycode from its name with no path, automatically casting it to u16.
+#[macro_export]
+macro_rules! key {
+    ($(_)*) => {
+        $crate::key!(KC_NO)
+    };
+
+    ($key:ident) => {
+        $crate::keys::$key as u16
+    };
+
+    ($key:expr) => {
+        $key as u16
+    };
+}
+
+/// Gets the uppercase equivalent of a keycode.
+#[macro_export]
+macro_rules! s {
+    ($key:expr) => {
+        $crate::mods!($crate::keys::MOD_LSFT, $key)
+    };
+}
+
+/// MO(layer) from QMK
+#[macro_export]
+macro_rules! mo {
+    ($layer:expr) => {
+        const {
+            let layer = $crate::keys::layer_index($layer as i128);
+            $crate::keys::Keycode::momentary(layer).raw()
+        }
+    };
+}
+
+/// TG(layer) from QMK
+#[macro_export]
+macro_rules! tg {
+    ($layer:expr) => {
+        const {
+            let layer = $crate::keys::layer_index($layer as i128);
+            $crate::keys::Keycode::toggle(layer).raw()
+        }
+    };
+}
+
+/// TO(layer) from QMK
+#[macro_export]
+macro_rules! to {
+    ($layer:expr) => {
+        const {
+            let layer = $crate::keys::layer_index($layer as i128);
+            $crate::keys::Keycode::to(layer).raw()
+        }
+    };
+}
+
+/// DF(layer) from QMK
+#[macro_export]
+macro_rules! df {
+    ($layer:expr) => {
+        const {
+            let layer = $crate::keys::layer_index($layer as i128);
+            $crate::keys::Keycode::default_layer(layer).raw()
+        }
+    };
+}
+
+/// PDF(layer) from QMK
+#[macro_export]
+macro_rules! pdf {
+    ($layer:expr) => {
+        const {
+            let layer = $crate::keys::layer_index($layer as i128);
+            $crate::keys::Keycode::persistent_default_layer(layer).raw()
+        }
+    };
+}
+
+/// OSL(layer) from QMK
+#[macro_export]
+macro_rules! osl {
+    ($layer:expr) => {
+        const {
+            let layer = $crate::keys::layer_index($layer as i128);
+            $crate::keys::Keycode::one_shot_layer(layer).raw()
+        }
+    };
+}
+
+/// TT(layer) from QMK, also known as `QK_LAYER_TAP_TOGGLE`
+#[macro_export]
+macro_rules! tt {
+    ($layer:expr) => {
+        const {
+            let layer = $crate::keys::layer_index($layer as i128);
+            $crate::keys::Keycode::layer_tap_toggle(layer).raw()
+        }
+    };
+}
+
+/// LT(layer, key) from QMK. The layer must be 0-15 and the key must be a basic keycode.
+#[macro_export]
+macro_rules! lt {
+    ($layer:expr, $key:expr) => {
+        const {
+            let layer = $crate::keys::layer_index($layer as i128);
+            $crate::keys::Keycode::layer_tap(layer, $crate::keys::Keycode::new($key as u16)).raw()
+        }
+    };
+}
+
+/// LM(layer, mods) from QMK, with `mods` as `MOD_*` constants. The layer must be 0-15.
+#[macro_export]
+macro_rules! lm {
+    ($layer:expr, $mods:expr) => {
+        const {
+            let layer = $crate::keys::layer_index($layer as i128);
+            $crate::keys::Keycode::layer_mod(layer, $crate::mods::Mods::from_packed($mods)).raw()
+        }
+    };
+}
+
+/// TD(index) from QMK, usually with a variant of the `TapDance` enum from `tap_dance!`
+#[macro_export]
+macro_rules! td {
+    ($index:expr) => {
+        const {
+            let index = $crate::keys::layer_index($index as i128);
+            $crate::keys::Keycode::tap_dance(index).raw()
+        }
+    };
+}
+
+/// MT(mods, key) from QMK, with `mods` as `MOD_*` constants. The key must be a basic keycode.
+#[macro_export]
+macro_rules! mt {
+    ($mods:expr, $key:expr) => {
+        const {
+            $crate::keys::Keycode::mod_tap(
+                $crate::mods::Mods::from_packed($mods),
+                $crate::keys::Keycode::new($key as u16),
+            )
+            .raw()
+        }
+    };
+}
+
+/// OSM(mods) from QMK, with `mods` as `MOD_*` constants.
+#[macro_export]
+macro_rules! osm {
+    ($mods:expr) => {
+        const { $crate::keys::Keycode::one_shot_mod($crate::mods::Mods::from_packed($mods)).raw() }
+    };
+}
+
+/// A key with modifiers held, with `mods` as `MOD_*` constants. This is what `lctl!` and friends
+/// expand to.
+#[macro_export]
+macro_rules! mods {
+    ($mods:expr, $key:expr) => {
+        const {
+            $crate::keys::Keycode::new($key as u16)
+                .with_mods($crate::mods::Mods::from_packed($mods))
+                .raw()
+        }
+    };
+}
+
+#[macro_export]
+macro_rules! c {
+    ($key:expr) => {
+        $crate::mods!($crate::keys::MOD_LCTL, $key)
+    };
+}
+
+/// LCTL(key) from QMK
+#[macro_export]
+macro_rules! lctl {
+    ($key:expr) => {
+        $crate::mods!($crate::keys::MOD_LCTL, $key)
+    };
+}
+
+/// LSFT(key) from QMK
+#[macro_export]
+macro_rules! lsft {
+    ($key:expr) => {
+        $crate::mods!($crate::keys::MOD_LSFT, $key)
+    };
+}
+
+/// LALT(key) from QMK
+#[macro_export]
+macro_rules! lalt {
+    ($key:expr) => {
+        $crate::mods!($crate::keys::MOD_LALT, $key)
+    };
+}
+
+/// LGUI(key) from QMK
+#[macro_export]
+macro_rules! lgui {
+    ($key:expr) => {
+        $crate::mods!($crate::keys::MOD_LGUI, $key)
+    };
+}
+
+/// RCTL(key) from QMK
+#[macro_export]
+macro_rules! rctl {
+    ($key:expr) => {
+        $crate::mods!($crate::keys::MOD_RCTL, $key)
+    };
+}
+
+/// RSFT(key) from QMK
+#[macro_export]
+macro_rules! rsft {
+    ($key:expr) => {
+        $crate::mods!($crate::keys::MOD_RSFT, $key)
+    };
+}
+
+/// RALT(key) from QMK
+#[macro_export]
+macro_rules! ralt {
+    ($key:expr) => {
+        $crate::mods!($crate::keys::MOD_RALT, $key)
+    };
+}
+
+/// RGUI(key) from QMK
+#[macro_export]
+macro_rules! rgui {
+    ($key:expr) => {
+        $crate::mods!($crate::keys::MOD_RGUI, $key)
+    };
+}
+
+/// HYPR(key) from QMK: left control, shift, alt and GUI.
+#[macro_export]
+macro_rules! hypr {
+    ($key:expr) => {
+        $crate::mods!($crate::keys::MOD_HYPR, $key)
+    };
+}
+
+/// MEH(key) from QMK: left control, shift and alt.
+#[macro_export]
+macro_rules! meh {
+    ($key:expr) => {
+        $crate::mods!($crate::keys::MOD_MEH, $key)
+    };
+}
+
+/// LCAG(key) from QMK: left control, alt and GUI.
+#[macro_export]
+macro_rules! lcag {
+    ($key:expr) => {
+        $crate::mods!(
+            $crate::keys::MOD_LCTL | $crate::keys::MOD_LALT | $crate::keys::MOD_LGUI,
+            $key
+        )
+    };
+}
+
+/// LSG(key) from QMK: left shift and GUI.
+#[macro_export]
+macro_rules! lsg {
+    ($key:expr) => {
+        $crate::mods!($crate::keys::MOD_LSFT | $crate::keys::MOD_LGUI, $key)
+    };
+}
+
+/// LAG(key) from QMK: left alt and GUI.
+#[macro_export]
+macro_rules! lag {
+    ($key:expr) => {
+        $crate::mods!($crate::keys::MOD_LALT | $crate::keys::MOD_LGUI, $key)
+    };
+}
+
+/// LCS(key) from QMK: left control and shift.
+#[macro_export]
+macro_rules! lcs {
+    ($key:expr) => {
+        $crate::mods!($crate::keys::MOD_LCTL | $crate::keys::MOD_LSFT, $key)
+    };
+}
+
+/// LCA(key) from QMK: left control and alt.
+#[macro_export]
+macro_rules! lca {
+    ($key:expr) => {
+        $crate::mods!($crate::keys::MOD_LCTL | $crate::keys::MOD_LALT, $key)
+    };
+}
+
+/// LSA(key) from QMK: left shift and alt.
+#[macro_export]
+macro_rules! lsa {
+    ($key:expr) => {
+        $crate::mods!($crate::keys::MOD_LSFT | $crate::keys::MOD_LALT, $key)
+    };
+}
+
+/// RSG(key) from QMK: right shift and GUI.
+#[macro_export]
+macro_rules! rsg {
+    ($key:expr) => {
+        $crate::mods!($crate::keys::MOD_RSFT | $crate::keys::MOD_RGUI, $key)
+    };
+}
+
+/// RAG(key) from QMK: right alt and GUI.
+#[macro_export]
+macro_rules! rag {
+    ($key:expr) => {
+        $crate::mods!($crate::keys::MOD_RALT | $crate::keys::MOD_RGUI, $key)
+    };
+}
+
+/// RCS(key) from QMK: right control and shift.
+#[macro_export]
+macro_rules! rcs {
+    ($key:expr) => {
+        $crate::mods!($crate::keys::MOD_RCTL | $crate::keys::MOD_RSFT, $key)
+    };
+}
+
+/// RSA(key) from QMK, also known as SAGR: right shift and alt.
+#[macro_export]
+macro_rules! rsa {
+    ($key:expr) => {
+        $crate::mods!($crate::keys::MOD_RSFT | $crate::keys::MOD_RALT, $key)
+    };
+}
+
+pub const KC_TILD: u16 = s!(key!(KC_GRV));
+pub const KC_EXLM: u16 = s!(key!(KC_1));
+pub const KC_AT: u16 = s!(key!(KC_2));
+pub const KC_HASH: u16 = s!(key!(KC_3));
+pub const KC_DLR: u16 = s!(key!(KC_4));
+pub const KC_PERC: u16 = s!(key!(KC_5));
+pub const KC_CIRC: u16 = s!(key!(KC_6));
+pub const KC_AMPR: u16 = s!(key!(KC_7));
+pub const KC_ASTR: u16 = s!(key!(KC_8));
+pub const KC_LPRN: u16 = s!(key!(KC_9));
+pub const KC_RPRN: u16 = s!(key!(KC_0));
+pub const KC_UNDS: u16 = s!(key!(KC_MINUS));
+pub const KC_PLUS: u16 = s!(key!(KC_EQUAL));
+pub const KC_LCBR: u16 = s!(key!(KC_LEFT_BRACKET));
+pub const KC_RCBR: u16 = s!(key!(KC_RIGHT_BRACKET));
+pub const KC_PIPE: u16 = s!(key!(KC_BACKSLASH));
+pub const KC_COLN: u16 = s!(key!(KC_SEMICOLON));
+pub const KC_DQUO: u16 = s!(key!(KC_QUOTE));
+pub const KC_LABK: u16 = s!(key!(KC_COMMA));
+pub const KC_RABK: u16 = s!(key!(KC_DOT));
+pub const KC_QUES: u16 = s!(key!(KC_SLASH));
+
+pub const KC_TILDE: u16 = KC_TILD;
+pub const KC_EXCLAIM: u16 = KC_EXLM;
+pub const KC_DOLLAR: u16 = KC_DLR;
+pub const KC_PERCENT: u16 = KC_PERC;
+pub const KC_CIRCUMFLEX: u16 = KC_CIRC;
+pub const KC_AMPERSAND: u16 = KC_AMPR;
+pub const KC_ASTERISK: u16 = KC_ASTR;
+pub const KC_LEFT_PAREN: u16 = KC_LPRN;
+pub const KC_RIGHT_PAREN: u16 = KC_RPRN;
+pub const KC_UNDERSCORE: u16 = KC_UNDS;
+pub const KC_LEFT_CURLY_BRACE: u16 = KC_LCBR;
+pub const KC_RIGHT_CURLY_BRACE: u16 = KC_RCBR;
+pub const KC_COLON: u16 = KC_COLN;
+pub const KC_DOUBLE_QUOTE: u16 = KC_DQUO;
+pub const KC_DQT: u16 = KC_DQUO;
+pub const KC_LEFT_ANGLE_BRACKET: u16 = KC_LABK;
+pub const KC_LT: u16 = KC_LABK;
+pub const KC_RIGHT_ANGLE_BRACKET: u16 = KC_RABK;
+pub const KC_GT: u16 = KC_RABK;
+pub const KC_QUESTION: u16 = KC_QUES;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn layer_macros_check_the_range() {
+        assert_eq!(mo!(3), QK_MOMENTARY as u16 | 3);
+        assert_eq!(
+            lt!(2u32, KC_A),
+            QK_LAYER_TAP as u16 | (2 << 8) | KC_A as u16
+        );
+        assert_eq!(layer_index(255), 255);
+    }
+
+    #[test]
+    #[should_panic(expected = "layer and tap dance indexes must be 0-255")]
+    fn layer_index_rejects_overflow() {
+        // `256 as u8` would be layer 0
+        layer_index(256);
+    }
+
+    #[test]
+    #[should_panic(expected = "layer and tap dance indexes must be 0-255")]
+    fn layer_index_rejects_negative() {
+        layer_index(-1i32 as i128);
+    }
+}
@@ -0,0 +1,2 @@
+// Generated from QMK's data/constants/keycodes at build time.
+qmk_macro::keycode_table!();
@@ -1,29 +1,645 @@
-use alloc::string::String;
+use alloc::{collections::VecDeque, string::String, vec::Vec};
+use core::{
+    cell::RefCell,
+    fmt::{self, Write},
+    sync::atomic::{AtomicU8, Ordering},
+};
+use critical_section::{Mutex, with};
 
-#[macro_export]
-macro_rules! qmk_log {
-    ($($arg:tt)*) => {
-        $crate::logging::printf(::alloc::format!($($arg)*));
-    };
+use crate::time::Instant;
+
+/// How many log lines are kept in RAM for `Logger::records`.
+pub const LOG_LINES: usize = 32;
+/// Longer messages are cut off at this many bytes in the ring buffer (but not on the console).
+pub const LOG_LINE_LENGTH: usize = 64;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[repr(u8)]
+pub enum Level {
+    Error = 1,
+    Warn,
+    Info,
+    Debug,
+    Trace,
 }
 
-#[cfg(not(target_arch = "wasm32"))]
-pub fn printf(s: impl Into<String>) {
-    let s = s.into();
-    for char in s.chars() {
-        unsafe {
-            qmk_sys::sendchar(char as u8);
+impl Level {
+    pub const fn as_str(self) -> &'static str {
+        match self {
+            Level::Error => "ERROR",
+            Level::Warn => "WARN",
+            Level::Info => "INFO",
+            Level::Debug => "DEBUG",
+            Level::Trace => "TRACE",
+        }
+    }
+
+    /// The first letter of the level, for narrow screens.
+    pub const fn as_char(self) -> char {
+        match self {
+            Level::Error => 'E',
+            Level::Warn => 'W',
+            Level::Info => 'I',
+            Level::Debug => 'D',
+            Level::Trace => 'T',
+        }
+    }
+
+    const fn from_u8(value: u8) -> Option<Level> {
+        match value {
+            1 => Some(Level::Error),
+            2 => Some(Level::Warn),
+            3 => Some(Level::Info),
+            4 => Some(Level::Debug),
+            5 => Some(Level::Trace),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for Level {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// The most verbose level compiled in, picked with the `max_level_*` features. Calls above it are
+/// removed entirely, format strings included.
+pub const STATIC_MAX_LEVEL: Option<Level> = if cfg!(feature = "max_level_off") {
+    None
+} else if cfg!(feature = "max_level_error") {
+    Some(Level::Error)
+} else if cfg!(feature = "max_level_warn") {
+    Some(Level::Warn)
+} else if cfg!(feature = "max_level_info") {
+    Some(Level::Info)
+} else if cfg!(feature = "max_level_debug") {
+    Some(Level::Debug)
+} else {
+    Some(Level::Trace)
+};
+
+/// Whether `level` survives `STATIC_MAX_LEVEL`. Used by the logging macros.
+#[doc(hidden)]
+pub const fn static_enabled(level: Level) -> bool {
+    match STATIC_MAX_LEVEL {
+        Some(max) => level as u8 <= max as u8,
+        None => false,
+    }
+}
+
+/// The id a format string is sent as in deferred mode: its 32-bit FNV-1a hash. The build script
+/// hashes the same strings into the table the host decoder reads.
+pub const fn string_id(format: &str) -> u32 {
+    let bytes = format.as_bytes();
+    let mut hash: u32 = 0x811c_9dc5;
+    let mut i = 0;
+    while i < bytes.len() {
+        hash ^= bytes[i] as u32;
+        hash = hash.wrapping_mul(0x0100_0193);
+        i += 1;
+    }
+    hash
+}
+
+/// How many `{}` placeholders a format string has, not counting `{{` escapes. Deferred mode
+/// checks this against the number of arguments, since it can't capture `{name}` from scope.
+#[doc(hidden)]
+pub const fn placeholder_count(format: &str) -> usize {
+    let bytes = format.as_bytes();
+    let mut count = 0;
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] == b'{' {
+            if i + 1 < bytes.len() && bytes[i + 1] == b'{' {
+                i += 1;
+            } else {
+                count += 1;
+            }
         }
+        i += 1;
+    }
+    count
+}
+
+/// One line kept in the ring buffer.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Record {
+    pub level: Level,
+    /// The module the line was logged from, unless the call gave its own `target:`.
+    pub target: &'static str,
+    pub time: Instant,
+    pub message: String,
+}
+
+struct LogBuffer {
+    records: VecDeque<Record>,
+    total: u32,
+}
+
+static LOG: Mutex<RefCell<LogBuffer>> = Mutex::new(RefCell::new(LogBuffer {
+    records: VecDeque::new(),
+    total: 0,
+}));
+static MAX_LEVEL: AtomicU8 = AtomicU8::new(Level::Trace as u8);
+
+/// Runtime control over logging, and the ring buffer of recent lines.
+pub struct Logger;
+
+impl Logger {
+    /// Drops everything more verbose than `level` (or everything, for `None`) until changed
+    /// again. This can only narrow `STATIC_MAX_LEVEL`, not widen it.
+    pub fn set_max_level(level: Option<Level>) {
+        MAX_LEVEL.store(level.map_or(0, |level| level as u8), Ordering::SeqCst);
+    }
+
+    pub fn max_level() -> Option<Level> {
+        Level::from_u8(MAX_LEVEL.load(Ordering::SeqCst))
+    }
+
+    pub fn enabled(level: Level) -> bool {
+        static_enabled(level) && level as u8 <= MAX_LEVEL.load(Ordering::SeqCst)
+    }
+
+    /// The last `LOG_LINES` lines, oldest first. Always empty on the keyboard with the
+    /// `deferred_log` feature, which only sends lines to the console.
+    pub fn records() -> Vec<Record> {
+        with(|cs| LOG.borrow_ref(cs).records.iter().cloned().collect())
+    }
+
+    /// How many lines have been logged since boot, including ones that fell out of the buffer.
+    pub fn count() -> u32 {
+        with(|cs| LOG.borrow_ref(cs).total)
+    }
+
+    pub fn clear() {
+        with(|cs| LOG.borrow_ref_mut(cs).records.clear());
+    }
+}
+
+/// Writes a line to the console and the ring buffer. Use the logging macros instead.
+#[doc(hidden)]
+pub fn log(level: Level, target: &'static str, args: fmt::Arguments) {
+    if !Logger::enabled(level) {
+        return;
     }
 
-    unsafe {
-        qmk_sys::sendchar(b'\n');
+    let mut message = String::new();
+    let _ = message.write_fmt(args);
+
+    write_console(level, target, &message);
+
+    if message.len() > LOG_LINE_LENGTH {
+        let mut end = LOG_LINE_LENGTH;
+        while !message.is_char_boundary(end) {
+            end -= 1;
+        }
+        message.truncate(end);
     }
+
+    push_record(Record {
+        level,
+        target,
+        time: Instant::now(),
+        message,
+    });
+}
+
+fn push_record(record: Record) {
+    with(|cs| {
+        let mut log = LOG.borrow_ref_mut(cs);
+        if log.records.len() >= LOG_LINES {
+            log.records.pop_front();
+        }
+        log.records.push_back(record);
+        log.total = log.total.wrapping_add(1);
+    });
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn write_console(level: Level, target: &str, message: &str) {
+    let mut console = Console;
+    let _ = writeln!(console, "{} {}: {}", level.as_char(), target, message);
 }
 
 #[cfg(target_arch = "wasm32")]
-pub fn printf(s: impl Into<String>) {
+fn write_console(level: Level, target: &str, message: &str) {
     use web_sys::console;
-    let s = s.into();
-    console::log_1(&s.into());
+
+    let line = js_sys::JsString::from(alloc::format!("{}: {}", target, message));
+    match level {
+        Level::Error => console::error_1(&line),
+        Level::Warn => console::warn_1(&line),
+        Level::Info => console::info_1(&line),
+        Level::Debug | Level::Trace => console::debug_1(&line),
+    }
+}
+
+// QMK's console, one byte at a time
+#[cfg(not(target_arch = "wasm32"))]
+struct Console;
+
+#[cfg(not(target_arch = "wasm32"))]
+impl Write for Console {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        for byte in s.bytes() {
+            unsafe {
+                qmk_sys::sendchar(byte);
+            }
+        }
+        Ok(())
+    }
+}
+
+// argument tags in a deferred frame, mirrored by the `log_decoder` crate
+const TAG_UNSIGNED: u8 = 1;
+const TAG_SIGNED: u8 = 2;
+const TAG_F32: u8 = 3;
+const TAG_F64: u8 = 4;
+const TAG_BOOL: u8 = 5;
+const TAG_CHAR: u8 = 6;
+const TAG_STR: u8 = 7;
+const TAG_FORMATTED: u8 = 8;
+
+/// A log line in deferred mode (the `deferred_log` feature), before it is sent.
+///
+/// Instead of formatting on the keyboard, the line goes out as the level, the id of its format
+/// string (see `string_id`), the time in milliseconds as a varint and then each argument as a tag
+/// byte and its value. Integers are LEB128 varints (zigzagged if signed), floats are
+/// little-endian and strings are a varint length and UTF-8. The frame is COBS-encoded and ended
+/// with a zero byte, so the host can pick up mid-stream.
+pub struct Frame {
+    level: Level,
+    id: u32,
+    bytes: Vec<u8>,
+}
+
+impl Frame {
+    pub fn new(level: Level, id: u32) -> Self {
+        let mut frame = Self {
+            level,
+            id,
+            bytes: Vec::with_capacity(16),
+        };
+        frame.bytes.push(level as u8);
+        frame.bytes.extend_from_slice(&id.to_le_bytes());
+        frame.write_varint(Instant::now().as_millis() as u64);
+        frame
+    }
+
+    pub fn level(&self) -> Level {
+        self.level
+    }
+
+    pub fn id(&self) -> u32 {
+        self.id
+    }
+
+    /// The frame before COBS encoding.
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.bytes
+    }
+
+    pub fn write_unsigned(&mut self, value: u64) {
+        self.bytes.push(TAG_UNSIGNED);
+        self.write_varint(value);
+    }
+
+    pub fn write_signed(&mut self, value: i64) {
+        self.bytes.push(TAG_SIGNED);
+        self.write_varint(((value << 1) ^ (value >> 63)) as u64);
+    }
+
+    pub fn write_f32(&mut self, value: f32) {
+        self.bytes.push(TAG_F32);
+        self.bytes.extend_from_slice(&value.to_le_bytes());
+    }
+
+    pub fn write_f64(&mut self, value: f64) {
+        self.bytes.push(TAG_F64);
+        self.bytes.extend_from_slice(&value.to_le_bytes());
+    }
+
+    pub fn write_bool(&mut self, value: bool) {
+        self.bytes.push(TAG_BOOL);
+        self.bytes.push(value as u8);
+    }
+
+    pub fn write_char(&mut self, value: char) {
+        self.bytes.push(TAG_CHAR);
+        self.write_varint(value as u64);
+    }
+
+    pub fn write_str(&mut self, value: &str) {
+        self.bytes.push(TAG_STR);
+        self.write_varint(value.len() as u64);
+        self.bytes.extend_from_slice(value.as_bytes());
+    }
+
+    /// Text formatted on the keyboard after all, which the host shows as-is whatever the
+    /// placeholder asks for. Used for arguments that only implement `Debug`.
+    pub fn write_formatted(&mut self, args: fmt::Arguments) {
+        let mut text = String::new();
+        let _ = text.write_fmt(args);
+        self.bytes.push(TAG_FORMATTED);
+        self.write_varint(text.len() as u64);
+        self.bytes.extend_from_slice(text.as_bytes());
+    }
+
+    fn write_varint(&mut self, mut value: u64) {
+        loop {
+            let byte = (value & 0x7f) as u8;
+            value >>= 7;
+            if value == 0 {
+                self.bytes.push(byte);
+                return;
+            }
+            self.bytes.push(byte | 0x80);
+        }
+    }
+
+    /// The frame as it goes over the wire: COBS-encoded, ending in a zero byte.
+    pub fn to_cobs(&self) -> Vec<u8> {
+        let mut out = Vec::with_capacity(self.bytes.len() + self.bytes.len() / 254 + 2);
+        let mut code_index = 0;
+        out.push(0);
+        for &byte in &self.bytes {
+            if byte != 0 {
+                out.push(byte);
+            }
+            if byte == 0 || out.len() - code_index == 0xff {
+                out[code_index] = (out.len() - code_index) as u8;
+                code_index = out.len();
+                out.push(0);
+            }
+        }
+        out[code_index] = (out.len() - code_index) as u8;
+        out.push(0);
+        out
+    }
+
+    /// Sends the frame to the console. It's counted, but stays out of the ring buffer, since an
+    /// id is no use on the keyboard. Use the logging macros instead.
+    #[doc(hidden)]
+    pub fn send(self) {
+        #[cfg(not(target_arch = "wasm32"))]
+        for byte in self.to_cobs() {
+            unsafe {
+                qmk_sys::sendchar(byte);
+            }
+        }
+
+        with(|cs| {
+            let mut log = LOG.borrow_ref_mut(cs);
+            log.total = log.total.wrapping_add(1);
+        });
+    }
+}
+
+/// An argument deferred mode can send without formatting it on the keyboard. Arguments that
+/// don't implement this are sent `Debug`-formatted instead.
+pub trait Encode {
+    fn encode(&self, frame: &mut Frame);
+}
+
+macro_rules! encode_as {
+    ($method:ident, $as:ty, $($ty:ty),+) => {
+        $(impl Encode for $ty {
+            fn encode(&self, frame: &mut Frame) {
+                frame.$method(*self as $as);
+            }
+        })+
+    };
+}
+
+encode_as!(write_unsigned, u64, u8, u16, u32, u64, usize);
+encode_as!(write_signed, i64, i8, i16, i32, i64, isize);
+encode_as!(write_f32, f32, f32);
+encode_as!(write_f64, f64, f64);
+encode_as!(write_bool, bool, bool);
+encode_as!(write_char, char, char);
+
+impl Encode for str {
+    fn encode(&self, frame: &mut Frame) {
+        frame.write_str(self);
+    }
+}
+
+impl Encode for String {
+    fn encode(&self, frame: &mut Frame) {
+        frame.write_str(self);
+    }
+}
+
+impl<T: Encode + ?Sized> Encode for &T {
+    fn encode(&self, frame: &mut Frame) {
+        (**self).encode(frame);
+    }
+}
+
+// Picks `Encode` over `Debug` for macro arguments by autoref: `(&Arg(&x)).encode_arg(..)` finds
+// `EncodeArg` on `Arg<T>` first and only falls back to `EncodeDebug` on `&Arg<T>`.
+#[doc(hidden)]
+pub struct Arg<'a, T: ?Sized>(pub &'a T);
+
+#[doc(hidden)]
+pub trait EncodeArg {
+    fn encode_arg(&self, frame: &mut Frame);
+}
+
+impl<T: Encode + ?Sized> EncodeArg for Arg<'_, T> {
+    fn encode_arg(&self, frame: &mut Frame) {
+        self.0.encode(frame);
+    }
+}
+
+#[doc(hidden)]
+pub trait EncodeDebug {
+    fn encode_arg(&self, frame: &mut Frame);
+}
+
+impl<T: fmt::Debug + ?Sized> EncodeDebug for &Arg<'_, T> {
+    fn encode_arg(&self, frame: &mut Frame) {
+        frame.write_formatted(format_args!("{:?}", self.0));
+    }
+}
+
+/// Logs at the given level, with an optional `target:` (the module path by default).
+/// ```rust
+/// use qmk::{log, logging::Level};
+///
+/// fn layer_changed(layer: u8) {
+///     log!(Level::Info, "layer {} on", layer);
+///     log!(target: "rgb", Level::Debug, "{} leds", 72);
+/// }
+/// ```
+///
+/// With the `deferred_log` feature the keyboard sends a `Frame` instead of text, and the format
+/// string has to be a literal with one argument per placeholder.
+#[macro_export]
+macro_rules! log {
+    (target: $target:expr, $level:expr, $($arg:tt)+) => {{
+        let level: $crate::logging::Level = $level;
+        if $crate::logging::static_enabled(level) {
+            $crate::__log!(level, $target, $($arg)+);
+        }
+    }};
+    ($level:expr, $($arg:tt)+) => {
+        $crate::log!(target: ::core::module_path!(), $level, $($arg)+)
+    };
+}
+
+#[cfg(not(all(feature = "deferred_log", not(target_arch = "wasm32"))))]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __log {
+    ($level:expr, $target:expr, $($arg:tt)+) => {
+        $crate::logging::log($level, $target, ::core::format_args!($($arg)+))
+    };
+}
+
+#[cfg(all(feature = "deferred_log", not(target_arch = "wasm32")))]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __log {
+    ($level:expr, $target:expr, $format:literal $(, $arg:expr)* $(,)?) => {{
+        const ID: u32 = $crate::logging::string_id($format);
+        const _: () = ::core::assert!(
+            $crate::logging::placeholder_count($format)
+                == <[&str]>::len(&[$(::core::stringify!($arg)),*]),
+            "deferred logging needs one argument per placeholder"
+        );
+        let level = $level;
+        if $crate::logging::Logger::enabled(level) {
+            #[allow(unused_imports)]
+            use $crate::logging::{EncodeArg as _, EncodeDebug as _};
+            #[allow(unused_mut)]
+            let mut frame = $crate::logging::Frame::new(level, ID);
+            $((&$crate::logging::Arg(&$arg)).encode_arg(&mut frame);)*
+            frame.send();
+        }
+    }};
+}
+
+#[macro_export]
+macro_rules! error {
+    (target: $target:expr, $($arg:tt)+) => {
+        $crate::log!(target: $target, $crate::logging::Level::Error, $($arg)+)
+    };
+    ($($arg:tt)+) => {
+        $crate::log!($crate::logging::Level::Error, $($arg)+)
+    };
+}
+
+#[macro_export]
+macro_rules! warn {
+    (target: $target:expr, $($arg:tt)+) => {
+        $crate::log!(target: $target, $crate::logging::Level::Warn, $($arg)+)
+    };
+    ($($arg:tt)+) => {
+        $crate::log!($crate::logging::Level::Warn, $($arg)+)
+    };
+}
+
+#[macro_export]
+macro_rules! info {
+    (target: $target:expr, $($arg:tt)+) => {
+        $crate::log!(target: $target, $crate::logging::Level::Info, $($arg)+)
+    };
+    ($($arg:tt)+) => {
+        $crate::log!($crate::logging::Level::Info, $($arg)+)
+    };
+}
+
+#[macro_export]
+macro_rules! debug {
+    (target: $target:expr, $($arg:tt)+) => {
+        $crate::log!(target: $target, $crate::logging::Level::Debug, $($arg)+)
+    };
+    ($($arg:tt)+) => {
+        $crate::log!($crate::logging::Level::Debug, $($arg)+)
+    };
+}
+
+#[macro_export]
+macro_rules! trace {
+    (target: $target:expr, $($arg:tt)+) => {
+        $crate::log!(target: $target, $crate::logging::Level::Trace, $($arg)+)
+    };
+    ($($arg:tt)+) => {
+        $crate::log!($crate::logging::Level::Trace, $($arg)+)
+    };
+}
+
+/// Logs at `Info`. Kept from before there were levels.
+#[macro_export]
+macro_rules! qmk_log {
+    ($($arg:tt)*) => {
+        $crate::info!($($arg)*)
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn string_ids_are_fnv1a() {
+        assert_eq!(string_id(""), 0x811c_9dc5);
+        assert_eq!(string_id("a"), 0xe40c_292c);
+        assert_eq!(string_id("foobar"), 0xbf9c_f968);
+    }
+
+    #[test]
+    fn placeholders_skip_escapes() {
+        assert_eq!(placeholder_count("layer {} is {:?}"), 2);
+        assert_eq!(placeholder_count("{{literal}} {:#04x}"), 1);
+        assert_eq!(placeholder_count("none"), 0);
+    }
+
+    #[test]
+    fn frames_are_cobs_encoded() {
+        // info, id 0x100, 1234ms, unsigned 2, str "on"
+        let frame = Frame {
+            level: Level::Info,
+            id: 0x100,
+            bytes: [
+                3,
+                0,
+                1,
+                0,
+                0,
+                0xd2,
+                0x09,
+                TAG_UNSIGNED,
+                2,
+                TAG_STR,
+                2,
+                b'o',
+                b'n',
+            ]
+            .to_vec(),
+        };
+        // what `log_decoder`'s `decodes_a_captured_frame` reads back
+        assert_eq!(
+            frame.to_cobs(),
+            [
+                2,
+                3,
+                2,
+                1,
+                1,
+                9,
+                0xd2,
+                0x09,
+                TAG_UNSIGNED,
+                2,
+                TAG_STR,
+                2,
+                b'o',
+                b'n',
+                0
+            ]
+        );
+    }
 }
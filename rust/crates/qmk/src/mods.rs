@@ -0,0 +1,251 @@
+use bitflags::bitflags;
+
+#[cfg(target_arch = "wasm32")]
+mod bindings {
+    use core::sync::atomic::{AtomicU8, Ordering};
+
+    // the browser has no host, so modifier state just lives here
+    macro_rules! simulated_mods {
+        ($state:ident, $get:ident, $add:ident, $del:ident, $set:ident, $clear:ident) => {
+            static $state: AtomicU8 = AtomicU8::new(0);
+
+            pub unsafe fn $get() -> u8 {
+                $state.load(Ordering::SeqCst)
+            }
+
+            pub unsafe fn $add(mods: u8) {
+                $state.fetch_or(mods, Ordering::SeqCst);
+            }
+
+            pub unsafe fn $del(mods: u8) {
+                $state.fetch_and(!mods, Ordering::SeqCst);
+            }
+
+            pub unsafe fn $set(mods: u8) {
+                $state.store(mods, Ordering::SeqCst);
+            }
+
+            pub unsafe fn $clear() {
+                $state.store(0, Ordering::SeqCst);
+            }
+        };
+    }
+
+    simulated_mods!(MODS, get_mods, add_mods, del_mods, set_mods, clear_mods);
+    simulated_mods!(
+        WEAK_MODS,
+        get_weak_mods,
+        add_weak_mods,
+        del_weak_mods,
+        set_weak_mods,
+        clear_weak_mods
+    );
+    simulated_mods!(
+        ONESHOT_MODS,
+        get_oneshot_mods,
+        add_oneshot_mods,
+        del_oneshot_mods,
+        set_oneshot_mods,
+        clear_oneshot_mods
+    );
+    simulated_mods!(
+        ONESHOT_LOCKED_MODS,
+        get_oneshot_locked_mods,
+        add_oneshot_locked_mods,
+        del_oneshot_locked_mods,
+        set_oneshot_locked_mods,
+        clear_oneshot_locked_mods
+    );
+
+    pub unsafe fn has_oneshot_mods_timed_out() -> bool {
+        false
+    }
+
+    pub unsafe fn send_keyboard_report() {}
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+mod bindings {
+    pub use qmk_sys::{
+        add_mods, add_oneshot_locked_mods, add_oneshot_mods, add_weak_mods, clear_mods,
+        clear_oneshot_locked_mods, clear_oneshot_mods, clear_weak_mods, del_mods,
+        del_oneshot_locked_mods, del_oneshot_mods, del_weak_mods, get_mods,
+        get_oneshot_locked_mods, get_oneshot_mods, get_weak_mods, has_oneshot_mods_timed_out,
+        send_keyboard_report, set_mods, set_oneshot_locked_mods, set_oneshot_mods, set_weak_mods,
+    };
+}
+
+bitflags! {
+    /// The 8-bit modifier mask QMK uses for `get_mods` and friends.
+    #[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+    pub struct Mods: u8 {
+        const LCTL = 0x01;
+        const LSFT = 0x02;
+        const LALT = 0x04;
+        const LGUI = 0x08;
+        const RCTL = 0x10;
+        const RSFT = 0x20;
+        const RALT = 0x40;
+        const RGUI = 0x80;
+
+        const CTRL = Self::LCTL.bits() | Self::RCTL.bits();
+        const SHIFT = Self::LSFT.bits() | Self::RSFT.bits();
+        const ALT = Self::LALT.bits() | Self::RALT.bits();
+        const GUI = Self::LGUI.bits() | Self::RGUI.bits();
+    }
+}
+
+impl Mods {
+    /// Converts from the 5-bit form used inside keycodes (`MOD_LCTL`, `MOD_RSFT`, ...), where bit 4
+    /// makes the whole set right-handed.
+    ///
+    /// # Panics
+    /// If `packed` has bits set above the low five.
+    pub const fn from_packed(packed: u8) -> Self {
+        assert!(packed <= 0x1F, "packed modifiers only use the low 5 bits");
+        let bits = packed & 0x0F;
+        if packed & 0x10 != 0 {
+            Self::from_bits_retain(bits << 4)
+        } else {
+            Self::from_bits_retain(bits)
+        }
+    }
+
+    /// Converts to the 5-bit form used inside keycodes. That form can't mix left and right
+    /// modifiers, so any right-hand modifier makes the whole set right-handed.
+    pub const fn to_packed(self) -> u8 {
+        let left = self.bits() & 0x0F;
+        let right = self.bits() >> 4;
+        if right != 0 {
+            0x10 | right | left
+        } else {
+            left
+        }
+    }
+
+    /// Returns true if either control key is held.
+    pub const fn ctrl(&self) -> bool {
+        self.intersects(Self::CTRL)
+    }
+
+    /// Returns true if either shift key is held.
+    pub const fn shift(&self) -> bool {
+        self.intersects(Self::SHIFT)
+    }
+
+    /// Returns true if either alt key is held.
+    pub const fn alt(&self) -> bool {
+        self.intersects(Self::ALT)
+    }
+
+    /// Returns true if either GUI (super/cmd) key is held.
+    pub const fn gui(&self) -> bool {
+        self.intersects(Self::GUI)
+    }
+}
+
+/// Access to QMK's modifier state.
+///
+/// Changes made through `set`, `add` and `del` are only seen by the host on the next keyboard
+/// report, so call `Modifiers::send` afterwards if nothing else will.
+pub struct Modifiers;
+
+impl Modifiers {
+    /// The real (physically held or registered) modifiers.
+    pub fn get() -> Mods {
+        Mods::from_bits_retain(unsafe { bindings::get_mods() })
+    }
+
+    pub fn set(mods: Mods) {
+        unsafe { bindings::set_mods(mods.bits()) }
+    }
+
+    pub fn add(mods: Mods) {
+        unsafe { bindings::add_mods(mods.bits()) }
+    }
+
+    pub fn del(mods: Mods) {
+        unsafe { bindings::del_mods(mods.bits()) }
+    }
+
+    pub fn clear() {
+        unsafe { bindings::clear_mods() }
+    }
+
+    /// Weak modifiers only apply to the next key sent, eg. the shift in `KC_EXLM`.
+    pub fn weak() -> Mods {
+        Mods::from_bits_retain(unsafe { bindings::get_weak_mods() })
+    }
+
+    pub fn set_weak(mods: Mods) {
+        unsafe { bindings::set_weak_mods(mods.bits()) }
+    }
+
+    pub fn add_weak(mods: Mods) {
+        unsafe { bindings::add_weak_mods(mods.bits()) }
+    }
+
+    pub fn del_weak(mods: Mods) {
+        unsafe { bindings::del_weak_mods(mods.bits()) }
+    }
+
+    pub fn clear_weak() {
+        unsafe { bindings::clear_weak_mods() }
+    }
+
+    /// One-shot modifiers waiting to be applied to the next key press.
+    pub fn oneshot() -> Mods {
+        Mods::from_bits_retain(unsafe { bindings::get_oneshot_mods() })
+    }
+
+    pub fn set_oneshot(mods: Mods) {
+        unsafe { bindings::set_oneshot_mods(mods.bits()) }
+    }
+
+    pub fn add_oneshot(mods: Mods) {
+        unsafe { bindings::add_oneshot_mods(mods.bits()) }
+    }
+
+    pub fn del_oneshot(mods: Mods) {
+        unsafe { bindings::del_oneshot_mods(mods.bits()) }
+    }
+
+    pub fn clear_oneshot() {
+        unsafe { bindings::clear_oneshot_mods() }
+    }
+
+    pub fn oneshot_timed_out() -> bool {
+        unsafe { bindings::has_oneshot_mods_timed_out() }
+    }
+
+    /// One-shot modifiers that have been locked by tapping them repeatedly.
+    pub fn oneshot_locked() -> Mods {
+        Mods::from_bits_retain(unsafe { bindings::get_oneshot_locked_mods() })
+    }
+
+    pub fn set_oneshot_locked(mods: Mods) {
+        unsafe { bindings::set_oneshot_locked_mods(mods.bits()) }
+    }
+
+    pub fn add_oneshot_locked(mods: Mods) {
+        unsafe { bindings::add_oneshot_locked_mods(mods.bits()) }
+    }
+
+    pub fn del_oneshot_locked(mods: Mods) {
+        unsafe { bindings::del_oneshot_locked_mods(mods.bits()) }
+    }
+
+    pub fn clear_oneshot_locked() {
+        unsafe { bindings::clear_oneshot_locked_mods() }
+    }
+
+    /// Everything that will be applied to the next key: real, weak and one-shot modifiers.
+    pub fn effective() -> Mods {
+        Self::get() | Self::weak() | Self::oneshot() | Self::oneshot_locked()
+    }
+
+    /// Sends a keyboard report so the host sees the current modifier state.
+    pub fn send() {
+        unsafe { bindings::send_keyboard_report() }
+    }
+}
@@ -0,0 +1,199 @@
+use crate::{keys::Keycode, mods::Mods};
+use core::{
+    cell::RefCell,
+    sync::atomic::{AtomicU8, Ordering},
+};
+use critical_section::{Mutex, with};
+
+pub use qmk_sys::os_variant_t as OsVariant;
+
+/// The host operating system, as guessed by QMK's `OS_DETECTION_ENABLE` from how it talks USB.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+#[repr(u8)]
+pub enum HostOs {
+    /// Not detected yet, or QMK couldn't tell.
+    #[default]
+    Unsure,
+    Linux,
+    Windows,
+    MacOs,
+    Ios,
+}
+
+/// Called with the new OS whenever detection settles on one.
+pub type DetectedHandler = fn(HostOs);
+
+static DETECTED: AtomicU8 = AtomicU8::new(HostOs::Unsure as u8);
+static HANDLER: Mutex<RefCell<Option<DetectedHandler>>> = Mutex::new(RefCell::new(None));
+
+impl HostOs {
+    /// Converts QMK's `os_variant_t`, anything unknown is `Unsure`.
+    pub const fn from_raw(raw: OsVariant::Type) -> Self {
+        match raw {
+            OsVariant::OS_LINUX => Self::Linux,
+            OsVariant::OS_WINDOWS => Self::Windows,
+            OsVariant::OS_MACOS => Self::MacOs,
+            OsVariant::OS_IOS => Self::Ios,
+            _ => Self::Unsure,
+        }
+    }
+
+    pub const fn raw(self) -> OsVariant::Type {
+        match self {
+            Self::Unsure => OsVariant::OS_UNSURE,
+            Self::Linux => OsVariant::OS_LINUX,
+            Self::Windows => OsVariant::OS_WINDOWS,
+            Self::MacOs => OsVariant::OS_MACOS,
+            Self::Ios => OsVariant::OS_IOS,
+        }
+    }
+
+    /// The OS detection last settled on.
+    ///
+    /// This is the value the keymap passed to `HostOs::process` from its
+    /// `process_detected_host_os_user`, so it stays `Unsure` until that has run. Detection takes
+    /// a moment after the keyboard is plugged in, and on the half without USB it needs
+    /// `SPLIT_DETECTED_OS_ENABLE`.
+    pub fn detected() -> Self {
+        let raw = DETECTED.load(Ordering::SeqCst);
+        Self::from_raw(raw as OsVariant::Type)
+    }
+
+    /// Sets (or with `None`, removes) the function called when detection completes.
+    pub fn on_detected(handler: Option<DetectedHandler>) {
+        with(|cs| *HANDLER.borrow_ref_mut(cs) = handler);
+    }
+
+    /// Records the detected OS and runs the handler. Call it from the keymap's
+    /// `process_detected_host_os_user`, e.g.
+    /// ```rust
+    /// use qmk::os_detection::{HostOs, OsVariant};
+    ///
+    /// fn process_detected_host_os_user(os: OsVariant::Type) -> bool {
+    ///     HostOs::process(os);
+    ///     true
+    /// }
+    /// ```
+    /// The browser build calls it from `set_host_os_wasm` to simulate a host.
+    pub fn process(raw: OsVariant::Type) -> Self {
+        let os = Self::from_raw(raw);
+        DETECTED.store(os as u8, Ordering::SeqCst);
+        let handler = with(|cs| *HANDLER.borrow_ref(cs));
+        if let Some(handler) = handler {
+            handler(os);
+        }
+        os
+    }
+
+    pub const fn name(self) -> &'static str {
+        match self {
+            Self::Unsure => "Unknown",
+            Self::Linux => "Linux",
+            Self::Windows => "Windows",
+            Self::MacOs => "macOS",
+            Self::Ios => "iOS",
+        }
+    }
+
+    pub const fn is_apple(self) -> bool {
+        matches!(self, Self::MacOs | Self::Ios)
+    }
+
+    /// The modifier shortcuts are held with: Cmd (GUI) on Apple hosts, Ctrl everywhere else.
+    pub const fn shortcut_mods(self) -> Mods {
+        if self.is_apple() {
+            Mods::LGUI
+        } else {
+            Mods::LCTL
+        }
+    }
+
+    /// `key` with the shortcut modifier held, so `KC_C` is copy on any host.
+    pub const fn shortcut(self, key: Keycode) -> Keycode {
+        key.with_mods(self.shortcut_mods())
+    }
+}
+
+/// A value that can differ between host operating systems, for keys and macros that need a
+/// different shortcut on each.
+/// ```rust
+/// use qmk::{
+///     keys::{KC_BSPC, KC_L, KC_Q, Keycode},
+///     lalt, lctl, lgui,
+///     os_detection::{HostOs, PerOs},
+/// };
+///
+/// fn delete_word() -> Keycode {
+///     PerOs::new(Keycode::new(lctl!(KC_BSPC)))
+///         .apple(Keycode::new(lalt!(KC_BSPC)))
+///         .current()
+/// }
+///
+/// fn lock_screen() -> Keycode {
+///     PerOs::new(Keycode::new(lgui!(KC_L)))
+///         .macos(Keycode::new(lctl!(lgui!(KC_Q))))
+///         .get(HostOs::detected())
+/// }
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PerOs<T> {
+    default: T,
+    linux: Option<T>,
+    windows: Option<T>,
+    macos: Option<T>,
+    ios: Option<T>,
+}
+
+impl<T: Copy> PerOs<T> {
+    /// `default` is used for every OS without its own value, including when detection is unsure.
+    pub const fn new(default: T) -> Self {
+        Self {
+            default,
+            linux: None,
+            windows: None,
+            macos: None,
+            ios: None,
+        }
+    }
+
+    pub const fn linux(mut self, value: T) -> Self {
+        self.linux = Some(value);
+        self
+    }
+
+    pub const fn windows(mut self, value: T) -> Self {
+        self.windows = Some(value);
+        self
+    }
+
+    pub const fn macos(mut self, value: T) -> Self {
+        self.macos = Some(value);
+        self
+    }
+
+    pub const fn ios(mut self, value: T) -> Self {
+        self.ios = Some(value);
+        self
+    }
+
+    /// Sets both macOS and iOS.
+    pub const fn apple(self, value: T) -> Self {
+        self.macos(value).ios(value)
+    }
+
+    pub fn get(&self, os: HostOs) -> T {
+        let value = match os {
+            HostOs::Unsure => None,
+            HostOs::Linux => self.linux,
+            HostOs::Windows => self.windows,
+            HostOs::MacOs => self.macos,
+            HostOs::Ios => self.ios,
+        };
+        value.unwrap_or(self.default)
+    }
+
+    /// The value for `HostOs::detected()`.
+    pub fn current(&self) -> T {
+        self.get(HostOs::detected())
+    }
+}
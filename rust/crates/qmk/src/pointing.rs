@@ -0,0 +1,151 @@
+use bitflags::bitflags;
+use core::cell::RefCell;
+use critical_section::{Mutex, with};
+use num_traits::{Bounded, NumCast};
+
+pub use qmk_sys::report_mouse_t as RawMouseReport;
+
+bitflags! {
+    /// Mouse buttons, as in `report_mouse_t::buttons`.
+    #[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+    pub struct MouseButtons: u8 {
+        const BUTTON_1 = 1 << 0;
+        const BUTTON_2 = 1 << 1;
+        const BUTTON_3 = 1 << 2;
+        const BUTTON_4 = 1 << 3;
+        const BUTTON_5 = 1 << 4;
+        const BUTTON_6 = 1 << 5;
+        const BUTTON_7 = 1 << 6;
+        const BUTTON_8 = 1 << 7;
+    }
+}
+
+impl MouseButtons {
+    pub const LEFT: Self = Self::BUTTON_1;
+    pub const RIGHT: Self = Self::BUTTON_2;
+    pub const MIDDLE: Self = Self::BUTTON_3;
+}
+
+/// One pointing device report: movement since the last one, scrolling and held buttons.
+///
+/// Fields are wide enough for `MOUSE_EXTENDED_REPORT` and `WHEEL_EXTENDED_REPORT`. Without them
+/// QMK's report is 8-bit, and larger values are clamped on the way back.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct MouseReport {
+    pub x: i16,
+    pub y: i16,
+    /// Vertical scroll, positive is up.
+    pub v: i16,
+    /// Horizontal scroll, positive is right.
+    pub h: i16,
+    pub buttons: MouseButtons,
+}
+
+impl MouseReport {
+    /// No movement, no scrolling and nothing held.
+    pub fn is_empty(&self) -> bool {
+        *self == Self::default()
+    }
+
+    /// Applies these values to `raw`, keeping anything else in it (like the report id).
+    pub fn write_to(&self, raw: &mut RawMouseReport) {
+        raw.x = saturate(self.x);
+        raw.y = saturate(self.y);
+        raw.v = saturate(self.v);
+        raw.h = saturate(self.h);
+        raw.buttons = self.buttons.bits();
+    }
+}
+
+impl From<RawMouseReport> for MouseReport {
+    fn from(raw: RawMouseReport) -> Self {
+        // copied out first, the struct is packed
+        let (x, y, v, h, buttons) = (raw.x, raw.y, raw.v, raw.h, raw.buttons);
+        Self {
+            x: x as i16,
+            y: y as i16,
+            v: v as i16,
+            h: h as i16,
+            buttons: MouseButtons::from_bits_retain(buttons),
+        }
+    }
+}
+
+fn saturate<T: NumCast + Bounded>(value: i16) -> T {
+    T::from(value).unwrap_or_else(|| {
+        if value < 0 {
+            T::min_value()
+        } else {
+            T::max_value()
+        }
+    })
+}
+
+/// Sees every report before it goes to the host. Returns the report to send, or `None` to send
+/// nothing (the device still moved, the host just doesn't hear about it).
+pub type ReportHandler = fn(MouseReport) -> Option<MouseReport>;
+
+static HANDLER: Mutex<RefCell<Option<ReportHandler>>> = Mutex::new(RefCell::new(None));
+static LAST_REPORT: Mutex<RefCell<MouseReport>> = Mutex::new(RefCell::new(MouseReport {
+    x: 0,
+    y: 0,
+    v: 0,
+    h: 0,
+    buttons: MouseButtons::empty(),
+}));
+
+/// Trackballs, trackpads and other pointing devices.
+///
+/// Needs `POINTING_DEVICE_ENABLE = yes`, and the keymap has to call `Pointing::task` from its
+/// `pointing_device_task_user`, e.g.
+/// ```rust
+/// use qmk::pointing::{MouseReport, Pointing, RawMouseReport};
+///
+/// fn pointing_device_task_user(report: RawMouseReport) -> RawMouseReport {
+///     Pointing::task(report)
+/// }
+///
+/// // slow everything down while the handler is set
+/// fn precision(mut report: MouseReport) -> Option<MouseReport> {
+///     report.x /= 4;
+///     report.y /= 4;
+///     Some(report)
+/// }
+///
+/// fn init() {
+///     Pointing::set_handler(Some(precision));
+/// }
+/// ```
+pub struct Pointing;
+
+impl Pointing {
+    /// Sets (or with `None`, removes) the function that can change or swallow reports.
+    pub fn set_handler(handler: Option<ReportHandler>) {
+        with(|cs| *HANDLER.borrow_ref_mut(cs) = handler);
+    }
+
+    /// The last report the device produced, before the handler saw it.
+    pub fn last_report() -> MouseReport {
+        with(|cs| *LAST_REPORT.borrow_ref(cs))
+    }
+
+    /// Runs a report through the handler, returning what should be sent. The browser build feeds
+    /// simulated reports through here too.
+    pub fn process(report: MouseReport) -> Option<MouseReport> {
+        let handler = with(|cs| {
+            *LAST_REPORT.borrow_ref_mut(cs) = report;
+            *HANDLER.borrow_ref(cs)
+        });
+        match handler {
+            Some(handler) => handler(report),
+            None => Some(report),
+        }
+    }
+
+    /// `Pointing::process` for QMK's report type, for `pointing_device_task_user`.
+    pub fn task(mut raw: RawMouseReport) -> RawMouseReport {
+        let report = Self::process(MouseReport::from(raw)).unwrap_or_default();
+        report.write_to(&mut raw);
+        raw
+    }
+}
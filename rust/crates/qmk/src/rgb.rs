@@ -1,30 +1,416 @@
-use num_traits::{Num, ToPrimitive};
+use micromath::F32Ext;
+
+/// An 8-bit per channel colour, as sent to the LEDs.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+#[repr(C)]
+pub struct Rgb {
+    pub r: u8,
+    pub g: u8,
+    pub b: u8,
+}
+
+/// A colour in QMK's HSV space, where hue also runs from 0 to 255 (so 85 is green and 170 is
+/// blue) rather than 0 to 360.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(
+    target_arch = "wasm32",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(from = "[u8; 3]", into = "[u8; 3]")
+)]
+#[repr(C)]
+pub struct Hsv {
+    pub h: u8,
+    pub s: u8,
+    pub v: u8,
+}
+
+/// Hue, saturation and lightness, on the same 0 to 255 scales as `Hsv`. Handy for pastels, where
+/// full lightness is white instead of the fully saturated colour.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+#[repr(C)]
+pub struct Hsl {
+    pub h: u8,
+    pub s: u8,
+    pub l: u8,
+}
+
+// scales `value` by `scale / 256`, like FastLED's (and QMK's) `scale8`, so 255 is the identity
+const fn scale8(value: u8, scale: u8) -> u8 {
+    ((value as u16 * (scale as u16 + 1)) >> 8) as u8
+}
+
+fn lerp8(from: u8, to: u8, t: f32) -> u8 {
+    let t = t.clamp(0.0, 1.0);
+    (from as f32 + (to as f32 - from as f32) * t + 0.5) as u8
+}
+
+impl Rgb {
+    pub const AZURE: Rgb = Rgb::new(0x99, 0xF5, 0xFF);
+    pub const BLACK: Rgb = Rgb::new(0x00, 0x00, 0x00);
+    pub const BLUE: Rgb = Rgb::new(0x00, 0x00, 0xFF);
+    pub const CHARTREUSE: Rgb = Rgb::new(0x80, 0xFF, 0x00);
+    pub const CORAL: Rgb = Rgb::new(0xFF, 0x7C, 0x4D);
+    pub const CYAN: Rgb = Rgb::new(0x00, 0xFF, 0xFF);
+    pub const GOLD: Rgb = Rgb::new(0xFF, 0xD9, 0x00);
+    pub const GOLDENROD: Rgb = Rgb::new(0xD9, 0xA5, 0x21);
+    pub const GREEN: Rgb = Rgb::new(0x00, 0xFF, 0x00);
+    pub const MAGENTA: Rgb = Rgb::new(0xFF, 0x00, 0xFF);
+    pub const ORANGE: Rgb = Rgb::new(0xFF, 0x80, 0x00);
+    pub const PINK: Rgb = Rgb::new(0xFF, 0x80, 0xBF);
+    pub const PURPLE: Rgb = Rgb::new(0x7A, 0x00, 0xFF);
+    pub const RED: Rgb = Rgb::new(0xFF, 0x00, 0x00);
+    pub const SPRINGGREEN: Rgb = Rgb::new(0x00, 0xFF, 0x80);
+    pub const TEAL: Rgb = Rgb::new(0x00, 0x80, 0x80);
+    pub const TURQUOISE: Rgb = Rgb::new(0x47, 0x6E, 0x6A);
+    pub const WHITE: Rgb = Rgb::new(0xFF, 0xFF, 0xFF);
+    pub const YELLOW: Rgb = Rgb::new(0xFF, 0xFF, 0x00);
+    pub const OFF: Rgb = Rgb::BLACK;
+
+    pub const fn new(r: u8, g: u8, b: u8) -> Self {
+        Self { r, g, b }
+    }
+
+    /// Dims every channel by `brightness`, where 255 leaves the colour as it is.
+    pub const fn scale(self, brightness: u8) -> Self {
+        Self::new(
+            scale8(self.r, brightness),
+            scale8(self.g, brightness),
+            scale8(self.b, brightness),
+        )
+    }
+
+    /// Applies a gamma curve to each channel. LEDs look much too bright at low values, so around
+    /// 2.2 makes fades look even.
+    pub fn gamma(self, gamma: f32) -> Self {
+        let correct =
+            |channel: u8| (F32Ext::powf(channel as f32 / 255.0, gamma) * 255.0 + 0.5) as u8;
+        Self::new(correct(self.r), correct(self.g), correct(self.b))
+    }
+
+    /// Blends channel by channel, `t` going from 0 (`self`) to 1 (`other`).
+    pub fn lerp(self, other: Rgb, t: f32) -> Self {
+        Self::new(
+            lerp8(self.r, other.r, t),
+            lerp8(self.g, other.g, t),
+            lerp8(self.b, other.b, t),
+        )
+    }
+
+    pub const fn to_hsv(self) -> Hsv {
+        let (r, g, b) = (self.r as i32, self.g as i32, self.b as i32);
+        let max = if r > g { r } else { g };
+        let max = if max > b { max } else { b };
+        let min = if r < g { r } else { g };
+        let min = if min < b { min } else { b };
+        let delta = max - min;
+
+        if max == 0 || delta == 0 {
+            return Hsv::new(0, 0, max as u8);
+        }
+
+        let s = 255 * delta / max;
+        // 43 is a sixth of the hue circle
+        let h = if max == r {
+            43 * (g - b) / delta
+        } else if max == g {
+            85 + 43 * (b - r) / delta
+        } else {
+            171 + 43 * (r - g) / delta
+        };
+
+        Hsv::new(h.rem_euclid(256) as u8, s as u8, max as u8)
+    }
+}
+
+impl Hsv {
+    pub const AZURE: Hsv = Hsv::new(132, 102, 255);
+    pub const BLACK: Hsv = Hsv::new(0, 0, 0);
+    pub const BLUE: Hsv = Hsv::new(170, 255, 255);
+    pub const CHARTREUSE: Hsv = Hsv::new(64, 255, 255);
+    pub const CORAL: Hsv = Hsv::new(11, 176, 255);
+    pub const CYAN: Hsv = Hsv::new(128, 255, 255);
+    pub const GOLD: Hsv = Hsv::new(36, 255, 255);
+    pub const GOLDENROD: Hsv = Hsv::new(30, 218, 218);
+    pub const GREEN: Hsv = Hsv::new(85, 255, 255);
+    pub const MAGENTA: Hsv = Hsv::new(213, 255, 255);
+    pub const ORANGE: Hsv = Hsv::new(21, 255, 255);
+    pub const PINK: Hsv = Hsv::new(234, 128, 255);
+    pub const PURPLE: Hsv = Hsv::new(191, 255, 255);
+    pub const RED: Hsv = Hsv::new(0, 255, 255);
+    pub const SPRINGGREEN: Hsv = Hsv::new(106, 255, 255);
+    pub const TEAL: Hsv = Hsv::new(128, 255, 128);
+    pub const TURQUOISE: Hsv = Hsv::new(123, 90, 112);
+    pub const WHITE: Hsv = Hsv::new(0, 0, 255);
+    pub const YELLOW: Hsv = Hsv::new(43, 255, 255);
+    pub const OFF: Hsv = Hsv::BLACK;
+
+    pub const fn new(h: u8, s: u8, v: u8) -> Self {
+        Self { h, s, v }
+    }
+
+    /// Dims the value by `brightness`, where 255 leaves the colour as it is.
+    pub const fn scale(self, brightness: u8) -> Self {
+        Self::new(self.h, self.s, scale8(self.v, brightness))
+    }
+
+    /// Blends towards `other`, `t` going from 0 (`self`) to 1 (`other`). Hue takes the short way
+    /// round the colour wheel, so red to purple doesn't pass through green.
+    pub fn lerp(self, other: Hsv, t: f32) -> Self {
+        let t = t.clamp(0.0, 1.0);
+        let distance = other.h.wrapping_sub(self.h) as i8 as f32;
+        let h = self
+            .h
+            .wrapping_add(F32Ext::floor(distance * t + 0.5) as i32 as u8);
+        Self::new(h, lerp8(self.s, other.s, t), lerp8(self.v, other.v, t))
+    }
+
+    /// The same conversion as QMK's `hsv_to_rgb_nocie`, so colours match the firmware's own
+    /// effects exactly.
+    pub const fn to_rgb(self) -> Rgb {
+        if self.s == 0 {
+            return Rgb::new(self.v, self.v, self.v);
+        }
+
+        let (h, s, v) = (self.h as u16, self.s as u16, self.v as u16);
+        let region = h * 6 / 255;
+        let remainder = ((h * 2 - region * 85) * 3) as u8 as u16;
+
+        let p = ((v * (255 - s)) >> 8) as u8;
+        let q = ((v * (255 - ((s * remainder) >> 8))) >> 8) as u8;
+        let t = ((v * (255 - ((s * (255 - remainder)) >> 8))) >> 8) as u8;
+        let v = self.v;
+
+        match region {
+            0 | 6 => Rgb::new(v, t, p),
+            1 => Rgb::new(q, v, p),
+            2 => Rgb::new(p, v, t),
+            3 => Rgb::new(p, q, v),
+            4 => Rgb::new(t, p, v),
+            _ => Rgb::new(v, p, q),
+        }
+    }
+}
+
+impl Hsl {
+    pub const fn new(h: u8, s: u8, l: u8) -> Self {
+        Self { h, s, l }
+    }
+}
+
+impl From<Hsv> for Rgb {
+    fn from(hsv: Hsv) -> Self {
+        hsv.to_rgb()
+    }
+}
+
+impl From<Rgb> for Hsv {
+    fn from(rgb: Rgb) -> Self {
+        rgb.to_hsv()
+    }
+}
+
+impl From<Hsl> for Hsv {
+    fn from(hsl: Hsl) -> Self {
+        let s = hsl.s as f32 / 255.0;
+        let l = hsl.l as f32 / 255.0;
+        let v = l + s * l.min(1.0 - l);
+        let s = if v == 0.0 { 0.0 } else { 2.0 * (1.0 - l / v) };
+        Hsv::new(hsl.h, (s * 255.0 + 0.5) as u8, (v * 255.0 + 0.5) as u8)
+    }
+}
+
+impl From<Hsv> for Hsl {
+    fn from(hsv: Hsv) -> Self {
+        let s = hsv.s as f32 / 255.0;
+        let v = hsv.v as f32 / 255.0;
+        let l = v * (1.0 - s / 2.0);
+        let s = if l == 0.0 || l == 1.0 {
+            0.0
+        } else {
+            (v - l) / l.min(1.0 - l)
+        };
+        Hsl::new(hsv.h, (s * 255.0 + 0.5) as u8, (l * 255.0 + 0.5) as u8)
+    }
+}
+
+impl From<Hsl> for Rgb {
+    fn from(hsl: Hsl) -> Self {
+        Hsv::from(hsl).to_rgb()
+    }
+}
+
+impl From<Rgb> for Hsl {
+    fn from(rgb: Rgb) -> Self {
+        rgb.to_hsv().into()
+    }
+}
+
+impl From<[u8; 3]> for Hsv {
+    fn from([h, s, v]: [u8; 3]) -> Self {
+        Self::new(h, s, v)
+    }
+}
+
+impl From<Hsv> for [u8; 3] {
+    fn from(hsv: Hsv) -> Self {
+        [hsv.h, hsv.s, hsv.v]
+    }
+}
+
+impl From<[u8; 3]> for Rgb {
+    fn from([r, g, b]: [u8; 3]) -> Self {
+        Self::new(r, g, b)
+    }
+}
+
+impl From<Rgb> for [u8; 3] {
+    fn from(rgb: Rgb) -> Self {
+        [rgb.r, rgb.g, rgb.b]
+    }
+}
+
+// the web config editor shows the colour as three bytes, which is also how it is stored
+#[cfg(target_arch = "wasm32")]
+impl layout_inspect::Inspect for Hsv {
+    fn name() -> alloc::string::String {
+        use alloc::string::ToString;
+        "[u8; 3]".to_string()
+    }
+
+    fn align() -> Option<usize> {
+        Some(0x1)
+    }
+
+    fn def(collector: &mut layout_inspect::TypesCollector) -> layout_inspect::defs::DefType {
+        layout_inspect::defs::DefType::Vec(layout_inspect::defs::DefVec {
+            name: Self::name(),
+            size: Self::size().unwrap(),
+            align: Self::align().unwrap(),
+            value_type_id: collector.collect::<u8>(),
+        })
+    }
+
+    fn size() -> Option<usize> {
+        Some(core::mem::size_of::<Self>())
+    }
+}
+
+#[cfg(target_arch = "wasm32")]
+mod bindings {
+    use core::sync::atomic::{AtomicU32, Ordering};
+
+    // websites do not have rgb strips, believe it or not, so only the colour is remembered
+    static HSV: AtomicU32 = AtomicU32::new(0);
+
+    pub unsafe fn rgblight_sethsv(h: u8, s: u8, v: u8) {
+        HSV.store(u32::from_le_bytes([h, s, v, 0]), Ordering::SeqCst);
+    }
+
+    pub unsafe fn rgblight_sethsv_noeeprom(h: u8, s: u8, v: u8) {
+        unsafe { rgblight_sethsv(h, s, v) }
+    }
+
+    pub unsafe fn rgblight_get_hsv() -> super::Hsv {
+        let [h, s, v, _] = HSV.load(Ordering::SeqCst).to_le_bytes();
+        super::Hsv::new(h, s, v)
+    }
+
+    pub unsafe fn rgblight_setrgb(r: u8, g: u8, b: u8) {
+        let super::Hsv { h, s, v } = super::Rgb::new(r, g, b).to_hsv();
+        unsafe { rgblight_sethsv(h, s, v) }
+    }
+
+    pub unsafe fn rgblight_setrgb_range(_r: u8, _g: u8, _b: u8, _start: u8, _end: u8) {}
+
+    pub unsafe fn rgblight_led_count() -> u8 {
+        0
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+mod bindings {
+    pub use qmk_sys::{
+        rgblight_sethsv, rgblight_sethsv_noeeprom, rgblight_setrgb, rgblight_setrgb_range,
+    };
+
+    pub unsafe fn rgblight_get_hsv() -> super::Hsv {
+        let qmk_sys::hsv_t { h, s, v } = unsafe { qmk_sys::rgblight_get_hsv() };
+        super::Hsv::new(h, s, v)
+    }
+
+    pub unsafe fn rgblight_led_count() -> u8 {
+        unsafe { qmk_sys::rgblight_ranges.clipping_num_leds }
+    }
+}
 
 pub struct RGBLight;
 
 impl RGBLight {
-    #[cfg(not(target_arch = "wasm32"))]
-    pub fn set_hsv<H, S, V>(hue: H, sat: S, val: V)
-    where
-        H: Num + ToPrimitive,
-        S: Num + ToPrimitive,
-        V: Num + ToPrimitive,
-    {
-        let hue = hue.to_u8().unwrap_or(0);
-        let sat = sat.to_u8().unwrap_or(0);
-        let val = val.to_u8().unwrap_or(0);
+    /// Sets every LED to `color` and saves it to EEPROM. Takes any of `Hsv`, `Rgb` or `Hsl`.
+    pub fn set_hsv(color: impl Into<Hsv>) {
+        let Hsv { h, s, v } = color.into();
+        unsafe {
+            bindings::rgblight_sethsv(h, s, v);
+        }
+    }
+
+    /// Like `set_hsv`, but doesn't touch EEPROM, for colours that change often.
+    pub fn set_hsv_noeeprom(color: impl Into<Hsv>) {
+        let Hsv { h, s, v } = color.into();
+        unsafe {
+            bindings::rgblight_sethsv_noeeprom(h, s, v);
+        }
+    }
+
+    /// Sets every LED to `color`, skipping the round trip through HSV.
+    pub fn set_rgb(color: impl Into<Rgb>) {
+        let Rgb { r, g, b } = color.into();
+        unsafe {
+            bindings::rgblight_setrgb(r, g, b);
+        }
+    }
+
+    /// Sets LEDs `start..end` to `color`. Only sticks while RGBLight is in static mode.
+    pub fn set_rgb_range(start: u8, end: u8, color: impl Into<Rgb>) {
+        let Rgb { r, g, b } = color.into();
         unsafe {
-            qmk_sys::rgblight_sethsv(hue, sat, val);
+            bindings::rgblight_setrgb_range(r, g, b, start, end);
         }
     }
 
-    #[cfg(target_arch = "wasm32")]
-    pub fn set_hsv<H, S, V>(_: H, _: S, _: V)
-    where
-        H: Num + ToPrimitive,
-        S: Num + ToPrimitive,
-        V: Num + ToPrimitive,
-    {
-        // websites do not have rgb matrices, believe it or not
+    /// The colour last set with `set_hsv` or the RGB keycodes.
+    pub fn hsv() -> Hsv {
+        unsafe { bindings::rgblight_get_hsv() }
+    }
+
+    /// The number of LEDs on the strip (on both halves, for a split keyboard).
+    pub fn led_count() -> u8 {
+        unsafe { bindings::rgblight_led_count() }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn hsv_to_rgb_matches_qmk() {
+        // from running QMK's hsv_to_rgb_nocie
+        assert_eq!(Hsv::RED.to_rgb(), Rgb::new(255, 0, 0));
+        assert_eq!(Hsv::GREEN.to_rgb(), Rgb::new(0, 255, 0));
+        assert_eq!(Hsv::BLUE.to_rgb(), Rgb::new(0, 0, 255));
+        assert_eq!(Hsv::MAGENTA.to_rgb(), Rgb::new(255, 0, 252));
+        assert_eq!(Hsv::new(100, 200, 150).to_rgb(), Rgb::new(32, 150, 74));
+        assert_eq!(Hsv::new(255, 255, 255).to_rgb(), Rgb::new(255, 0, 0));
+        assert_eq!(Hsv::WHITE.to_rgb(), Rgb::WHITE);
+    }
+
+    #[test]
+    fn hsv_lerp_takes_the_short_way_round() {
+        let from = Hsv::new(250, 255, 255);
+        let to = Hsv::new(10, 255, 255);
+        assert_eq!(from.lerp(to, 0.5).h, 2);
+        assert_eq!(to.lerp(from, 0.5).h, 2);
+        assert_eq!(from.lerp(to, 1.0), to);
     }
 }
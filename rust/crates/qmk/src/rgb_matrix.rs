@@ -0,0 +1,313 @@
+use alloc::{boxed::Box, vec, vec::Vec};
+use bitflags::bitflags;
+use core::cell::RefCell;
+use critical_section::{Mutex, with};
+
+use crate::{indicator::Indicators, rgb::Rgb, time::Instant};
+
+#[cfg(all(not(target_arch = "wasm32"), feature = "rgb_matrix"))]
+mod bindings {
+    pub use qmk_sys::rgb_matrix_set_color;
+}
+
+bitflags! {
+    /// What an LED is for, matching QMK's `LED_FLAG_*` values.
+    #[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+    pub struct LedFlags: u8 {
+        const MODIFIER = 0x01;
+        const UNDERGLOW = 0x02;
+        const KEYLIGHT = 0x04;
+        const INDICATOR = 0x08;
+    }
+}
+
+/// One entry of the keyboard's `rgb_matrix.layout`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Led {
+    /// The `(row, col)` of the key this LED sits under, if any.
+    pub matrix: Option<(u8, u8)>,
+    /// Position on QMK's 224x64 LED grid.
+    pub x: u8,
+    pub y: u8,
+    pub flags: LedFlags,
+}
+
+/// The physical LED layout of a keyboard, usually generated with `qmk::led_config!`.
+#[derive(Debug)]
+pub struct LedConfig {
+    pub leds: &'static [Led],
+}
+
+impl LedConfig {
+    pub const fn len(&self) -> usize {
+        self.leds.len()
+    }
+
+    pub const fn is_empty(&self) -> bool {
+        self.leds.is_empty()
+    }
+
+    pub fn get(&self, index: usize) -> Option<&Led> {
+        self.leds.get(index)
+    }
+
+    /// The index of the LED under the key at `(row, col)`.
+    pub fn led_at(&self, row: u8, col: u8) -> Option<usize> {
+        self.leds
+            .iter()
+            .position(|led| led.matrix == Some((row, col)))
+    }
+}
+
+/// The colour of every LED, as handed to an `RgbEffect` each frame.
+pub struct LedBuffer {
+    config: &'static LedConfig,
+    colors: Vec<Rgb>,
+}
+
+impl LedBuffer {
+    fn new(config: &'static LedConfig) -> Self {
+        Self {
+            config,
+            colors: vec![Rgb::BLACK; config.len()],
+        }
+    }
+
+    pub fn config(&self) -> &'static LedConfig {
+        self.config
+    }
+
+    pub fn len(&self) -> usize {
+        self.colors.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.colors.is_empty()
+    }
+
+    /// Sets one LED. Indices past the end are ignored.
+    pub fn set(&mut self, index: usize, color: impl Into<Rgb>) {
+        if let Some(led) = self.colors.get_mut(index) {
+            *led = color.into();
+        }
+    }
+
+    pub fn get(&self, index: usize) -> Option<Rgb> {
+        self.colors.get(index).copied()
+    }
+
+    pub fn fill(&mut self, color: impl Into<Rgb>) {
+        self.colors.fill(color.into());
+    }
+
+    /// Every LED alongside its layout entry.
+    pub fn iter_mut(&mut self) -> impl Iterator<Item = (&'static Led, &mut Rgb)> {
+        self.config.leds.iter().zip(self.colors.iter_mut())
+    }
+
+    pub fn as_slice(&self) -> &[Rgb] {
+        &self.colors
+    }
+}
+
+/// A lighting effect written in Rust. `tick` is called once per RGB matrix frame and should
+/// write the whole frame into `leds`.
+pub trait RgbEffect: Send {
+    fn tick(&mut self, leds: &mut LedBuffer, time: Instant);
+}
+
+struct State {
+    buffer: LedBuffer,
+    effect: Option<Box<dyn RgbEffect>>,
+    // false until something has been drawn, so QMK's own effects show through until then
+    active: bool,
+    // the buffer with indicators drawn over it, as last sent to the LEDs
+    frame: Vec<Rgb>,
+    brightness: u8,
+}
+
+static STATE: Mutex<RefCell<Option<State>>> = Mutex::new(RefCell::new(None));
+
+/// Per-LED control over QMK's RGB matrix.
+///
+/// The colours set here are written over whatever QMK's current effect drew, every frame, from
+/// `RgbMatrix::task` (which the keymap calls from `rgb_matrix_indicators_user`). On the keyboard
+/// this needs the `rgb_matrix` feature and `RGB_MATRIX_ENABLE = yes`; without them, and in the
+/// browser, the colours only end up in `RgbMatrix::frame`.
+pub struct RgbMatrix;
+
+impl RgbMatrix {
+    /// Sets up the LED buffer for `config`. Must be called before anything else here does
+    /// anything.
+    pub fn init(config: &'static LedConfig) {
+        with(|cs| {
+            STATE.borrow_ref_mut(cs).replace(State {
+                buffer: LedBuffer::new(config),
+                effect: None,
+                active: false,
+                frame: Vec::new(),
+                brightness: u8::MAX,
+            });
+        });
+    }
+
+    pub fn led_config() -> Option<&'static LedConfig> {
+        with(|cs| {
+            STATE
+                .borrow_ref(cs)
+                .as_ref()
+                .map(|state| state.buffer.config)
+        })
+    }
+
+    /// The index of the LED under the key at `(row, col)`.
+    pub fn led_at(row: u8, col: u8) -> Option<usize> {
+        Self::led_config()?.led_at(row, col)
+    }
+
+    /// Draws `effect` instead of QMK's effect until it is replaced or cleared.
+    pub fn set_effect<E>(effect: E)
+    where
+        E: RgbEffect + 'static,
+    {
+        Self::with_state(|state| {
+            state.effect = Some(Box::new(effect));
+            state.active = true;
+        });
+    }
+
+    /// Removes the Rust effect and any colours that were set, handing the LEDs back to QMK.
+    pub fn clear() {
+        Self::with_state(|state| {
+            state.effect = None;
+            state.active = false;
+            state.buffer.fill(Rgb::BLACK);
+        });
+    }
+
+    pub fn set_color(index: usize, color: impl Into<Rgb>) {
+        let color = color.into();
+        Self::with_state(|state| {
+            state.buffer.set(index, color);
+            state.active = true;
+        });
+    }
+
+    pub fn set_color_all(color: impl Into<Rgb>) {
+        let color = color.into();
+        Self::with_state(|state| {
+            state.buffer.fill(color);
+            state.active = true;
+        });
+    }
+
+    /// Dims everything drawn from Rust, indicators included, where 255 is full brightness. QMK's
+    /// own effect isn't touched, it has `rgb_matrix_sethsv_noeeprom` for that.
+    pub fn set_brightness(brightness: u8) {
+        Self::with_state(|state| state.brightness = brightness);
+    }
+
+    pub fn brightness() -> u8 {
+        with(|cs| {
+            STATE
+                .borrow_ref(cs)
+                .as_ref()
+                .map_or(u8::MAX, |state| state.brightness)
+        })
+    }
+
+    /// A copy of the current colour of every LED.
+    pub fn buffer() -> Vec<Rgb> {
+        with(|cs| {
+            STATE
+                .borrow_ref(cs)
+                .as_ref()
+                .map(|state| state.buffer.colors.clone())
+                .unwrap_or_default()
+        })
+    }
+
+    /// The colours shown on the last frame, with indicators drawn over the buffer.
+    pub fn frame() -> Vec<Rgb> {
+        with(|cs| {
+            STATE
+                .borrow_ref(cs)
+                .as_ref()
+                .map(|state| state.frame.clone())
+                .unwrap_or_default()
+        })
+    }
+
+    /// Runs the effect for this frame, draws the indicators over it and writes it out to the
+    /// LEDs.
+    pub fn task() {
+        let Some((mut effect, mut leds, active, brightness)) = with(|cs| {
+            let mut state = STATE.borrow_ref_mut(cs);
+            let state = state.as_mut()?;
+            // taken out while the effect runs, so it can call back into `RgbMatrix`
+            let empty = LedBuffer {
+                config: state.buffer.config,
+                colors: Vec::new(),
+            };
+            Some((
+                state.effect.take(),
+                core::mem::replace(&mut state.buffer, empty),
+                state.active,
+                state.brightness,
+            ))
+        }) else {
+            return;
+        };
+
+        let now = Instant::now();
+        if let Some(effect) = effect.as_mut() {
+            effect.tick(&mut leds, now);
+        }
+
+        // indicators go on a copy, so they don't stick around once their rule stops matching
+        let mut frame = leds.colors.clone();
+        let lit = Indicators::apply(&mut frame, Some(leds.config), now);
+        if brightness < u8::MAX {
+            for color in frame.iter_mut() {
+                *color = color.scale(brightness);
+            }
+        }
+        for (index, color) in frame.iter().enumerate() {
+            // leave QMK's own effect alone wherever nothing has been drawn
+            if active || lit.get(index) == Some(&true) {
+                Self::flush(index, *color);
+            }
+        }
+
+        with(|cs| {
+            if let Some(state) = STATE.borrow_ref_mut(cs).as_mut() {
+                state.buffer = leds;
+                state.frame = frame;
+                // unless the effect replaced itself
+                if state.effect.is_none() && state.active {
+                    state.effect = effect;
+                }
+            }
+        });
+    }
+
+    #[cfg(all(not(target_arch = "wasm32"), feature = "rgb_matrix"))]
+    fn flush(index: usize, color: Rgb) {
+        unsafe {
+            bindings::rgb_matrix_set_color(index as i32, color.r, color.g, color.b);
+        }
+    }
+
+    #[cfg(any(target_arch = "wasm32", not(feature = "rgb_matrix")))]
+    fn flush(_: usize, _: Rgb) {
+        // nothing to send to, the buffer is all there is
+    }
+
+    fn with_state(f: impl FnOnce(&mut State)) {
+        with(|cs| {
+            if let Some(state) = STATE.borrow_ref_mut(cs).as_mut() {
+                f(state);
+            }
+        });
+    }
+}
@@ -8,6 +8,9 @@ use include_image::QmkImage;
 #[cfg(not(target_arch = "wasm32"))]
 use num_traits::{Num, ToPrimitive};
 
+#[cfg(target_arch = "wasm32")]
+static IS_ON: core::sync::atomic::AtomicBool = core::sync::atomic::AtomicBool::new(true);
+
 pub struct Screen;
 
 impl Screen {
@@ -95,4 +98,37 @@ impl Screen {
             qmk_sys::oled_write(text.as_ptr(), false);
         }
     }
+
+    /// Turns the display back on after `Screen::off`. QMK also turns it on by itself when
+    /// something is drawn.
+    pub fn on() {
+        #[cfg(not(target_arch = "wasm32"))]
+        unsafe {
+            qmk_sys::oled_on();
+        }
+
+        #[cfg(target_arch = "wasm32")]
+        IS_ON.store(true, core::sync::atomic::Ordering::SeqCst);
+    }
+
+    /// Turns the display off, keeping what was on it.
+    pub fn off() {
+        #[cfg(not(target_arch = "wasm32"))]
+        unsafe {
+            qmk_sys::oled_off();
+        }
+
+        #[cfg(target_arch = "wasm32")]
+        IS_ON.store(false, core::sync::atomic::Ordering::SeqCst);
+    }
+
+    pub fn is_on() -> bool {
+        #[cfg(not(target_arch = "wasm32"))]
+        unsafe {
+            qmk_sys::is_oled_on()
+        }
+
+        #[cfg(target_arch = "wasm32")]
+        IS_ON.load(core::sync::atomic::Ordering::SeqCst)
+    }
 }
@@ -1 +1 @@
-pub use qmk_sys::{keyevent_t, keypos_t, tap_t};
+pub use qmk_sys::{MATRIX_SIZE, keyevent_t, keypos_t, tap_t};
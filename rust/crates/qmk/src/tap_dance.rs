@@ -0,0 +1,44 @@
+use core::ffi::c_void;
+use qmk_sys::{tap_dance_action_t, tap_dance_pair_t, tap_dance_state_t};
+
+/// An entry in QMK's `tap_dance_actions`, which `tap_dance!` generates.
+pub type TapDanceAction = tap_dance_action_t;
+/// How far a dance has got: `count` taps so far, and whether the key is still `pressed()`, the
+/// dance has `finished()` or another key `interrupted()` it.
+pub type TapDanceState = tap_dance_state_t;
+/// The two keycodes of a `double` dance.
+pub type TapDancePair = tap_dance_pair_t;
+
+/// A tap dance callback as QMK calls it. `tap_dance!` wraps its closures in these.
+pub type TapDanceFn = unsafe extern "C" fn(state: *mut TapDanceState, user_data: *mut c_void);
+
+/// ACTION_TAP_DANCE_DOUBLE(kc1, kc2): `kc1` when tapped once, `kc2` when tapped twice.
+pub const fn double(pair: &'static TapDancePair) -> TapDanceAction {
+    #[cfg(not(target_arch = "wasm32"))]
+    let mut action = advanced(
+        Some(qmk_sys::tap_dance_pair_on_each_tap as TapDanceFn),
+        Some(qmk_sys::tap_dance_pair_finished as TapDanceFn),
+        Some(qmk_sys::tap_dance_pair_reset as TapDanceFn),
+    );
+    // the browser has no QMK to dance with
+    #[cfg(target_arch = "wasm32")]
+    let mut action = advanced(None, None, None);
+    action.user_data = pair as *const TapDancePair as *mut c_void;
+    action
+}
+
+/// ACTION_TAP_DANCE_FN_ADVANCED(each_tap, finished, reset): `each_tap` runs on every tap,
+/// `finished` once the dance is over (the tapping term passed or another key was pressed) and
+/// `reset` after `finished` when the key is released.
+pub const fn advanced(
+    each_tap: Option<TapDanceFn>,
+    finished: Option<TapDanceFn>,
+    reset: Option<TapDanceFn>,
+) -> TapDanceAction {
+    // all zeroes is a dance that hasn't started
+    let mut action: TapDanceAction = unsafe { core::mem::zeroed() };
+    action.fn_.on_each_tap = each_tap;
+    action.fn_.on_dance_finished = finished;
+    action.fn_.on_reset = reset;
+    action
+}
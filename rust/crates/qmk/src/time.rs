@@ -0,0 +1,109 @@
+use crate::timer::Timer;
+use core::ops::{Add, AddAssign, Sub, SubAssign};
+
+pub use core::time::Duration;
+
+/// A point in time, measured in milliseconds since boot.
+///
+/// This is QMK's 32-bit millisecond timer, so it wraps after about 49 days. Comparisons between
+/// instants are done with wrapping arithmetic, which is correct as long as they are less than
+/// about 24 days apart. The browser build reads `Timer`'s simulated clock, like everything else
+/// that keeps time there.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Instant(u32);
+
+impl Instant {
+    /// The moment the keyboard booted.
+    pub const BOOT: Instant = Instant(0);
+
+    pub fn now() -> Self {
+        Self(Timer::read32())
+    }
+
+    pub const fn from_millis(millis: u32) -> Self {
+        Self(millis)
+    }
+
+    pub const fn as_millis(self) -> u32 {
+        self.0
+    }
+
+    /// Time passed since `earlier`, or zero if `earlier` is actually later.
+    pub const fn duration_since(self, earlier: Instant) -> Duration {
+        let millis = self.0.wrapping_sub(earlier.0) as i32;
+        if millis > 0 {
+            Duration::from_millis(millis as u64)
+        } else {
+            Duration::ZERO
+        }
+    }
+
+    pub fn elapsed(self) -> Duration {
+        Self::now().duration_since(self)
+    }
+
+    pub fn checked_add(self, duration: Duration) -> Option<Instant> {
+        let millis = u32::try_from(duration.as_millis()).ok()?;
+        Some(Self(self.0.wrapping_add(millis)))
+    }
+
+    pub fn checked_sub(self, duration: Duration) -> Option<Instant> {
+        let millis = u32::try_from(duration.as_millis()).ok()?;
+        Some(Self(self.0.wrapping_sub(millis)))
+    }
+}
+
+impl PartialOrd for Instant {
+    fn partial_cmp(&self, other: &Self) -> Option<core::cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Instant {
+    fn cmp(&self, other: &Self) -> core::cmp::Ordering {
+        (self.0.wrapping_sub(other.0) as i32).cmp(&0)
+    }
+}
+
+impl Add<Duration> for Instant {
+    type Output = Instant;
+
+    fn add(self, rhs: Duration) -> Self::Output {
+        self.checked_add(rhs)
+            .expect("overflow when adding duration to instant")
+    }
+}
+
+impl AddAssign<Duration> for Instant {
+    fn add_assign(&mut self, rhs: Duration) {
+        *self = *self + rhs;
+    }
+}
+
+impl Sub<Duration> for Instant {
+    type Output = Instant;
+
+    fn sub(self, rhs: Duration) -> Self::Output {
+        self.checked_sub(rhs)
+            .expect("overflow when subtracting duration from instant")
+    }
+}
+
+impl SubAssign<Duration> for Instant {
+    fn sub_assign(&mut self, rhs: Duration) {
+        *self = *self - rhs;
+    }
+}
+
+impl Sub<Instant> for Instant {
+    type Output = Duration;
+
+    fn sub(self, rhs: Instant) -> Self::Output {
+        self.duration_since(rhs)
+    }
+}
+
+/// How long the keyboard has been running. Wraps back to zero after about 49 days.
+pub fn uptime() -> Duration {
+    Duration::from_millis(Instant::now().as_millis() as u64)
+}
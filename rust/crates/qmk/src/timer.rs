@@ -0,0 +1,272 @@
+use alloc::{boxed::Box, vec::Vec};
+use core::cell::RefCell;
+use critical_section::{Mutex, with};
+
+/// Returned by a deferred callback: 0 to stop, or the delay in milliseconds until it runs again.
+type Callback = Box<dyn FnMut() -> u32 + Send>;
+
+struct Deferred {
+    id: u32,
+    // taken out while the callback is running, so it can defer or cancel other work
+    callback: Option<Callback>,
+    cancelled: bool,
+    #[cfg(not(target_arch = "wasm32"))]
+    token: qmk_sys::deferred_token,
+    #[cfg(target_arch = "wasm32")]
+    due: u32,
+}
+
+impl Deferred {
+    #[cfg(not(target_arch = "wasm32"))]
+    fn cancel(&self) {
+        unsafe {
+            bindings::cancel_deferred_exec(self.token);
+        }
+    }
+
+    #[cfg(target_arch = "wasm32")]
+    fn cancel(&self) {}
+
+    #[cfg(not(target_arch = "wasm32"))]
+    fn extend(&mut self, delay_ms: u32) -> bool {
+        unsafe { bindings::extend_deferred_exec(self.token, delay_ms) }
+    }
+
+    #[cfg(target_arch = "wasm32")]
+    fn extend(&mut self, delay_ms: u32) -> bool {
+        self.due = Timer::read32().wrapping_add(delay_ms);
+        true
+    }
+}
+
+static DEFERRED: Mutex<RefCell<Vec<Deferred>>> = Mutex::new(RefCell::new(Vec::new()));
+static NEXT_ID: Mutex<RefCell<u32>> = Mutex::new(RefCell::new(1));
+
+#[cfg(target_arch = "wasm32")]
+mod bindings {
+    use core::sync::atomic::{AtomicU32, Ordering};
+
+    // the browser has no hardware timer, so time only moves when the page advances it
+    pub static CLOCK: AtomicU32 = AtomicU32::new(0);
+
+    pub unsafe fn timer_read32() -> u32 {
+        CLOCK.load(Ordering::SeqCst)
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+mod bindings {
+    pub use qmk_sys::{cancel_deferred_exec, defer_exec, extend_deferred_exec, timer_read32};
+
+    pub const INVALID_DEFERRED_TOKEN: qmk_sys::deferred_token = 0;
+}
+
+/// A handle to work scheduled with `Timer::defer` or `Timer::repeat`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct DeferToken(u32);
+
+impl DeferToken {
+    /// Stops the callback from running again. Returns false if it had already finished.
+    pub fn cancel(self) -> bool {
+        let (found, removed) = with(|cs| {
+            let mut deferred = DEFERRED.borrow_ref_mut(cs);
+            let Some(index) = deferred.iter().position(|d| d.id == self.0) else {
+                return (false, None);
+            };
+            if deferred[index].callback.is_none() {
+                // cancelled from inside its own callback, `run_deferred` will clean it up
+                deferred[index].cancelled = true;
+                return (true, None);
+            }
+            (true, Some(deferred.remove(index)))
+        });
+
+        if let Some(deferred) = removed {
+            deferred.cancel();
+        }
+
+        found
+    }
+
+    /// Pushes the next run back to `delay_ms` from now.
+    pub fn extend(self, delay_ms: u32) -> bool {
+        with(|cs| {
+            let mut deferred = DEFERRED.borrow_ref_mut(cs);
+            deferred
+                .iter_mut()
+                .find(|d| d.id == self.0)
+                .is_some_and(|d| d.extend(delay_ms))
+        })
+    }
+
+    /// Returns true if the callback is still scheduled to run.
+    pub fn is_pending(self) -> bool {
+        with(|cs| {
+            DEFERRED
+                .borrow_ref(cs)
+                .iter()
+                .any(|d| d.id == self.0 && !d.cancelled)
+        })
+    }
+}
+
+pub struct Timer;
+
+impl Timer {
+    /// Milliseconds since the keyboard started. Wraps after about 49 days.
+    pub fn read32() -> u32 {
+        unsafe { bindings::timer_read32() }
+    }
+
+    /// Milliseconds elapsed since `last`, a value from `Timer::read32`.
+    pub fn elapsed32(last: u32) -> u32 {
+        Self::read32().wrapping_sub(last)
+    }
+
+    /// Runs `callback` once, `delay_ms` from now.
+    ///
+    /// Returns `None` if QMK has no free deferred executor slots (see `MAX_DEFERRED_EXECUTORS`).
+    pub fn defer<F>(delay_ms: u32, callback: F) -> Option<DeferToken>
+    where
+        F: FnOnce() + Send + 'static,
+    {
+        let mut callback = Some(callback);
+        Self::defer_with(delay_ms, move || {
+            if let Some(callback) = callback.take() {
+                callback();
+            }
+            0
+        })
+    }
+
+    /// Runs `callback` every `interval_ms` until it returns false or the token is cancelled.
+    pub fn repeat<F>(interval_ms: u32, mut callback: F) -> Option<DeferToken>
+    where
+        F: FnMut() -> bool + Send + 'static,
+    {
+        Self::defer_with(
+            interval_ms,
+            move || if callback() { interval_ms } else { 0 },
+        )
+    }
+
+    /// Runs `callback` `delay_ms` from now, using QMK's `defer_exec` convention for the return
+    /// value: 0 to stop, otherwise the delay in milliseconds until it runs again.
+    pub fn defer_with<F>(delay_ms: u32, callback: F) -> Option<DeferToken>
+    where
+        F: FnMut() -> u32 + Send + 'static,
+    {
+        let id = with(|cs| {
+            let mut next_id = NEXT_ID.borrow_ref_mut(cs);
+            let id = *next_id;
+            // 0 is reserved so that a null callback argument is never a valid id
+            *next_id = next_id.wrapping_add(1).max(1);
+            id
+        });
+
+        #[cfg(not(target_arch = "wasm32"))]
+        let token = unsafe {
+            bindings::defer_exec(
+                delay_ms.max(1),
+                Some(deferred_trampoline),
+                id as usize as *mut core::ffi::c_void,
+            )
+        };
+        #[cfg(not(target_arch = "wasm32"))]
+        if token == bindings::INVALID_DEFERRED_TOKEN {
+            return None;
+        }
+
+        with(|cs| {
+            DEFERRED.borrow_ref_mut(cs).push(Deferred {
+                id,
+                callback: Some(Box::new(callback)),
+                cancelled: false,
+                #[cfg(not(target_arch = "wasm32"))]
+                token,
+                #[cfg(target_arch = "wasm32")]
+                due: Self::read32().wrapping_add(delay_ms),
+            });
+        });
+
+        Some(DeferToken(id))
+    }
+
+    /// Moves the simulated clock forward by `elapsed_ms`, running every callback that comes due
+    /// in order. This is the browser's replacement for QMK's timer and `deferred_exec_task`.
+    #[cfg(target_arch = "wasm32")]
+    pub fn advance(elapsed_ms: u32) {
+        use core::sync::atomic::Ordering;
+
+        let target = Self::read32().wrapping_add(elapsed_ms);
+        loop {
+            let next = with(|cs| {
+                DEFERRED
+                    .borrow_ref(cs)
+                    .iter()
+                    .filter(|d| d.callback.is_some())
+                    .map(|d| (d.id, d.due))
+                    .filter(|(_, due)| target.wrapping_sub(*due) as i32 >= 0)
+                    .min_by_key(|(_, due)| due.wrapping_sub(Self::read32()) as i32)
+            });
+            let Some((id, due)) = next else {
+                break;
+            };
+
+            // callbacks see the time they were due at, like they would on the keyboard
+            let now = if due.wrapping_sub(Self::read32()) as i32 > 0 {
+                due
+            } else {
+                Self::read32()
+            };
+            bindings::CLOCK.store(now, Ordering::SeqCst);
+            let next_delay = run_deferred(id);
+            with(|cs| {
+                if let Some(deferred) = DEFERRED.borrow_ref_mut(cs).iter_mut().find(|d| d.id == id)
+                {
+                    deferred.due = now.wrapping_add(next_delay);
+                }
+            });
+        }
+        bindings::CLOCK.store(target, Ordering::SeqCst);
+    }
+}
+
+/// Runs the callback with the given id, returning the delay until it should run again (0 means
+/// it has finished and has been removed).
+fn run_deferred(id: u32) -> u32 {
+    let callback = with(|cs| {
+        DEFERRED
+            .borrow_ref_mut(cs)
+            .iter_mut()
+            .find(|d| d.id == id)
+            .and_then(|d| d.callback.take())
+    });
+    let Some(mut callback) = callback else {
+        return 0;
+    };
+
+    let next_delay = callback();
+
+    with(|cs| {
+        let mut deferred = DEFERRED.borrow_ref_mut(cs);
+        let Some(index) = deferred.iter().position(|d| d.id == id) else {
+            return 0;
+        };
+        if next_delay == 0 || deferred[index].cancelled {
+            deferred.remove(index);
+            0
+        } else {
+            deferred[index].callback = Some(callback);
+            next_delay
+        }
+    })
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+unsafe extern "C" fn deferred_trampoline(
+    _trigger_time: u32,
+    cb_arg: *mut core::ffi::c_void,
+) -> u32 {
+    run_deferred(cb_arg as usize as u32)
+}
@@ -1,9 +1,21 @@
 use glob::glob;
+use proc_macro2::{TokenStream, TokenTree};
 use qmk::EEPROM_BYTES;
+use qmk::logging::string_id;
+use qmk_callback_parsing::custom_keycodes::{CustomKeycodes, PROCESS_RECORD_USER};
+use qmk_callback_parsing::keymap::Keymap;
+use qmk_callback_parsing::keymap_json::{EncoderTurn, KeymapJson, keycode_to_qmk};
 use qmk_callback_parsing::{QmkCallback, Signature};
-use quote::ToTokens;
-use std::fs;
-use syn::parse::Parse;
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    env, fs,
+    path::Path,
+};
+use syn::{
+    Expr, Ident, LitStr, Macro, Token,
+    parse::{Parse, ParseStream, Parser},
+    visit::Visit,
+};
 
 pub fn write_glue_code(path: impl Into<String>) {
     // re-run if any of the source files change
@@ -12,6 +24,8 @@ pub fn write_glue_code(path: impl Into<String>) {
     println!("cargo:rerun-if-changed=build.rs");
     println!("cargo:rerun-if-changed={}", path);
     let mut attributes = Vec::new();
+    let mut encoder_map = false;
+    let mut rules_flags = BTreeSet::new();
 
     for entry in glob("src/**/*.rs").unwrap() {
         let Ok(entry) = entry else {
@@ -21,19 +35,26 @@ pub fn write_glue_code(path: impl Into<String>) {
         let file = fs::read_to_string(entry).unwrap();
         let file = syn::parse_file(&file).unwrap();
 
-        // look for a constant named "NUM_LAYERS"
-        let mut num_layers = None;
-        for item in &file.items {
-            if let syn::Item::Const(constant) = item {
-                if constant.ident == "NUM_LAYERS" {
-                    if let syn::Expr::Lit(lit) = &*constant.expr {
-                        if let syn::Lit::Int(int) = &lit.lit {
-                            num_layers = int.base10_parse::<u8>().ok();
-                        }
-                    }
+        // the `keymap!` in this file, if it has one. One that doesn't parse is left for the
+        // macro to report
+        let keymap = find_macro(&file, "keymap").and_then(|tokens| parse_keymap(tokens).ok());
+        let custom_keycodes = find_macro(&file, "custom_keycodes").is_some();
+
+        // `combos!` and friends, with how many entries each has
+        let tables = file
+            .items
+            .iter()
+            .filter_map(|item| match item {
+                syn::Item::Macro(item) => {
+                    let name = &item.mac.path.segments.last()?.ident;
+                    let table = TABLE_MACROS
+                        .iter()
+                        .find(|(macro_name, ..)| name == macro_name)?;
+                    Some((table, count_entries(item.mac.tokens.clone())))
                 }
-            }
-        }
+                _ => None,
+            })
+            .collect::<Vec<_>>();
 
         let fn_attributes = file
             .items
@@ -64,10 +85,29 @@ pub fn write_glue_code(path: impl Into<String>) {
             attributes.push(attr);
         }
 
-        if let Some(num_layers) = num_layers {
+        // `custom_keycodes!` generates `process_record_user`, which the scan above can't see
+        if custom_keycodes {
+            let signature = syn::parse_str::<Signature>(PROCESS_RECORD_USER).unwrap();
+            let callback = QmkCallback::new(String::from("process_record_user"), signature);
+            attributes.push(callback.to_c_fn());
+        }
+
+        if let Some(keymap) = keymap {
+            let num_layers = keymap.layers.len();
             attributes.push(format!(
                 "const extern uint16_t PROGMEM keymaps[{num_layers}][MATRIX_ROWS][MATRIX_COLS];"
             ));
+            if keymap.layers.iter().any(|layer| layer.encoders.is_some()) {
+                attributes.push(format!(
+                    "const extern uint16_t PROGMEM encoder_map[{num_layers}][NUM_ENCODERS][NUM_DIRECTIONS];"
+                ));
+                encoder_map = true;
+            }
+        }
+
+        for ((_, declaration, flag), entries) in tables {
+            attributes.push(declaration.replace("{entries}", &entries.to_string()));
+            rules_flags.insert(*flag);
         }
     }
 
@@ -89,5 +129,288 @@ pub fn write_glue_code(path: impl Into<String>) {
     config_file += line;
     config_file += "\n";
     config_file += &format!("#define EECONFIG_USER_DATA_SIZE {}\n", EEPROM_BYTES);
+    if encoder_map {
+        config_file += "#ifndef ENCODER_MAP_ENABLE\n";
+        config_file +=
+            "#    error \"keymap! has encoders, set ENCODER_MAP_ENABLE = yes in rules.mk\"\n";
+        config_file += "#endif\n";
+    }
     fs::write(format!("{}/config.h", dir), config_file).unwrap();
+
+    // the features the tables need, in the same kind of section at the end of rules.mk
+    let mut rules_file = fs::read_to_string(format!("{}/rules.mk", dir)).unwrap_or_default();
+    let line =
+        "\n# ### NULLPTR'S STUFF BEGINS HERE -- DO NOT TOUCH! DON'T EVEN MODIFY THIS COMMENT!";
+    if let Some(pos) = rules_file.find(line) {
+        rules_file.truncate(pos);
+    }
+    rules_file += line;
+    rules_file += "\n";
+    for flag in rules_flags {
+        rules_file += &format!("{flag} = yes\n");
+    }
+    fs::write(format!("{}/rules.mk", dir), rules_file).unwrap();
+}
+
+// the macros generating QMK tables: the macro, the table's C declaration with `{entries}` for its
+// length, and the rules.mk flag that makes QMK use it
+const TABLE_MACROS: [(&str, &str, &str); 3] = [
+    (
+        "combos",
+        "extern combo_t key_combos[{entries}];",
+        "COMBO_ENABLE",
+    ),
+    (
+        "key_overrides",
+        "extern const key_override_t *key_overrides[{entries}];",
+        "KEY_OVERRIDE_ENABLE",
+    ),
+    (
+        "tap_dance",
+        "extern tap_dance_action_t tap_dance_actions[{entries}];",
+        "TAP_DANCE_ENABLE",
+    ),
+];
+
+/// The number of comma-separated entries in a table macro like `combos!`. Commas inside
+/// brackets, parentheses and braces belong to an entry, so they don't count.
+fn count_entries(tokens: TokenStream) -> usize {
+    let mut entries = 0;
+    let mut in_entry = false;
+    for token in tokens {
+        match token {
+            TokenTree::Punct(punct) if punct.as_char() == ',' => in_entry = false,
+            _ if !in_entry => {
+                entries += 1;
+                in_entry = true;
+            }
+            _ => {}
+        }
+    }
+    entries
+}
+
+/// The tokens of the top-level `name!` in `file`, like `keymap!`, if it has one.
+fn find_macro(file: &syn::File, name: &str) -> Option<TokenStream> {
+    file.items.iter().find_map(|item| match item {
+        syn::Item::Macro(item) if item.mac.path.segments.last()?.ident == name => {
+            Some(item.mac.tokens.clone())
+        }
+        _ => None,
+    })
+}
+
+/// Parses `keymap!` the way the macro does, loading its layers from `json:` if it has one.
+fn parse_keymap(tokens: TokenStream) -> syn::Result<Keymap> {
+    let mut keymap = syn::parse2::<Keymap>(tokens)?;
+    let dir = env::var("CARGO_MANIFEST_DIR").unwrap_or_else(|_| String::from("."));
+    if let Some(path) = keymap.load_json(Path::new(&dir))? {
+        println!("cargo:rerun-if-changed={}", path.display());
+    }
+    Ok(keymap)
+}
+
+/// Writes the `keymap!` in `src/**/*.rs` to `path` as a QMK `keymap.json`, for the configurator
+/// and other QMK tools. Keycodes from `custom_keycodes!` are written as the `QK_USER_n` it gives
+/// them. Nothing is written for a keymap that is itself loaded from JSON.
+///
+/// QMK builds a `keymap.json` it finds next to `keymap.c` into a second keymap, so `path` should
+/// be outside the keymap's folder.
+pub fn write_keymap_json(path: impl Into<String>) {
+    let path: String = path.into();
+    let mut keymap = None;
+    let mut custom = BTreeMap::new();
+
+    for entry in glob("src/**/*.rs").unwrap() {
+        let Ok(entry) = entry else {
+            continue;
+        };
+        let file = fs::read_to_string(entry).unwrap();
+        let file = syn::parse_file(&file).unwrap();
+
+        if let Some(tokens) = find_macro(&file, "keymap") {
+            keymap = parse_keymap(tokens).ok();
+        }
+        if let Some(tokens) = find_macro(&file, "custom_keycodes")
+            && let Ok(keycodes) = syn::parse2::<CustomKeycodes>(tokens)
+        {
+            for (i, keycode) in keycodes.keycodes.iter().enumerate() {
+                custom.insert(
+                    format!("CustomKeycode::{}", keycode.name),
+                    format!("QK_USER_{i}"),
+                );
+            }
+        }
+    }
+
+    let Some(keymap) = keymap.filter(|keymap| keymap.json.is_none()) else {
+        return;
+    };
+    match keymap_json(&keymap, &custom) {
+        Ok(json) => fs::write(&path, json).unwrap(),
+        Err(e) => println!("cargo:warning=Not writing {path}: {e}"),
+    }
+}
+
+fn keymap_json(keymap: &Keymap, custom: &BTreeMap<String, String>) -> Result<String, String> {
+    let layer_names = keymap
+        .layers
+        .iter()
+        .filter_map(|layer| layer.name.as_ref().map(|name| name.to_string()))
+        .collect::<Vec<_>>();
+    let export = |key: &Expr| keycode_to_qmk(key, &layer_names, custom);
+
+    let layers = keymap
+        .layers
+        .iter()
+        .map(|layer| layer.keys.iter().map(export).collect())
+        .collect::<Result<Vec<_>, _>>()?;
+
+    // layers without encoders are transparent, as in the `encoder_map` the macro generates
+    let num_encoders = keymap
+        .layers
+        .iter()
+        .filter_map(|layer| layer.encoders.as_ref())
+        .map(|encoders| encoders.turns.len())
+        .max()
+        .unwrap_or(0);
+    let encoders = if num_encoders == 0 {
+        Vec::new()
+    } else {
+        keymap
+            .layers
+            .iter()
+            .map(|layer| match &layer.encoders {
+                Some(encoders) => encoders
+                    .turns
+                    .iter()
+                    .map(|(ccw, cw)| {
+                        Ok(EncoderTurn {
+                            ccw: export(ccw)?,
+                            cw: export(cw)?,
+                        })
+                    })
+                    .collect(),
+                None => {
+                    let transparent = EncoderTurn {
+                        ccw: String::from("KC_TRNS"),
+                        cw: String::from("KC_TRNS"),
+                    };
+                    Ok(vec![transparent; num_encoders])
+                }
+            })
+            .collect::<Result<Vec<_>, String>>()?
+    };
+
+    // QMK wants the layout macro's full name
+    let layout = match keymap.layout.as_ref().map(LitStr::value) {
+        Some(layout) if layout.starts_with("LAYOUT") => layout,
+        Some(layout) => format!("LAYOUT_{layout}"),
+        None => String::from("LAYOUT"),
+    };
+
+    let json = KeymapJson {
+        version: 1,
+        keyboard: keymap.keeb.value(),
+        layout,
+        layers,
+        encoders,
+        ..KeymapJson::default()
+    };
+    let mut json = serde_json::to_string_pretty(&json).map_err(|e| e.to_string())?;
+    json.push('\n');
+    Ok(json)
+}
+
+// the macros from `qmk::logging`, matched by name since the scan can't resolve paths
+const LOG_MACROS: [&str; 7] = ["log", "error", "warn", "info", "debug", "trace", "qmk_log"];
+
+/// Writes the table the `log_decoder` crate needs for the `deferred_log` feature: every format
+/// string passed to a logging macro in files matching `sources`, keyed by its `string_id`. Each
+/// line is the id in hex, the format string with tabs, newlines and backslashes escaped, and the
+/// comma-separated `file:line` places it is logged from.
+pub fn write_log_table(sources: &[&str], path: impl Into<String>) {
+    let path: String = path.into();
+    let mut table = BTreeMap::<u32, (String, Vec<String>)>::new();
+
+    for pattern in sources {
+        for entry in glob(pattern).unwrap() {
+            let Ok(entry) = entry else {
+                continue;
+            };
+            println!("cargo:rerun-if-changed={}", entry.display());
+
+            let file = fs::read_to_string(&entry).unwrap();
+            let file = syn::parse_file(&file).unwrap();
+            let mut visitor = LogCalls::default();
+            visitor.visit_file(&file);
+
+            for (format, line) in visitor.calls {
+                let id = string_id(&format);
+                let location = format!("{}:{}", entry.display(), line);
+                let (existing, locations) = table
+                    .entry(id)
+                    .or_insert_with(|| (format.clone(), Vec::new()));
+                if *existing != format {
+                    panic!(
+                        "log format strings {:?} and {:?} have the same id {:08x}, reword one of them",
+                        existing, format, id
+                    );
+                }
+                locations.push(location);
+            }
+        }
+    }
+
+    let mut out = String::from("# id\tformat\tlocations\n");
+    for (id, (format, locations)) in table {
+        out += &format!("{:08x}\t{}\t{}\n", id, escape(&format), locations.join(","));
+    }
+    fs::write(&path, out).unwrap();
+}
+
+#[derive(Default)]
+struct LogCalls {
+    // format string and line of each call
+    calls: Vec<(String, usize)>,
+}
+
+impl<'ast> Visit<'ast> for LogCalls {
+    fn visit_macro(&mut self, mac: &'ast Macro) {
+        let Some(name) = mac.path.segments.last().map(|segment| &segment.ident) else {
+            return;
+        };
+        if !LOG_MACROS.iter().any(|log_macro| name == log_macro) {
+            return;
+        }
+
+        let is_log = name == "log";
+        let parser = |input: ParseStream| {
+            if input.peek(Ident) && input.peek2(Token![:]) {
+                input.parse::<Ident>()?;
+                input.parse::<Token![:]>()?;
+                input.parse::<Expr>()?;
+                input.parse::<Token![,]>()?;
+            }
+            if is_log {
+                input.parse::<Expr>()?;
+                input.parse::<Token![,]>()?;
+            }
+            let format = input.parse::<LitStr>()?;
+            input.parse::<TokenStream>()?;
+            Ok(format)
+        };
+
+        if let Ok(format) = parser.parse2(mac.tokens.clone()) {
+            let line = name.span().start().line;
+            self.calls.push((format.value(), line));
+        }
+    }
+}
+
+fn escape(format: &str) -> String {
+    format
+        .replace('\\', "\\\\")
+        .replace('\t', "\\t")
+        .replace('\n', "\\n")
 }
@@ -0,0 +1,119 @@
+use syn::{Expr, Ident, Path, Token, bracketed, parse::Parse, token};
+
+/// The QMK callback `custom_keycodes!` generates, so the build script writes its glue too.
+pub const PROCESS_RECORD_USER: &str = "(uint16_t, keyrecord_t*) -> bool";
+
+/// The input of `custom_keycodes!`, shared with the build script so both number the keycodes
+/// the same way.
+pub struct CustomKeycodes {
+    /// What the generated `process_record_user` does with keys the handlers don't stop, as in
+    /// `fallback: input::process_record`.
+    pub fallback: Option<Path>,
+    pub keycodes: Vec<CustomKeycode>,
+}
+
+impl Parse for CustomKeycodes {
+    fn parse(input: syn::parse::ParseStream) -> syn::Result<Self> {
+        let mut fallback = None;
+        let mut keycodes = Vec::new();
+        while !input.is_empty() {
+            // keycodes are named like variants, so `fallback` can't be one
+            let setting = input.fork();
+            if setting
+                .parse::<Ident>()
+                .is_ok_and(|ident| ident == "fallback")
+                && setting.peek(Token![:])
+            {
+                let keyword = input.parse::<Ident>()?;
+                input.parse::<Token![:]>()?;
+                if fallback.replace(input.parse::<Path>()?).is_some() {
+                    return Err(syn::Error::new(keyword.span(), "`fallback` is given twice"));
+                }
+            } else {
+                keycodes.push(input.parse::<CustomKeycode>()?);
+            }
+            if !input.is_empty() {
+                input.parse::<Token![,]>()?;
+            }
+        }
+        Ok(CustomKeycodes { fallback, keycodes })
+    }
+}
+
+/// `Reset: sequence [KC_ENTER, KC_DOWN]` or `Boot: |record| { .. }`
+pub struct CustomKeycode {
+    /// The keycode's variant in the generated `CustomKeycode` enum.
+    pub name: Ident,
+    pub action: CustomKeycodeAction,
+}
+
+// only lives while the macros expand, so the size doesn't matter
+#[allow(clippy::large_enum_variant)]
+pub enum CustomKeycodeAction {
+    /// Keycodes tapped one after another when the key is pressed.
+    Sequence(Vec<Expr>),
+    /// A closure taking the `&KeyRecord` of every press and release, returning whether QMK should
+    /// carry on processing the key.
+    Handler(Expr),
+}
+
+impl Parse for CustomKeycode {
+    fn parse(input: syn::parse::ParseStream) -> syn::Result<Self> {
+        let name = input.parse::<Ident>()?;
+        input.parse::<Token![:]>()?;
+
+        let action = if input.peek(Ident) && input.peek2(token::Bracket) {
+            let kind = input.parse::<Ident>()?;
+            if kind != "sequence" {
+                return Err(syn::Error::new(
+                    kind.span(),
+                    format!("Unknown action `{kind}`, expected `sequence [..]` or a closure"),
+                ));
+            }
+            let content;
+            bracketed!(content in input);
+            let keys = content.parse_terminated(Expr::parse, Token![,])?;
+            CustomKeycodeAction::Sequence(keys.into_iter().collect())
+        } else {
+            CustomKeycodeAction::Handler(input.parse::<Expr>()?)
+        };
+
+        Ok(CustomKeycode { name, action })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_sequences_and_handlers() {
+        let keycodes: CustomKeycodes = syn::parse_quote!(
+            Reset: sequence [KC_ENTER, KC_DOWN],
+            Hello: |record| true,
+        );
+        assert!(keycodes.fallback.is_none());
+        assert_eq!(keycodes.keycodes.len(), 2);
+        assert!(matches!(
+            &keycodes.keycodes[0].action,
+            CustomKeycodeAction::Sequence(keys) if keys.len() == 2
+        ));
+        assert!(matches!(
+            keycodes.keycodes[1].action,
+            CustomKeycodeAction::Handler(_)
+        ));
+
+        let keycodes = syn::parse_str::<CustomKeycodes>("Reset: keys [KC_A]");
+        assert!(keycodes.is_err());
+    }
+
+    #[test]
+    fn parses_fallback() {
+        let keycodes: CustomKeycodes = syn::parse_quote!(
+            fallback: crate::input::process_record,
+            Reset: sequence [KC_ENTER],
+        );
+        assert!(keycodes.fallback.is_some());
+        assert_eq!(keycodes.keycodes.len(), 1);
+    }
+}
@@ -0,0 +1,326 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use proc_macro2::{Group, Span, TokenStream, TokenTree};
+use quote::ToTokens;
+use syn::{Expr, Ident, LitStr, Token, braced, bracketed, parenthesized, parse::Parse, token};
+
+use crate::keymap_json::{KeymapJson, keycode_from_qmk};
+
+/// The input of `keymap!`, shared with the build script so both see the same layers.
+pub struct Keymap {
+    pub keeb: LitStr,
+    /// The layout named after the keyboard, if any.
+    pub layout: Option<LitStr>,
+    pub layers: Vec<Layer>,
+    /// The QMK `keymap.json` the layers are loaded from, as in `json: "keymap.json"`.
+    pub json: Option<LitStr>,
+    /// Names for the layers loaded from `json`, as in `layers: [base, lower]`.
+    pub json_layer_names: Vec<Ident>,
+    /// The folder the layers are drawn to, as in `svg: "docs"`.
+    pub svg: Option<LitStr>,
+    /// Whether problems like unreachable layers are warnings or errors, as in `lints: deny`.
+    pub lints: Option<Ident>,
+}
+
+impl Parse for Keymap {
+    fn parse(input: syn::parse::ParseStream) -> syn::Result<Self> {
+        // comma seperated arguments
+        let mut layers = Vec::new();
+        let mut json = None;
+        let mut json_layer_names = Vec::new();
+        let mut svg = None;
+        let mut lints = None;
+        let keeb = input.parse::<LitStr>()?;
+        let mut layout = None;
+        if input.peek(Token![,]) && input.peek2(LitStr) {
+            input.parse::<Token![,]>()?;
+            layout = Some(input.parse::<LitStr>()?);
+        }
+        if input.peek(Token![,]) {
+            input.parse::<Token![,]>()?;
+            while !input.is_empty() {
+                // `json:`, `layers:`, `svg:` and `lints:` are told apart from layers with those names by what
+                // follows the colon
+                let setting = input.fork();
+                let is_setting = setting.parse::<Ident>().is_ok()
+                    && setting.parse::<Token![:]>().is_ok()
+                    && !setting.peek(token::Brace);
+                let name = if is_setting {
+                    let keyword = input.parse::<Ident>()?;
+                    input.parse::<Token![:]>()?;
+                    if keyword == "json" {
+                        json = Some(input.parse::<LitStr>()?);
+                    } else if keyword == "layers" {
+                        let content;
+                        bracketed!(content in input);
+                        json_layer_names = content
+                            .parse_terminated(Ident::parse, Token![,])?
+                            .into_iter()
+                            .collect();
+                    } else if keyword == "svg" {
+                        svg = Some(input.parse::<LitStr>()?);
+                    } else if keyword == "lints" {
+                        lints = Some(input.parse::<Ident>()?);
+                    } else {
+                        return Err(syn::Error::new(
+                            keyword.span(),
+                            format!(
+                                "Expected a layer, `json`, `layers`, `svg` or `lints`, found `{keyword}:`"
+                            ),
+                        ));
+                    }
+                    if !input.is_empty() {
+                        input.parse::<Token![,]>()?;
+                    }
+                    continue;
+                } else if input.peek(Ident) {
+                    let name = input.parse::<Ident>()?;
+                    input.parse::<Token![:]>()?;
+                    Some(name)
+                } else {
+                    None
+                };
+                let content;
+                let brace = braced!(content in input);
+                let mut layer: Layer = content.parse()?;
+                layer.name = name;
+                layer.end = brace.span.close();
+                layers.push(layer);
+                if !input.is_empty() {
+                    input.parse::<Token![,]>()?;
+                }
+            }
+        }
+
+        if let Some(json) = &json {
+            if !layers.is_empty() {
+                return Err(syn::Error::new(
+                    json.span(),
+                    "The layers come from the JSON file, so the keymap can't have its own",
+                ));
+            }
+        } else if let Some(name) = json_layer_names.first() {
+            return Err(syn::Error::new(
+                name.span(),
+                "`layers: [..]` names the layers from `json`, name the keymap's own layers instead",
+            ));
+        }
+
+        Ok(Keymap {
+            keeb,
+            layout,
+            layers,
+            json,
+            json_layer_names,
+            svg,
+            lints,
+        })
+    }
+}
+
+impl Keymap {
+    /// Fills in the layers, and the layout unless one is named, from the `keymap.json` in `json`,
+    /// relative to `dir`. Returns the file's path, so callers can rebuild when it changes.
+    pub fn load_json(&mut self, dir: &Path) -> syn::Result<Option<PathBuf>> {
+        let Some(json) = &self.json else {
+            return Ok(None);
+        };
+        let span = json.span();
+        let error = |message: String| syn::Error::new(span, message);
+
+        let path = dir.join(json.value());
+        let source = fs::read_to_string(&path)
+            .map_err(|e| error(format!("Failed to read {}: {e}", path.display())))?;
+        let keymap: KeymapJson = serde_json::from_str(&source)
+            .map_err(|e| error(format!("Failed to parse {}: {e}", path.display())))?;
+
+        let keeb = self.keeb.value();
+        if !keymap.keyboard.is_empty() && keymap.keyboard != keeb {
+            return Err(error(format!(
+                "{} is for {}, not {keeb}",
+                path.display(),
+                keymap.keyboard
+            )));
+        }
+        if self.layout.is_none() && !keymap.layout.is_empty() {
+            self.layout = Some(LitStr::new(&keymap.layout, span));
+        }
+
+        let names = &self.json_layer_names;
+        if !names.is_empty() && names.len() != keymap.layers.len() {
+            return Err(error(format!(
+                "{} has {} layers but {} are named",
+                path.display(),
+                keymap.layers.len(),
+                names.len()
+            )));
+        }
+
+        // errors in the keys point at the file's name
+        let import = |x: usize, keycode: &str| {
+            let key = keycode_from_qmk(keycode)
+                .map_err(|e| error(format!("Layer {x} of the JSON: {e}")))?;
+            syn::parse2(respan(key.to_token_stream(), span))
+        };
+        for (x, keys) in keymap.layers.iter().enumerate() {
+            let keys = keys
+                .iter()
+                .map(|keycode| import(x, keycode))
+                .collect::<syn::Result<Vec<_>>>()?;
+            let encoders = match keymap.encoders.get(x) {
+                Some(turns) => Some(Encoders {
+                    span,
+                    turns: turns
+                        .iter()
+                        .map(|turn| Ok((import(x, &turn.ccw)?, import(x, &turn.cw)?)))
+                        .collect::<syn::Result<Vec<_>>>()?,
+                }),
+                None => None,
+            };
+            self.layers.push(Layer {
+                name: names.get(x).cloned(),
+                keys,
+                encoders,
+                end: span,
+            });
+        }
+        Ok(Some(path))
+    }
+}
+
+fn respan(tokens: TokenStream, span: Span) -> TokenStream {
+    tokens
+        .into_iter()
+        .map(|mut token| {
+            if let TokenTree::Group(group) = &token {
+                let mut respanned = Group::new(group.delimiter(), respan(group.stream(), span));
+                respanned.set_span(span);
+                token = TokenTree::Group(respanned);
+            }
+            token.set_span(span);
+            token
+        })
+        .collect()
+}
+
+pub struct Layer {
+    /// The name before the layer, as in `lower: { .. }`.
+    pub name: Option<Ident>,
+    pub keys: Vec<Expr>,
+    /// What each encoder sends on this layer, from `encoders: [(ccw, cw), ..]` after the keys.
+    pub encoders: Option<Encoders>,
+    /// The closing brace, where missing keys are reported.
+    pub end: Span,
+}
+
+pub struct Encoders {
+    /// The `encoders` keyword, where a wrong count is reported.
+    pub span: Span,
+    /// The counter-clockwise and clockwise keycodes, in QMK's `ENCODER_CCW_CW` order.
+    pub turns: Vec<(Expr, Expr)>,
+}
+
+impl Parse for Encoders {
+    fn parse(input: syn::parse::ParseStream) -> syn::Result<Self> {
+        let keyword = input.parse::<Ident>()?;
+        input.parse::<Token![:]>()?;
+        let content;
+        bracketed!(content in input);
+        let mut turns = Vec::new();
+        while !content.is_empty() {
+            let pair;
+            parenthesized!(pair in content);
+            let ccw = pair.parse::<Expr>()?;
+            pair.parse::<Token![,]>()?;
+            let cw = pair.parse::<Expr>()?;
+            if !pair.is_empty() {
+                pair.parse::<Token![,]>()?;
+            }
+            turns.push((ccw, cw));
+            if !content.is_empty() {
+                content.parse::<Token![,]>()?;
+            }
+        }
+        Ok(Encoders {
+            span: keyword.span(),
+            turns,
+        })
+    }
+}
+
+impl Parse for Layer {
+    fn parse(input: syn::parse::ParseStream) -> syn::Result<Self> {
+        let mut keys = Vec::new();
+        let mut encoders = None;
+        while !input.is_empty() {
+            if input.peek(Ident) && input.peek2(Token![:]) && !input.peek2(Token![::]) {
+                let keyword = input.fork().parse::<Ident>()?;
+                if keyword != "encoders" {
+                    return Err(syn::Error::new(
+                        keyword.span(),
+                        format!("Expected a keycode or `encoders`, found `{keyword}:`"),
+                    ));
+                }
+                encoders = Some(input.parse::<Encoders>()?);
+                if input.peek(Token![,]) {
+                    input.parse::<Token![,]>()?;
+                }
+                if !input.is_empty() {
+                    return Err(input.error("The encoders go after the layer's keys"));
+                }
+                break;
+            }
+            let key: Expr = input.parse()?;
+            keys.push(key);
+            if input.peek(Token![,]) {
+                input.parse::<Token![,]>()?;
+            }
+        }
+        Ok(Layer {
+            name: None,
+            keys,
+            encoders,
+            end: input.span(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_encoders_after_keys() {
+        let layer: Layer =
+            syn::parse_quote!(KC_A, KC_B, encoders: [(KC_VOLD, KC_VOLU), (KC_NO, KC_NO)],);
+        assert_eq!(layer.keys.len(), 2);
+        assert_eq!(layer.encoders.map(|encoders| encoders.turns.len()), Some(2));
+
+        let layer = syn::parse_str::<Layer>("encoders: [(KC_VOLD, KC_VOLU)], KC_A");
+        assert!(layer.is_err());
+    }
+
+    #[test]
+    fn parses_json_settings() {
+        let keymap: Keymap = syn::parse_quote!(
+            "sofle/rev1",
+            json: "keymap.json",
+            layers: [base, lower],
+        );
+        assert_eq!(
+            keymap.json.map(|json| json.value()).as_deref(),
+            Some("keymap.json")
+        );
+        assert_eq!(keymap.json_layer_names.len(), 2);
+        assert!(keymap.layers.is_empty());
+
+        // a layer can still be called `json`
+        let keymap: Keymap = syn::parse_quote!("sofle/rev1", json: { KC_A }, layers: { KC_B });
+        assert!(keymap.json.is_none());
+        assert_eq!(keymap.layers.len(), 2);
+
+        let keymap = syn::parse_str::<Keymap>(r#""sofle/rev1", json: "keymap.json", { KC_A }"#);
+        assert!(keymap.is_err());
+    }
+}
@@ -0,0 +1,275 @@
+use std::collections::BTreeMap;
+
+use quote::{ToTokens, format_ident, quote};
+use serde::{Deserialize, Serialize};
+use syn::{BinOp, Expr, Lit, Token, punctuated::Punctuated};
+
+/// The parts of QMK's `keymap.json` that `keymap!` reads and writes. Other fields, like `config`,
+/// are ignored when reading.
+#[derive(Serialize, Deserialize, Debug, Default)]
+pub struct KeymapJson {
+    #[serde(default = "version")]
+    pub version: u32,
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub keyboard: String,
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub keymap: String,
+    #[serde(default)]
+    pub layout: String,
+    pub layers: Vec<Vec<String>>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub encoders: Vec<Vec<EncoderTurn>>,
+}
+
+fn version() -> u32 {
+    1
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct EncoderTurn {
+    pub ccw: String,
+    pub cw: String,
+}
+
+/// The QMK keycode functions with a `qmk::keys` macro of the same name in lowercase.
+const KEYCODE_MACROS: [&str; 34] = [
+    "MO", "TG", "TO", "DF", "PDF", "OSL", "TT", "LT", "LM", "TD", "MT", "OSM", "S", "C", "LCTL",
+    "LSFT", "LALT", "LGUI", "RCTL", "RSFT", "RALT", "RGUI", "HYPR", "MEH", "LCAG", "LSG", "LAG",
+    "LCS", "LCA", "LSA", "RSG", "RAG", "RCS", "RSA",
+];
+
+/// The `X_T(key)` mod-taps, which are `mt!(MOD_X, key)` in Rust.
+const MOD_TAPS: [&str; 10] = [
+    "LCTL", "LSFT", "LALT", "LGUI", "RCTL", "RSFT", "RALT", "RGUI", "HYPR", "MEH",
+];
+
+/// Turns a keycode from a `keymap.json`, like `LT(1,KC_SPC)` or `LSFT_T(KC_A)`, into the Rust
+/// `keymap!` would have for it. Names inside a call get the full `::qmk::keys` path, since the
+/// macros don't look them up there themselves.
+pub fn keycode_from_qmk(keycode: &str) -> Result<Expr, String> {
+    let expr =
+        syn::parse_str::<Expr>(keycode).map_err(|_| format!("`{keycode}` isn't a keycode"))?;
+    from_qmk(&expr, false).map_err(|e| format!("{e} in `{keycode}`"))
+}
+
+fn from_qmk(expr: &Expr, nested: bool) -> Result<Expr, String> {
+    match expr {
+        Expr::Path(path) => {
+            let Some(name) = path.path.get_ident() else {
+                return Err(format!("`{}` isn't a keycode", path.to_token_stream()));
+            };
+            Ok(if nested {
+                syn::parse_quote!(::qmk::keys::#name)
+            } else {
+                expr.clone()
+            })
+        }
+        Expr::Lit(_) => Ok(expr.clone()),
+        Expr::Paren(paren) => from_qmk(&paren.expr, nested),
+        Expr::Binary(binary) if matches!(binary.op, BinOp::BitOr(_)) => {
+            let left = from_qmk(&binary.left, true)?;
+            let right = from_qmk(&binary.right, true)?;
+            Ok(syn::parse_quote!(#left | #right))
+        }
+        Expr::Call(call) => {
+            let Expr::Path(function) = &*call.func else {
+                return Err(format!("`{}` isn't a keycode", call.func.to_token_stream()));
+            };
+            let Some(function) = function.path.get_ident() else {
+                return Err(format!("`{}` isn't a keycode", function.to_token_stream()));
+            };
+            let function = function.to_string();
+            let args = call
+                .args
+                .iter()
+                .map(|arg| from_qmk(arg, true))
+                .collect::<Result<Vec<_>, _>>()?;
+
+            // the configurator wraps keycodes it doesn't know in `ANY`
+            if function == "ANY" && args.len() == 1 {
+                return from_qmk(&call.args[0], nested);
+            }
+            if let Some(mods) = function
+                .strip_suffix("_T")
+                .filter(|mods| MOD_TAPS.contains(mods))
+            {
+                let mods = format_ident!("MOD_{mods}");
+                return Ok(syn::parse_quote!(
+                    ::qmk::mt!(::qmk::keys::#mods, #(#args),*)
+                ));
+            }
+            if KEYCODE_MACROS.contains(&function.as_str()) {
+                let name = format_ident!("{}", function.to_lowercase());
+                return Ok(syn::parse_quote!(::qmk::#name!(#(#args),*)));
+            }
+            Err(format!("`{function}` has no qmk macro"))
+        }
+        _ => Err(format!("`{}` isn't a keycode", expr.to_token_stream())),
+    }
+}
+
+/// Turns a key from `keymap!` into its `keymap.json` form, the other way around from
+/// [`keycode_from_qmk`]. Layer names become their index in `layers`, and the paths in `custom`
+/// become what they map to, e.g. `CustomKeycode::Reset` to its `QK_USER_n`.
+pub fn keycode_to_qmk(
+    key: &Expr,
+    layers: &[String],
+    custom: &BTreeMap<String, String>,
+) -> Result<String, String> {
+    match key {
+        Expr::Path(path) => {
+            let segments = path
+                .path
+                .segments
+                .iter()
+                .map(|segment| segment.ident.to_string())
+                .collect::<Vec<_>>();
+            if let Some(keycode) = custom.get(&segments.join("::")) {
+                return Ok(keycode.clone());
+            }
+            let name = match segments.as_slice() {
+                [name] => name,
+                [.., keys, name] if keys == "keys" => name,
+                _ => return Err(format!("`{}` has no QMK name", segments.join("::"))),
+            };
+            if let Some(index) = layers.iter().position(|layer| layer == name) {
+                return Ok(index.to_string());
+            }
+            Ok(match name.as_str() {
+                "_______" => String::from("KC_TRNS"),
+                "XXXXXXX" => String::from("KC_NO"),
+                _ => name.clone(),
+            })
+        }
+        Expr::Lit(lit) => match &lit.lit {
+            Lit::Int(int) => {
+                let digits = int.to_string();
+                Ok(digits
+                    .strip_suffix(int.suffix())
+                    .unwrap_or(&digits)
+                    .to_string())
+            }
+            _ => Err(format!("`{}` isn't a keycode", lit.to_token_stream())),
+        },
+        Expr::Paren(paren) => keycode_to_qmk(&paren.expr, layers, custom),
+        Expr::Group(group) => keycode_to_qmk(&group.expr, layers, custom),
+        Expr::Cast(cast) => keycode_to_qmk(&cast.expr, layers, custom),
+        Expr::Binary(binary) if matches!(binary.op, BinOp::BitOr(_)) => Ok(format!(
+            "{} | {}",
+            keycode_to_qmk(&binary.left, layers, custom)?,
+            keycode_to_qmk(&binary.right, layers, custom)?
+        )),
+        Expr::Macro(mac) => {
+            let name = mac
+                .mac
+                .path
+                .segments
+                .last()
+                .map(|segment| segment.ident.to_string())
+                .unwrap_or_default();
+            let args = mac
+                .mac
+                .parse_body_with(Punctuated::<Expr, Token![,]>::parse_terminated)
+                .map_err(|e| format!("`{name}!`: {e}"))?;
+            let args = args
+                .iter()
+                .map(|arg| keycode_to_qmk(arg, layers, custom))
+                .collect::<Result<Vec<_>, _>>()?;
+
+            if name == "key" && args.len() == 1 {
+                return Ok(args[0].clone());
+            }
+            if let ("mt", [mods, key]) = (name.as_str(), args.as_slice())
+                && let Some(mods) = mods
+                    .strip_prefix("MOD_")
+                    .filter(|mods| MOD_TAPS.contains(mods))
+            {
+                return Ok(format!("{mods}_T({key})"));
+            }
+            let function = name.to_uppercase();
+            if !KEYCODE_MACROS.contains(&function.as_str()) {
+                return Err(format!("`{name}!` has no keymap.json form"));
+            }
+            Ok(format!("{function}({})", args.join(",")))
+        }
+        _ => Err(format!("`{}` has no keymap.json form", quote!(#key))),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn round_trip(keycode: &str) -> String {
+        let key = keycode_from_qmk(keycode).unwrap();
+        keycode_to_qmk(&key, &[], &BTreeMap::new()).unwrap()
+    }
+
+    #[test]
+    fn imports_keycodes() {
+        let key = keycode_from_qmk("LT(2, KC_SPC)").unwrap();
+        assert_eq!(
+            key.to_token_stream().to_string(),
+            quote!(::qmk::lt!(2, ::qmk::keys::KC_SPC)).to_string()
+        );
+
+        let key = keycode_from_qmk("LSFT_T(KC_A)").unwrap();
+        assert_eq!(
+            key.to_token_stream().to_string(),
+            quote!(::qmk::mt!(::qmk::keys::MOD_LSFT, ::qmk::keys::KC_A)).to_string()
+        );
+
+        let key = keycode_from_qmk("ANY(KC_ESC)").unwrap();
+        assert_eq!(key.to_token_stream().to_string(), "KC_ESC");
+
+        assert_eq!(
+            keycode_from_qmk("SH_TOGG(KC_A)").err().as_deref(),
+            Some("`SH_TOGG` has no qmk macro in `SH_TOGG(KC_A)`")
+        );
+    }
+
+    #[test]
+    fn exports_keycodes() {
+        let layers = [String::from("base"), String::from("lower")];
+        let custom = BTreeMap::from([(
+            String::from("CustomKeycode::Reset"),
+            String::from("QK_USER_0"),
+        )]);
+        let export = |key: Expr| keycode_to_qmk(&key, &layers, &custom).unwrap();
+
+        assert_eq!(export(syn::parse_quote!(mo!(lower))), "MO(1)");
+        assert_eq!(export(syn::parse_quote!(lt!(2u8, KC_SPC))), "LT(2,KC_SPC)");
+        assert_eq!(
+            export(syn::parse_quote!(mt!(MOD_LCTL, KC_A))),
+            "LCTL_T(KC_A)"
+        );
+        assert_eq!(
+            export(syn::parse_quote!(mt!(MOD_LCTL | MOD_LSFT, KC_A))),
+            "MT(MOD_LCTL | MOD_LSFT,KC_A)"
+        );
+        assert_eq!(export(syn::parse_quote!(_______)), "KC_TRNS");
+        assert_eq!(export(syn::parse_quote!(CustomKeycode::Reset)), "QK_USER_0");
+        assert_eq!(export(syn::parse_quote!(0x7E00)), "0x7E00");
+
+        let error = keycode_to_qmk(&syn::parse_quote!(td!(TapDance::Esc)), &layers, &custom);
+        assert_eq!(error.unwrap_err(), "`TapDance::Esc` has no QMK name");
+    }
+
+    #[test]
+    fn keycodes_round_trip() {
+        for keycode in [
+            "KC_A",
+            "KC_TRNS",
+            "QK_USER_3",
+            "MO(1)",
+            "LT(3,KC_BSPC)",
+            "LSFT_T(KC_A)",
+            "MT(MOD_LCTL | MOD_LALT,KC_ESC)",
+            "LCTL(LSFT(KC_T))",
+            "OSM(MOD_RSFT)",
+            "0x7E00",
+        ] {
+            assert_eq!(round_trip(keycode), keycode);
+        }
+    }
+}
@@ -1,3 +1,7 @@
+pub mod custom_keycodes;
+pub mod keymap;
+pub mod keymap_json;
+
 use quote::ToTokens;
 use syn::{Token, Type, parse::Parse};
 
@@ -0,0 +1,39 @@
+use proc_macro2::Span;
+use syn::{Expr, Token, bracketed, parse::Parse, punctuated::Punctuated};
+
+/// The body of `combos!`, one combo per comma.
+pub struct Combos {
+    pub combos: Vec<Combo>,
+}
+
+impl Parse for Combos {
+    fn parse(input: syn::parse::ParseStream) -> syn::Result<Self> {
+        let combos = Punctuated::<Combo, Token![,]>::parse_terminated(input)?;
+        Ok(Combos {
+            combos: combos.into_iter().collect(),
+        })
+    }
+}
+
+/// `[KC_J, KC_K] => KC_ESC`
+pub struct Combo {
+    pub keys: Vec<Expr>,
+    /// The brackets around the keys, where too few keys are reported.
+    pub span: Span,
+    pub keycode: Expr,
+}
+
+impl Parse for Combo {
+    fn parse(input: syn::parse::ParseStream) -> syn::Result<Self> {
+        let content;
+        let bracket = bracketed!(content in input);
+        let keys = Punctuated::<Expr, Token![,]>::parse_terminated(&content)?;
+        input.parse::<Token![=>]>()?;
+        let keycode = input.parse::<Expr>()?;
+        Ok(Combo {
+            keys: keys.into_iter().collect(),
+            span: bracket.span.join(),
+            keycode,
+        })
+    }
+}
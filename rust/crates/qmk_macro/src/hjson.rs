@@ -0,0 +1,249 @@
+use serde_json::{Map, Number, Value};
+
+/// A small reader for the subset of hjson used by QMK's `data/` tree: JSON with `//`, `#` and
+/// `/* */` comments, optional commas between members and trailing commas. Keys must be quoted.
+pub fn parse(source: &str) -> Result<Value, String> {
+    let mut parser = Parser {
+        chars: source.chars().collect(),
+        pos: 0,
+    };
+
+    parser.skip_whitespace();
+    // some files are completely empty
+    if parser.is_eof() {
+        return Ok(Value::Object(Map::new()));
+    }
+
+    let value = parser.value()?;
+    parser.skip_whitespace();
+    if !parser.is_eof() {
+        return Err(parser.error("trailing characters after value"));
+    }
+
+    Ok(value)
+}
+
+struct Parser {
+    chars: Vec<char>,
+    pos: usize,
+}
+
+impl Parser {
+    fn is_eof(&self) -> bool {
+        self.pos >= self.chars.len()
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.pos).copied()
+    }
+
+    fn peek_at(&self, offset: usize) -> Option<char> {
+        self.chars.get(self.pos + offset).copied()
+    }
+
+    fn error(&self, message: &str) -> String {
+        let line = self.chars[..self.pos.min(self.chars.len())]
+            .iter()
+            .filter(|c| **c == '\n')
+            .count()
+            + 1;
+        format!("{message} (line {line})")
+    }
+
+    fn expect(&mut self, expected: char) -> Result<(), String> {
+        if self.peek() == Some(expected) {
+            self.pos += 1;
+            Ok(())
+        } else {
+            Err(self.error(&format!("expected '{expected}'")))
+        }
+    }
+
+    fn skip_whitespace(&mut self) {
+        while let Some(c) = self.peek() {
+            if c.is_whitespace() {
+                self.pos += 1;
+            } else if c == '#' || (c == '/' && self.peek_at(1) == Some('/')) {
+                while let Some(c) = self.peek() {
+                    if c == '\n' {
+                        break;
+                    }
+                    self.pos += 1;
+                }
+            } else if c == '/' && self.peek_at(1) == Some('*') {
+                self.pos += 2;
+                while !(self.is_eof() || self.peek() == Some('*') && self.peek_at(1) == Some('/')) {
+                    self.pos += 1;
+                }
+                self.pos += 2;
+            } else {
+                break;
+            }
+        }
+    }
+
+    /// Skips whitespace and a single optional separating comma.
+    fn skip_separator(&mut self) {
+        self.skip_whitespace();
+        if self.peek() == Some(',') {
+            self.pos += 1;
+            self.skip_whitespace();
+        }
+    }
+
+    fn value(&mut self) -> Result<Value, String> {
+        self.skip_whitespace();
+        match self.peek() {
+            Some('{') => self.object(),
+            Some('[') => self.array(),
+            Some('"') => self.string().map(Value::String),
+            Some(c) if c == '-' || c.is_ascii_digit() => self.number(),
+            Some(_) => self.literal(),
+            None => Err(self.error("unexpected end of input")),
+        }
+    }
+
+    fn object(&mut self) -> Result<Value, String> {
+        self.expect('{')?;
+        let mut map = Map::new();
+        self.skip_whitespace();
+        while self.peek() != Some('}') {
+            if self.is_eof() {
+                return Err(self.error("unterminated object"));
+            }
+            let key = self.string()?;
+            self.skip_whitespace();
+            self.expect(':')?;
+            let value = self.value()?;
+            map.insert(key, value);
+            self.skip_separator();
+        }
+        self.expect('}')?;
+        Ok(Value::Object(map))
+    }
+
+    fn array(&mut self) -> Result<Value, String> {
+        self.expect('[')?;
+        let mut values = Vec::new();
+        self.skip_whitespace();
+        while self.peek() != Some(']') {
+            if self.is_eof() {
+                return Err(self.error("unterminated array"));
+            }
+            values.push(self.value()?);
+            self.skip_separator();
+        }
+        self.expect(']')?;
+        Ok(Value::Array(values))
+    }
+
+    fn string(&mut self) -> Result<String, String> {
+        self.expect('"')?;
+        let mut string = String::new();
+        loop {
+            let Some(c) = self.peek() else {
+                return Err(self.error("unterminated string"));
+            };
+            self.pos += 1;
+            match c {
+                '"' => break,
+                '\\' => {
+                    let Some(escaped) = self.peek() else {
+                        return Err(self.error("unterminated escape"));
+                    };
+                    self.pos += 1;
+                    match escaped {
+                        'n' => string.push('\n'),
+                        't' => string.push('\t'),
+                        'r' => string.push('\r'),
+                        'u' => {
+                            let hex = self
+                                .chars
+                                .get(self.pos..self.pos + 4)
+                                .ok_or_else(|| self.error("truncated unicode escape"))?
+                                .iter()
+                                .collect::<String>();
+                            self.pos += 4;
+                            let code = u32::from_str_radix(&hex, 16)
+                                .map_err(|_| self.error("invalid unicode escape"))?;
+                            string.push(char::from_u32(code).unwrap_or('\u{FFFD}'));
+                        }
+                        other => string.push(other),
+                    }
+                }
+                c => string.push(c),
+            }
+        }
+        Ok(string)
+    }
+
+    fn number(&mut self) -> Result<Value, String> {
+        let start = self.pos;
+        while let Some(c) = self.peek() {
+            if c.is_ascii_alphanumeric() || matches!(c, '-' | '+' | '.') {
+                self.pos += 1;
+            } else {
+                break;
+            }
+        }
+        let text = self.chars[start..self.pos].iter().collect::<String>();
+        if let Ok(int) = text.parse::<i64>() {
+            return Ok(Value::Number(int.into()));
+        }
+        text.parse::<f64>()
+            .ok()
+            .and_then(Number::from_f64)
+            .map(Value::Number)
+            .ok_or_else(|| self.error(&format!("invalid number '{text}'")))
+    }
+
+    fn literal(&mut self) -> Result<Value, String> {
+        let start = self.pos;
+        while let Some(c) = self.peek() {
+            if c.is_ascii_alphabetic() {
+                self.pos += 1;
+            } else {
+                break;
+            }
+        }
+        match self.chars[start..self.pos]
+            .iter()
+            .collect::<String>()
+            .as_str()
+        {
+            "true" => Ok(Value::Bool(true)),
+            "false" => Ok(Value::Bool(false)),
+            "null" => Ok(Value::Null),
+            _ => Err(self.error("unexpected token")),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn comments_and_missing_commas() {
+        let value = parse(
+            r#"{
+                // a comment
+                "ranges": {
+                    "0x0000/0x00FF": { "define": "QK_BASIC" }, # another
+                }
+                "keycodes": {
+                    "0x0004": { "key": "KC_A", "aliases": ["A", "B",] }
+                }
+            }"#,
+        )
+        .unwrap();
+
+        assert_eq!(value["ranges"]["0x0000/0x00FF"]["define"], "QK_BASIC");
+        assert_eq!(value["keycodes"]["0x0004"]["aliases"][1], "B");
+    }
+
+    #[test]
+    fn empty_file() {
+        assert_eq!(parse("\n").unwrap(), Value::Object(Map::new()));
+    }
+}
@@ -0,0 +1,51 @@
+use syn::{BinOp, Expr, Token, parse::Parse, punctuated::Punctuated};
+
+/// The body of `key_overrides!`, one override per comma.
+pub struct KeyOverrides {
+    pub overrides: Vec<KeyOverride>,
+}
+
+impl Parse for KeyOverrides {
+    fn parse(input: syn::parse::ParseStream) -> syn::Result<Self> {
+        let overrides = Punctuated::<KeyOverride, Token![,]>::parse_terminated(input)?;
+        Ok(KeyOverrides {
+            overrides: overrides.into_iter().collect(),
+        })
+    }
+}
+
+// only lives while the macro expands, so the size doesn't matter
+#[allow(clippy::large_enum_variant)]
+pub enum KeyOverride {
+    /// `Mods::SHIFT + KC_BSPC => KC_DEL`, QMK's `ko_make_basic`.
+    Basic {
+        mods: Expr,
+        trigger: Expr,
+        replacement: Expr,
+    },
+    /// Any other expression, expected to be a `qmk::key_override::KeyOverride`.
+    Full(Expr),
+}
+
+impl Parse for KeyOverride {
+    fn parse(input: syn::parse::ParseStream) -> syn::Result<Self> {
+        let expr = input.parse::<Expr>()?;
+        if !input.peek(Token![=>]) {
+            return Ok(KeyOverride::Full(expr));
+        }
+        input.parse::<Token![=>]>()?;
+        let replacement = input.parse::<Expr>()?;
+
+        match expr {
+            Expr::Binary(binary) if matches!(binary.op, BinOp::Add(_)) => Ok(KeyOverride::Basic {
+                mods: *binary.left,
+                trigger: *binary.right,
+                replacement,
+            }),
+            expr => Err(syn::Error::new_spanned(
+                expr,
+                "Expected `mods + trigger => replacement`",
+            )),
+        }
+    }
+}
@@ -0,0 +1,252 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use serde_json::{Map, Value};
+
+use crate::hjson;
+
+const KEYCODES_PATH: &str = "../data/constants/keycodes";
+
+#[derive(Debug, Clone)]
+pub struct KeycodeSpec {
+    pub code: u16,
+    pub key: String,
+    pub label: Option<String>,
+    pub aliases: Vec<String>,
+}
+
+/// Loads the newest keycode spec from QMK's `data/constants/keycodes`, merging the versioned
+/// fragments the same way `qmk.keycodes.load_spec` does.
+pub fn load_spec() -> Result<Vec<KeycodeSpec>, String> {
+    let path = Path::new(KEYCODES_PATH);
+    let entries = fs::read_dir(path)
+        .map_err(|e| format!("Failed to read keycode spec directory {KEYCODES_PATH}: {e}"))?
+        .filter_map(|entry| entry.ok().map(|e| e.path()))
+        .filter(|path| path.extension().is_some_and(|ext| ext == "hjson"))
+        .collect::<Vec<_>>();
+
+    let mut versions = entries
+        .iter()
+        .filter_map(|path| path.file_stem()?.to_str()?.strip_prefix("keycodes_"))
+        .filter(|stem| !stem.contains('_'))
+        .map(String::from)
+        .collect::<Vec<_>>();
+    versions.sort_by_key(|v| parse_version(v));
+
+    // collate files by fragment "type", oldest version first
+    let mut fragments: BTreeMap<String, Vec<PathBuf>> = BTreeMap::new();
+    for version in &versions {
+        let base = format!("keycodes_{version}");
+        fragments
+            .entry(String::from("_"))
+            .or_default()
+            .push(path.join(format!("{base}.hjson")));
+
+        let mut versioned = entries
+            .iter()
+            .filter_map(|path| {
+                let stem = path.file_stem()?.to_str()?;
+                let fragment = stem.strip_prefix(&base)?.strip_prefix('_')?;
+                Some((fragment.to_string(), path.clone()))
+            })
+            .collect::<Vec<_>>();
+        versioned.sort();
+
+        for (fragment, path) in versioned {
+            fragments.entry(fragment).or_default().push(path);
+        }
+    }
+
+    // allow override within types of fragments - but not globally
+    let mut spec = Map::new();
+    for files in fragments.values() {
+        let mut specs = Vec::new();
+        for file in files {
+            let source = fs::read_to_string(file)
+                .map_err(|e| format!("Failed to read {}: {e}", file.display()))?;
+            let value = hjson::parse(&source)
+                .map_err(|e| format!("Failed to parse {}: {e}", file.display()))?;
+            specs.push(value);
+        }
+
+        deep_update(&mut spec, merge_ordered(specs));
+    }
+
+    let Some(Value::Object(keycodes)) = spec.get("keycodes") else {
+        return Err(String::from("Keycode spec contains no keycodes"));
+    };
+
+    let mut table = keycodes
+        .iter()
+        .filter_map(|(code, entry)| {
+            let code = u16::from_str_radix(code.trim_start_matches("0x"), 16).ok()?;
+            let key = entry.get("key")?.as_str()?.to_string();
+            let label = entry
+                .get("label")
+                .and_then(Value::as_str)
+                .filter(|label| !label.is_empty())
+                .map(String::from);
+            let aliases = entry
+                .get("aliases")
+                .and_then(Value::as_array)
+                .map(|aliases| {
+                    aliases
+                        .iter()
+                        .filter_map(Value::as_str)
+                        .filter(|alias| *alias != "!reset!")
+                        .map(String::from)
+                        .collect()
+                })
+                .unwrap_or_default();
+
+            Some(KeycodeSpec {
+                code,
+                key,
+                label,
+                aliases,
+            })
+        })
+        .collect::<Vec<_>>();
+    table.sort_by_key(|spec| spec.code);
+
+    Ok(table)
+}
+
+/// The names from the newest `extras/keycodes_<language>_<version>.hjson`, like `KC_EXLM` from
+/// the `us` table. These are aliases for shifted keys, with no keycode of their own.
+pub fn load_extra_names(language: &str) -> Result<Vec<String>, String> {
+    Ok(load_extras(language)?
+        .into_iter()
+        .flat_map(|(names, _)| names)
+        .collect())
+}
+
+/// The labels of the names from [`load_extra_names`], like `!` for `KC_EXLM` and `KC_EXCLAIM`.
+pub fn load_extra_labels(language: &str) -> Result<Vec<(String, String)>, String> {
+    Ok(load_extras(language)?
+        .into_iter()
+        .filter_map(|(names, label)| Some((names, label?)))
+        .flat_map(|(names, label)| names.into_iter().map(move |name| (name, label.clone())))
+        .collect())
+}
+
+/// An entry of an extras table, as its key and aliases, and its label if it has one.
+type Extra = (Vec<String>, Option<String>);
+
+/// Each entry of the newest extras table.
+fn load_extras(language: &str) -> Result<Vec<Extra>, String> {
+    let path = Path::new(KEYCODES_PATH).join("extras");
+    let prefix = format!("keycodes_{language}_");
+    let newest = fs::read_dir(&path)
+        .map_err(|e| format!("Failed to read keycode extras in {}: {e}", path.display()))?
+        .filter_map(|entry| entry.ok().map(|e| e.path()))
+        .filter_map(|path| {
+            let version = path.file_stem()?.to_str()?.strip_prefix(&prefix)?;
+            // `us_extended` and friends are other languages
+            version
+                .starts_with(|c: char| c.is_ascii_digit())
+                .then(|| (parse_version(version), path.clone()))
+        })
+        .max()
+        .map(|(_, path)| path)
+        .ok_or_else(|| format!("No keycode extras for {language}"))?;
+
+    let source = fs::read_to_string(&newest)
+        .map_err(|e| format!("Failed to read {}: {e}", newest.display()))?;
+    let spec =
+        hjson::parse(&source).map_err(|e| format!("Failed to parse {}: {e}", newest.display()))?;
+    let Some(Value::Object(aliases)) = spec.get("aliases") else {
+        return Ok(Vec::new());
+    };
+
+    Ok(aliases
+        .values()
+        .map(|entry| {
+            let key = entry.get("key").and_then(Value::as_str);
+            let aliases = entry
+                .get("aliases")
+                .and_then(Value::as_array)
+                .into_iter()
+                .flatten()
+                .filter_map(Value::as_str);
+            let names = key.into_iter().chain(aliases).map(String::from).collect();
+            let label = entry
+                .get("label")
+                .and_then(Value::as_str)
+                .filter(|label| !label.is_empty())
+                .map(String::from);
+            (names, label)
+        })
+        .collect())
+}
+
+fn parse_version(version: &str) -> Vec<u32> {
+    version.split('.').filter_map(|v| v.parse().ok()).collect()
+}
+
+/// Later values override earlier ones, `"!delete!"` removes an entry, arrays are appended unless
+/// they start with `"!reset!"`, and objects are merged unless they contain a `"!reset!"` key.
+fn merge_ordered(values: Vec<Value>) -> Map<String, Value> {
+    let mut result = Map::new();
+    for value in values {
+        let Value::Object(map) = value else {
+            continue;
+        };
+        for (key, value) in map {
+            add_entry(&mut result, key, value);
+        }
+    }
+    result
+}
+
+fn add_entry(target: &mut Map<String, Value>, key: String, value: Value) {
+    match (target.get_mut(&key), value) {
+        (Some(existing @ Value::Object(_)), Value::Object(map)) => {
+            if map.contains_key("!reset!") {
+                *existing = Value::Object(map);
+            } else {
+                let merged = merge_ordered(vec![existing.take(), Value::Object(map)]);
+                *existing = Value::Object(merged);
+            }
+            if let Value::Object(existing) = existing {
+                existing.remove("!reset!");
+            }
+        }
+        (Some(Value::Array(existing)), Value::Array(values)) => {
+            if values.first().is_some_and(|v| v == "!reset!") {
+                *existing = values.into_iter().skip(1).collect();
+            } else {
+                existing.extend(values);
+            }
+        }
+        (_, Value::String(s)) if s == "!delete!" => {
+            target.remove(&key);
+        }
+        (_, value) => {
+            target.insert(key, value);
+        }
+    }
+}
+
+/// Merges `source` into `target`, recursing into objects and replacing everything else.
+pub fn deep_update(target: &mut Map<String, Value>, source: Map<String, Value>) {
+    for (key, value) in source {
+        match value {
+            Value::Object(map) => {
+                let entry = target
+                    .entry(key)
+                    .or_insert_with(|| Value::Object(Map::new()));
+                if !entry.is_object() {
+                    *entry = Value::Object(Map::new());
+                }
+                if let Value::Object(entry) = entry {
+                    deep_update(entry, map);
+                }
+            }
+            value => {
+                target.insert(key, value);
+            }
+        }
+    }
+}
@@ -1,64 +1,373 @@
-use std::collections::HashMap;
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
 
 use serde::Deserialize;
-use syn::{Expr, Ident, Token, braced, parse::Parse};
-
-#[derive(Debug)]
-pub struct Keymap {
-    pub keeb: String,
-    pub layers: Vec<Layer>,
-}
-
-impl Parse for Keymap {
-    fn parse(input: syn::parse::ParseStream) -> syn::Result<Self> {
-        // comma seperated arguments
-        let mut layers = Vec::new();
-        let lit = input.parse::<syn::LitStr>()?;
-        let keeb = lit.value();
-        if input.peek(Token![,]) {
-            input.parse::<Token![,]>()?;
-            while !input.is_empty() {
-                let content;
-                braced!(content in input);
-                while !content.is_empty() {
-                    let layer: Layer = content.parse()?;
-                    layers.push(layer);
-                    if content.peek(Token![,]) {
-                        content.parse::<Token![,]>()?;
-                    }
-                }
-                if !input.is_empty() {
-                    input.parse::<Token![,]>()?;
-                }
-            }
+use serde::de::IgnoredAny;
+use serde_json::{Map, Value};
+use syn::{Expr, LitInt, Token, punctuated::Punctuated};
+
+use crate::keycodes::deep_update;
+
+pub use qmk_callback_parsing::keymap::Keymap;
+
+const KEYBOARDS_PATH: &str = "../keyboards";
+const LAYOUTS_PATH: &str = "../layouts";
+
+/// Checks that a bare `KC_` or `QK_` name is one QMK knows, suggesting the closest one if not.
+pub fn check_keycode(key: &Expr, names: &[&str]) -> syn::Result<()> {
+    let Expr::Path(path) = key else {
+        return Ok(());
+    };
+    let Some(ident) = path.path.get_ident() else {
+        return Ok(());
+    };
+    let name = ident.to_string();
+    let upper = name.to_uppercase();
+    if !(upper.starts_with("KC_") || upper.starts_with("QK_")) || names.contains(&name.as_str()) {
+        return Ok(());
+    }
+
+    let message = match closest(&upper, names) {
+        Some(suggestion) => format!("Unknown keycode `{name}`, did you mean `{suggestion}`?"),
+        None => format!("Unknown keycode `{name}`"),
+    };
+    Err(syn::Error::new(ident.span(), message))
+}
+
+/// The macros from `qmk::keys` whose first argument is a layer.
+pub const LAYER_MACROS: [&str; 9] = ["mo", "tg", "to", "df", "pdf", "osl", "tt", "lt", "lm"];
+
+/// Replaces a layer name given to `mo!`, `to!` and the other layer macros with the layer's index,
+/// so `mo!(lower)` works inside `keymap!`. Other arguments are left for the macro to handle.
+pub fn resolve_layer_name(key: &mut Expr, names: &[&str]) -> syn::Result<()> {
+    let Expr::Macro(mac) = key else {
+        return Ok(());
+    };
+    let Some(macro_name) = mac.mac.path.segments.last().map(|segment| &segment.ident) else {
+        return Ok(());
+    };
+    if !LAYER_MACROS
+        .iter()
+        .any(|layer_macro| macro_name == layer_macro)
+    {
+        return Ok(());
+    }
+
+    let Ok(mut args) = mac
+        .mac
+        .parse_body_with(Punctuated::<Expr, Token![,]>::parse_terminated)
+    else {
+        return Ok(());
+    };
+    let Some(Expr::Path(path)) = args.first() else {
+        return Ok(());
+    };
+    let Some(ident) = path.path.get_ident() else {
+        return Ok(());
+    };
+    // constants are uppercase, so only lowercase names can be layers
+    let name = ident.to_string();
+    if !name.starts_with(|c: char| c.is_ascii_lowercase()) {
+        return Ok(());
+    }
+
+    let Some(index) = names.iter().position(|layer| *layer == name) else {
+        let message = match closest(&name, names) {
+            Some(suggestion) => format!("Unknown layer `{name}`, did you mean `{suggestion}`?"),
+            None => format!(
+                "Unknown layer `{name}`, the layers are {}",
+                names.join(", ")
+            ),
+        };
+        return Err(syn::Error::new(ident.span(), message));
+    };
+    let index = LitInt::new(&format!("{index}u8"), ident.span());
+    args[0] = syn::parse_quote!(#index);
+    mac.mac.tokens = quote::quote!(#args);
+    Ok(())
+}
+
+/// `lower_thumb` as `LowerThumb`, for the `Layer` enum's variants.
+pub fn to_camel_case(name: &str) -> String {
+    name.split('_')
+        .filter(|word| !word.is_empty())
+        .map(|word| {
+            let mut chars = word.chars();
+            let first = chars.next().unwrap_or_default().to_ascii_uppercase();
+            std::iter::once(first).chain(chars).collect::<String>()
+        })
+        .collect()
+}
+
+/// The name with the smallest edit distance to `name`, if any is close enough to be a typo.
+fn closest<'a>(name: &str, names: &[&'a str]) -> Option<&'a str> {
+    names
+        .iter()
+        .map(|candidate| (edit_distance(name, candidate), *candidate))
+        .filter(|(distance, _)| *distance <= (name.len() / 3).max(2))
+        .min()
+        .map(|(_, candidate)| candidate)
+}
+
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b = b.chars().collect::<Vec<_>>();
+    let mut previous = (0..=b.len()).collect::<Vec<_>>();
+    for (i, a) in a.chars().enumerate() {
+        let mut current = vec![i + 1; b.len() + 1];
+        for (j, b) in b.iter().enumerate() {
+            let substitution = previous[j] + usize::from(a != *b);
+            current[j + 1] = substitution.min(previous[j + 1] + 1).min(current[j] + 1);
         }
+        previous = current;
+    }
+    previous[b.len()]
+}
+
+/// The parts of a keyboard's `keyboard.json` / `info.json` that `keymap!` needs, merged from the
+/// keyboard's folder and each parent the same way QMK does.
+#[derive(Deserialize, Debug, Default)]
+pub struct KeyboardDefinition {
+    #[serde(default)]
+    pub layouts: BTreeMap<String, KeebDefLayout>,
+    #[serde(default)]
+    pub layout_aliases: BTreeMap<String, String>,
+    #[serde(default)]
+    pub community_layouts: Vec<String>,
+    pub matrix_size: Option<MatrixSize>,
+    pub matrix_pins: Option<MatrixPins>,
+    pub encoder: Option<Encoder>,
+    #[serde(default)]
+    pub split: Split,
+}
+
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MatrixSize {
+    pub rows: usize,
+    pub cols: usize,
+}
 
-        Ok(Keymap { keeb, layers })
+#[derive(Deserialize, Debug, Default)]
+pub struct MatrixPins {
+    pub cols: Option<Vec<Value>>,
+    pub rows: Option<Vec<Value>>,
+    pub direct: Option<Vec<Vec<Value>>>,
+}
+
+impl MatrixPins {
+    fn size(&self) -> Option<MatrixSize> {
+        if let Some(direct) = &self.direct {
+            return Some(MatrixSize {
+                rows: direct.len(),
+                cols: direct.iter().map(Vec::len).max().unwrap_or(0),
+            });
+        }
+        Some(MatrixSize {
+            rows: self.rows.as_ref()?.len(),
+            cols: self.cols.as_ref()?.len(),
+        })
     }
 }
 
-#[derive(Debug)]
-pub struct Layer {
-    pub keys: Vec<Expr>,
+#[derive(Deserialize, Debug, Default)]
+pub struct Split {
+    #[serde(default)]
+    pub enabled: bool,
+    pub matrix_pins: Option<SplitMatrixPins>,
+    pub encoder: Option<SplitEncoder>,
+}
+
+#[derive(Deserialize, Debug, Default)]
+pub struct SplitMatrixPins {
+    pub right: Option<MatrixPins>,
 }
 
-impl Parse for Layer {
-    fn parse(input: syn::parse::ParseStream) -> syn::Result<Self> {
-        let mut keys = Vec::new();
-        while !input.is_empty() {
-            let key: Expr = input.parse()?;
-            keys.push(key);
-            if input.peek(Token![,]) {
-                input.parse::<Token![,]>()?;
+#[derive(Deserialize, Debug, Default)]
+pub struct Encoder {
+    #[serde(default)]
+    pub rotary: Vec<IgnoredAny>,
+}
+
+#[derive(Deserialize, Debug, Default)]
+pub struct SplitEncoder {
+    pub right: Option<Encoder>,
+}
+
+impl KeyboardDefinition {
+    pub fn load(keeb: &str) -> Result<Self, String> {
+        let mut dirs = Vec::new();
+        let mut dir = PathBuf::from(KEYBOARDS_PATH).join(keeb);
+        while dir != Path::new(KEYBOARDS_PATH) {
+            dirs.push(dir.clone());
+            if !dir.pop() {
+                break;
+            }
+        }
+
+        // parents first, so the keyboard's own files win
+        let mut merged = Map::new();
+        for dir in dirs.iter().rev() {
+            for file in ["info.json", "keyboard.json"] {
+                let path = dir.join(file);
+                let Ok(source) = fs::read_to_string(&path) else {
+                    continue;
+                };
+                let Value::Object(part) = serde_json::from_str(&source)
+                    .map_err(|e| format!("Failed to parse {}: {e}", path.display()))?
+                else {
+                    return Err(format!("{} isn't a JSON object", path.display()));
+                };
+                deep_update(&mut merged, part);
             }
         }
-        Ok(Layer { keys })
+
+        if merged.is_empty() {
+            return Err(format!("Failed to read keyboard definition for {keeb}"));
+        }
+        serde_json::from_value(Value::Object(merged))
+            .map_err(|e| format!("Failed to parse keyboard definition for {keeb}: {e}"))
+    }
+
+    /// How many encoders QMK's `NUM_ENCODERS` counts: the ones in `encoder.rotary`, plus for a
+    /// split keyboard the right half's, which are the same as the left's unless
+    /// `split.encoder.right` says otherwise.
+    pub fn num_encoders(&self) -> usize {
+        let left = self
+            .encoder
+            .as_ref()
+            .map_or(0, |encoder| encoder.rotary.len());
+        if !self.split.enabled {
+            return left;
+        }
+        let right = self
+            .split
+            .encoder
+            .as_ref()
+            .and_then(|encoder| encoder.right.as_ref())
+            .map_or(left, |encoder| encoder.rotary.len());
+        left + right
+    }
+
+    /// The size of the whole matrix, worked out the way QMK's `info.py` does: `matrix_size` if
+    /// it's given, otherwise from the pins, with twice the rows for a split keyboard. The right
+    /// half can have its own pins in `split.matrix_pins.right`.
+    pub fn matrix_size(&self) -> Result<MatrixSize, String> {
+        if let Some(size) = self.matrix_size {
+            return Ok(size);
+        }
+
+        let left = self
+            .matrix_pins
+            .as_ref()
+            .and_then(MatrixPins::size)
+            .ok_or("the keyboard definition has neither matrix_size nor matrix_pins")?;
+        if !self.split.enabled {
+            return Ok(left);
+        }
+
+        let right = self
+            .split
+            .matrix_pins
+            .as_ref()
+            .and_then(|pins| pins.right.as_ref())
+            .and_then(MatrixPins::size)
+            .unwrap_or(left);
+        Ok(MatrixSize {
+            rows: left.rows.max(right.rows) * 2,
+            cols: left.cols.max(right.cols),
+        })
+    }
+
+    /// Finds a layout by name, following `layout_aliases`. Community layouts can also be named
+    /// without the `LAYOUT_` prefix, e.g. `split_3x6_3`. Without a name the keyboard's `LAYOUT`
+    /// is used, or its only layout if it has just the one.
+    pub fn layout(&self, name: Option<&str>) -> Result<(String, &KeebDefLayout), String> {
+        let name = match name {
+            Some(name) => name.to_string(),
+            None if self.layouts.len() == 1 => self.layouts.keys().next().cloned().unwrap(),
+            None => String::from("LAYOUT"),
+        };
+
+        let mut resolved = name.clone();
+        // aliases can point at other aliases, but not forever
+        for _ in 0..8 {
+            match self.layout_aliases.get(&resolved) {
+                Some(target) => resolved = target.clone(),
+                None => break,
+            }
+        }
+
+        let community = resolved
+            .strip_prefix("LAYOUT_")
+            .unwrap_or(&resolved)
+            .to_string();
+        if !self.layouts.contains_key(&resolved) && self.community_layouts.contains(&community) {
+            resolved = format!("LAYOUT_{community}");
+        }
+
+        let Some(layout) = self.layouts.get(&resolved) else {
+            return Err(format!(
+                "No layout named {name}, available layouts are {}",
+                self.layout_names().join(", ")
+            ));
+        };
+
+        if self.community_layouts.contains(&community) {
+            check_community_layout(&community, layout)?;
+        }
+        Ok((resolved, layout))
+    }
+
+    /// Every name `layout` accepts, for error messages.
+    pub fn layout_names(&self) -> Vec<String> {
+        let mut names = self.layouts.keys().cloned().collect::<Vec<_>>();
+        names.extend(
+            self.layout_aliases
+                .iter()
+                .map(|(alias, target)| format!("{alias} (alias of {target})")),
+        );
+        names.extend(self.community_layouts.iter().cloned());
+        names
     }
 }
 
-#[derive(Deserialize, Debug)]
-pub struct KeyboardDefinition {
-    pub layouts: HashMap<String, KeebDefLayout>,
+#[derive(Deserialize)]
+struct CommunityLayoutDefinition {
+    layouts: BTreeMap<String, CommunityLayout>,
+}
+
+#[derive(Deserialize)]
+struct CommunityLayout {
+    layout: Vec<IgnoredAny>,
+}
+
+/// Checks the keyboard's version of a community layout against the one in `layouts/`, so a
+/// keymap written for the community layout fits.
+fn check_community_layout(community: &str, layout: &KeebDefLayout) -> Result<(), String> {
+    for folder in ["default", "community"] {
+        let path = Path::new(LAYOUTS_PATH)
+            .join(folder)
+            .join(community)
+            .join("info.json");
+        let Ok(source) = fs::read_to_string(&path) else {
+            continue;
+        };
+        let definition: CommunityLayoutDefinition = serde_json::from_str(&source)
+            .map_err(|e| format!("Failed to parse {}: {e}", path.display()))?;
+        let name = format!("LAYOUT_{community}");
+        let Some(expected) = definition.layouts.get(&name) else {
+            continue;
+        };
+        if expected.layout.len() != layout.layout.len() {
+            return Err(format!(
+                "{name} has {} keys but the community layout in {} has {}",
+                layout.layout.len(),
+                path.display(),
+                expected.layout.len()
+            ));
+        }
+    }
+    Ok(())
 }
 
 #[derive(Deserialize, Debug)]
@@ -66,7 +375,86 @@ pub struct KeebDefLayout {
     pub layout: Vec<MatrixMapping>,
 }
 
+/// A key of a layout: where it is in the matrix, and where it is on the keyboard in key units.
 #[derive(Deserialize, Debug)]
 pub struct MatrixMapping {
     pub matrix: [u8; 2],
+    pub x: f32,
+    pub y: f32,
+    #[serde(default = "one_unit")]
+    pub w: f32,
+    #[serde(default = "one_unit")]
+    pub h: f32,
+    /// Rotation in degrees, clockwise around `rx` and `ry`.
+    #[serde(default)]
+    pub r: f32,
+    #[serde(default)]
+    pub rx: f32,
+    #[serde(default)]
+    pub ry: f32,
+}
+
+fn one_unit() -> f32 {
+    1.0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use quote::{ToTokens, quote};
+
+    #[test]
+    fn suggests_close_keycodes() {
+        let names = [
+            "KC_ENTER",
+            "KC_ENT",
+            "KC_ESCAPE",
+            "KC_ESC",
+            "KC_LSFT",
+            "KC_RSFT",
+        ];
+        assert_eq!(closest("KC_ENTERR", &names), Some("KC_ENTER"));
+        assert_eq!(closest("KC_LSHFT", &names), Some("KC_LSFT"));
+        assert_eq!(closest("KC_BACKSPACE", &names), None);
+        assert_eq!(edit_distance("KC_ESC", "KC_ESCAPE"), 3);
+    }
+
+    #[test]
+    fn resolves_layer_names() {
+        let names = ["base", "lower", "game"];
+        let mut key: Expr = syn::parse_quote!(mo!(lower));
+        resolve_layer_name(&mut key, &names).unwrap();
+        assert_eq!(
+            key.to_token_stream().to_string(),
+            quote!(mo!(1u8)).to_string()
+        );
+
+        let mut key: Expr = syn::parse_quote!(lt!(game, KC_SPC));
+        resolve_layer_name(&mut key, &names).unwrap();
+        assert_eq!(
+            key.to_token_stream().to_string(),
+            quote!(lt!(2u8, KC_SPC)).to_string()
+        );
+
+        let mut key: Expr = syn::parse_quote!(to!(LAYER_GAME));
+        resolve_layer_name(&mut key, &names).unwrap();
+        assert_eq!(
+            key.to_token_stream().to_string(),
+            quote!(to!(LAYER_GAME)).to_string()
+        );
+
+        let mut key: Expr = syn::parse_quote!(tg!(gmae));
+        let error = resolve_layer_name(&mut key, &names).unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "Unknown layer `gmae`, did you mean `game`?"
+        );
+    }
+
+    #[test]
+    fn camel_cases_layer_names() {
+        assert_eq!(to_camel_case("base"), "Base");
+        assert_eq!(to_camel_case("lower_thumb"), "LowerThumb");
+        assert_eq!(to_camel_case("fn2"), "Fn2");
+    }
 }
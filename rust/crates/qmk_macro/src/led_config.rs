@@ -0,0 +1,45 @@
+use std::fs;
+use std::path::PathBuf;
+
+use serde::Deserialize;
+
+#[derive(Deserialize, Debug)]
+pub struct RgbMatrixDefinition {
+    pub rgb_matrix: RgbMatrixLayout,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct RgbMatrixLayout {
+    pub layout: Vec<LedDefinition>,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct LedDefinition {
+    pub matrix: Option<[u8; 2]>,
+    pub x: u8,
+    pub y: u8,
+    #[serde(default)]
+    pub flags: u8,
+}
+
+/// Finds `rgb_matrix.layout` for a keyboard, looking at the keyboard's own `keyboard.json` /
+/// `info.json` first and then at each parent folder, the same way QMK merges them.
+pub fn load_led_layout(keeb: &str) -> Result<Vec<LedDefinition>, String> {
+    let mut dir = PathBuf::from("../keyboards").join(keeb);
+    loop {
+        for file in ["keyboard.json", "info.json"] {
+            let Ok(source) = fs::read_to_string(dir.join(file)) else {
+                continue;
+            };
+            if let Ok(definition) = serde_json::from_str::<RgbMatrixDefinition>(&source) {
+                return Ok(definition.rgb_matrix.layout);
+            }
+        }
+
+        if !dir.pop() || dir.ends_with("keyboards") {
+            return Err(format!(
+                "Failed to find an rgb_matrix layout in the keyboard definition for {keeb}"
+            ));
+        }
+    }
+}
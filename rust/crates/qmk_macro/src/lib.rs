@@ -1,19 +1,29 @@
+mod combos;
+mod hjson;
+mod key_overrides;
+mod keycodes;
 mod keymap;
+mod led_config;
+mod lint;
+mod song;
+mod svg;
+mod tap_dance;
 
-use std::collections::HashSet;
-use std::fs;
+use std::collections::HashMap;
+use std::path::PathBuf;
 
 use keymap::KeyboardDefinition;
 use keymap::Keymap;
 use proc_macro2::Span;
 use qmk_callback_parsing::QmkCallback;
 use qmk_callback_parsing::Signature;
+use qmk_callback_parsing::custom_keycodes;
 use quote::ToTokens;
 use quote::quote;
+use quote::quote_spanned;
 use syn::AttrStyle;
 use syn::Expr;
-use syn::ExprLit;
-use syn::LitInt;
+use syn::spanned::Spanned;
 use syn::{
     Attribute, Ident, LitStr, MacroDelimiter, Meta, MetaList, Path, Token, Visibility,
     parse_macro_input, punctuated::Punctuated, token::Paren,
@@ -159,86 +169,352 @@ pub fn qmk_callback(
 ///     }
 /// }
 /// ```
+/// Layers can be named, in which case every one must be. This generates a `Layer` enum with a
+/// variant per layer (`game` becomes `Layer::Game`) alongside `NUM_LAYERS`, and inside the keymap
+/// `mo!`, `to!`, `tg!` and the other layer macros take the names directly:
+/// ```ignore
+/// use qmk_macro::keymap;
+///
+/// keymap! {
+///     "sofle/rev1",
+///     base: {
+///         KC_NO, mo!(lower), to!(game), // ...
+///     },
+///     lower: {
+///         _______, _______, _______, // ...
+///     },
+///     game: {
+///         to!(base), XXXXXXX, XXXXXXX, // ...
+///     },
+/// }
+/// ```
+/// A layer can end with what each encoder sends, as `(counter-clockwise, clockwise)` pairs in the
+/// keyboard's encoder order. This generates QMK's `encoder_map`, which needs
+/// `ENCODER_MAP_ENABLE = yes` in `rules.mk`, and layers without encoders are transparent:
+/// ```ignore
+/// use qmk_macro::keymap;
+///
+/// keymap! {
+///     "sofle/rev1",
+///     base: {
+///         KC_NO, mo!(lower), KC_NO, // ...
+///         encoders: [(KC_VOLD, KC_VOLU), (KC_PGUP, KC_PGDN)],
+///     },
+///     lower: {
+///         _______, _______, _______, // ...
+///     },
+/// }
+/// ```
+/// With the map QMK doesn't call `encoder_update_user`, the turns arrive in
+/// `process_record_user` as presses of the mapped keycodes instead.
+///
+/// Bare names are looked up in `qmk::keys`. A key named by path has to be a keycode constant or a
+/// `CustomKeycode` from `custom_keycodes!`, like `CustomKeycode::Reset`, so an enum like `Layer`
+/// isn't cast to a keycode by mistake.
+///
+/// The layers can come from a QMK `keymap.json` instead, relative to the crate's `Cargo.toml`,
+/// along with its layout. Names for its layers are optional:
+/// ```ignore
+/// use qmk_macro::keymap;
+///
+/// keymap! {
+///     "sofle/rev1",
+///     json: "keymap.json",
+///     layers: [base, lower, game],
+/// }
+/// ```
+/// `qmk_callback::write_keymap_json` goes the other way, writing the keymap out as a
+/// `keymap.json` when the crate builds.
+///
+/// Each layer is drawn as an SVG with the layout's key shapes and QMK's key labels, to
+/// `layer_<n>_<name>.svg` in the crate's `OUT_DIR`, or in a folder relative to its `Cargo.toml`:
+/// ```ignore
+/// use qmk_macro::keymap;
+///
+/// keymap! {
+///     "sofle/rev1",
+///     svg: "docs",
+///     {
+///         KC_NO, KC_NO, KC_NO, // ...
+///     }
+/// }
+/// ```
+///
+/// The layers are checked for keys that can't work: layers nothing switches to from layer 0,
+/// `_______` on layer 0, and layers switched to with `to!` or `tg!` that have no key leading back
+/// out. These are warnings, which `lints: deny` turns into errors and `lints: allow` turns off:
+/// ```ignore
+/// use qmk_macro::keymap;
+///
+/// keymap! {
+///     "sofle/rev1",
+///     lints: deny,
+///     {
+///         KC_NO, KC_NO, KC_NO, // ...
+///     }
+/// }
+/// ```
+///
+/// The keyboard's `LAYOUT` is used unless a layout is named after it, either in full, through
+/// one of the keyboard's `layout_aliases`, or as a community layout:
+/// ```ignore
+/// use qmk_macro::keymap;
+///
+/// keymap! {
+///     "crkbd/rev1", "split_3x6_3",
+///     {
+///         KC_NO, KC_NO, KC_NO, // ...
+///     }
+/// }
+/// ```
 #[proc_macro]
 pub fn keymap(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
-    let keymap = parse_macro_input!(input as Keymap);
-    let keyboard_definition = fs::read_to_string(format!(
-        "../keyboards/{}/keyboard.json",
-        keymap.keeb
-    ))
-    .unwrap_or_else(|_| {
-        fs::read_to_string(format!("../keyboards/{}/info.json", keymap.keeb))
-            .unwrap_or_else(|_| panic!("Failed to read keyboard definition for {}", keymap.keeb))
+    let mut keymap = parse_macro_input!(input as Keymap);
+    let manifest_dir = std::env::var("CARGO_MANIFEST_DIR").unwrap_or_default();
+    let json_path = match keymap.load_json(std::path::Path::new(&manifest_dir)) {
+        Ok(path) => path,
+        Err(e) => return e.to_compile_error().into(),
+    };
+    // rebuild when the JSON changes
+    let json_dependency = json_path.map(|path| {
+        let path = path.display().to_string();
+        quote! { const _: &[u8] = include_bytes!(#path); }
     });
+    let keeb = keymap.keeb.value();
+    let keyboard_definition = match KeyboardDefinition::load(&keeb) {
+        Ok(definition) => definition,
+        Err(e) => {
+            return syn::Error::new(keymap.keeb.span(), e)
+                .to_compile_error()
+                .into();
+        }
+    };
+
+    let requested_layout = keymap.layout.as_ref().map(LitStr::value);
+    let (layout_name, matrix_map) = match keyboard_definition.layout(requested_layout.as_deref()) {
+        Ok((name, layout)) => (name, layout),
+        Err(e) => {
+            let span = keymap.layout.as_ref().unwrap_or(&keymap.keeb).span();
+            return syn::Error::new(span, format!("{e} (for {keeb})"))
+                .to_compile_error()
+                .into();
+        }
+    };
 
-    let keyboard_definition: KeyboardDefinition = serde_json::from_str(&keyboard_definition)
-        .unwrap_or_else(|_| panic!("Failed to parse keyboard definition for {}", keymap.keeb));
+    let matrix_size = match keyboard_definition.matrix_size() {
+        Ok(size) => size,
+        Err(e) => {
+            return syn::Error::new(keymap.keeb.span(), format!("{e} (for {keeb})"))
+                .to_compile_error()
+                .into();
+        }
+    };
+    let (matrix_rows, matrix_cols) = (matrix_size.rows, matrix_size.cols);
 
-    let matrix_map = keyboard_definition
-        .layouts
-        .get("LAYOUT")
-        .unwrap_or_else(|| {
-            panic!(
-                "Failed to find layout LAYOUT in keyboard definition for {}",
-                keymap.keeb
+    let mut positions: HashMap<[u8; 2], usize> = HashMap::new();
+    for (i, mapping) in matrix_map.layout.iter().enumerate() {
+        let [row, col] = mapping.matrix;
+        if row as usize >= matrix_rows || col as usize >= matrix_cols {
+            return syn::Error::new(
+                keymap.keeb.span(),
+                format!(
+                    "Key {i} of the layout is at [{row}, {col}], outside the {matrix_rows}x{matrix_cols} matrix of {keeb}"
+                ),
             )
-        });
+            .to_compile_error()
+            .into();
+        }
+        if let Some(other) = positions.insert(mapping.matrix, i) {
+            return syn::Error::new(
+                keymap.keeb.span(),
+                format!("Keys {other} and {i} of the layout are both at [{row}, {col}] in {keeb}"),
+            )
+            .to_compile_error()
+            .into();
+        }
+    }
 
-    // find the number of unique matrix row values, ie matrix_map.layout[0].matrix[0]
+    let keycode_names = known_keycode_names();
+    let keycode_names = keycode_names.iter().map(String::as_str).collect::<Vec<_>>();
 
-    let matrix_rows = matrix_map
-        .layout
-        .iter()
-        .map(|l| l.matrix[0])
-        .collect::<HashSet<_>>()
-        .len();
+    let mut errors: Option<syn::Error> = None;
+    let mut error = |e: syn::Error| match errors.as_mut() {
+        Some(errors) => errors.combine(e),
+        None => errors = Some(e),
+    };
 
-    let matrix_cols = matrix_map
-        .layout
+    // layers are named all or nothing, so every index has a name when there are any
+    let named = keymap.layers.iter().any(|layer| layer.name.is_some());
+    let mut layer_names: Vec<Ident> = vec![];
+    for (x, layer) in keymap.layers.iter().enumerate() {
+        match &layer.name {
+            Some(name) if layer_names.contains(name) => {
+                error(syn::Error::new(
+                    name.span(),
+                    format!("Layer `{name}` is defined twice"),
+                ));
+            }
+            Some(name) => layer_names.push(name.clone()),
+            None if named => error(syn::Error::new(
+                layer.end,
+                format!("Layer {x} needs a name, like the other layers"),
+            )),
+            None => {}
+        }
+    }
+    let layer_name_strings = layer_names.iter().map(Ident::to_string).collect::<Vec<_>>();
+    let layer_name_strs = layer_name_strings
         .iter()
-        .map(|l| l.matrix[1])
-        .collect::<HashSet<_>>()
-        .len();
+        .map(String::as_str)
+        .collect::<Vec<_>>();
+
+    // the layers are drawn to `svg:` if it's given, otherwise to the crate's OUT_DIR
+    let svg_dir = match &keymap.svg {
+        Some(svg) => Some(PathBuf::from(&manifest_dir).join(svg.value())),
+        None => std::env::var("OUT_DIR").ok().map(PathBuf::from),
+    };
+    let svg_span = keymap
+        .svg
+        .as_ref()
+        .map_or_else(Span::call_site, LitStr::span);
+    let labels = if svg_dir.is_some() {
+        keycode_labels()
+    } else {
+        HashMap::new()
+    };
+    let mut legends = vec![];
+
+    let lint_level = keymap.lints.as_ref().map(Ident::to_string);
+    if let Some(level) = &keymap.lints
+        && !["allow", "warn", "deny"].iter().any(|known| level == known)
+    {
+        error(syn::Error::new(
+            level.span(),
+            format!("Unknown lint level `{level}`, expected `allow`, `warn` or `deny`"),
+        ));
+    }
+    let mut lint_layers = vec![];
 
     let mut layers = vec![];
+    let mut encoder_layers = vec![];
+    let mut has_encoders = false;
+    let num_encoders = keyboard_definition.num_encoders();
 
     let num_layers = keymap.layers.len();
+    let expected = matrix_map.layout.len();
 
     for (x, layer) in keymap.layers.into_iter().enumerate() {
-        let mut key_idents = vec![
-            vec![
-                Expr::Lit(ExprLit {
-                    attrs: vec![],
-                    lit: syn::Lit::Int(LitInt::new("0u16", Span::call_site())),
-                });
-                matrix_cols
-            ];
-            matrix_rows
-        ];
+        let found = layer.keys.len();
+        let lint_layer = lint::LintLayer {
+            label: match &layer.name {
+                Some(name) => format!("layer {x} (`{name}`)"),
+                None => format!("layer {x}"),
+            },
+            span: layer.name.as_ref().map_or(layer.end, Ident::span),
+            keys: vec![],
+        };
+        lint_layers.push(lint_layer);
+        if found < expected {
+            error(syn::Error::new(
+                layer.end,
+                match expected - found {
+                    1 => format!(
+                        "Layer {x} has {found} keys but {layout_name} has {expected}, missing key {found}"
+                    ),
+                    _ => format!(
+                        "Layer {x} has {found} keys but {layout_name} has {expected}, missing keys {found}..={}",
+                        expected - 1
+                    ),
+                },
+            ));
+        }
+
+        // positions the layout doesn't use stay KC_NO
+        let mut key_idents: Vec<Vec<Option<Expr>>> = vec![vec![None; matrix_cols]; matrix_rows];
+
+        // layers without encoders are transparent, like `_______` keys
+        let mut turns = vec![None; num_encoders];
+        if let Some(encoders) = layer.encoders {
+            if encoders.turns.len() != num_encoders {
+                error(syn::Error::new(
+                    encoders.span,
+                    format!(
+                        "Layer {x} has {} encoders but {keeb} has {num_encoders}",
+                        encoders.turns.len()
+                    ),
+                ));
+            }
+            for (turn, (mut ccw, mut cw)) in turns.iter_mut().zip(encoders.turns) {
+                for key in [&mut ccw, &mut cw] {
+                    if let Err(e) = check_key(key, &layer_name_strs, &keycode_names) {
+                        error(e);
+                    }
+                }
+                *turn = Some((ccw, cw));
+            }
+            has_encoders = true;
+        }
+        encoder_layers.push(turns);
+
+        // before layer names are replaced, so the drawing has them
+        if svg_dir.is_some() {
+            let layer_legends = layer.keys.iter().map(|key| svg::legend(key, &labels));
+            legends.push(layer_legends.collect::<Vec<_>>());
+        }
+
+        for (i, mut key) in layer.keys.into_iter().enumerate() {
+            if let Err(e) = check_key(&mut key, &layer_name_strs, &keycode_names) {
+                error(e);
+            }
+            lint_layers[x].keys.push(key.clone());
 
-        for (i, key) in layer.keys.into_iter().enumerate() {
             let Some(matrix_mapping) = matrix_map.layout.get(i).map(|m| m.matrix) else {
-                panic!(
-                    "Too many keys for {} in layer {x} (at key {i}, or '{}')",
-                    keymap.keeb,
-                    key.to_token_stream()
-                );
-            };
-            let col = matrix_mapping[1] as usize;
-            let row = matrix_mapping[0] as usize;
-            // key_idents[row][col] = key;
-            let Some(ident) = key_idents.get_mut(row).and_then(|r| r.get_mut(col)) else {
-                panic!(
-                    "Too many keys for {} in layer {x} (at key {i}, or '{}')",
-                    keymap.keeb,
-                    key.to_token_stream()
-                );
+                error(syn::Error::new(
+                    key.span(),
+                    format!(
+                        "Layer {x} has {found} keys but {layout_name} has {expected}, key {i} is extra"
+                    ),
+                ));
+                continue;
             };
-
-            *ident = key;
+            let [row, col] = matrix_mapping;
+            key_idents[row as usize][col as usize] = Some(key);
         }
 
         layers.push(key_idents);
+
+        lint_layers[x].push_encoders(x, &encoder_layers[x]);
+    }
+
+    let mut warnings = vec![];
+    if lint_level.as_deref() != Some("allow") {
+        for (span, message) in lint::lint(&lint_layers) {
+            if lint_level.as_deref() == Some("deny") {
+                error(syn::Error::new(span, message));
+            } else {
+                warnings.push(warning(span, &message));
+            }
+        }
+    }
+
+    if let Some(errors) = errors {
+        return errors.to_compile_error().into();
+    }
+
+    if let Some(dir) = svg_dir {
+        for (x, legends) in legends.iter().enumerate() {
+            let (file, title) = match layer_name_strings.get(x) {
+                Some(name) => (
+                    format!("layer_{x}_{name}.svg"),
+                    format!("Layer {x}: {name}"),
+                ),
+                None => (format!("layer_{x}.svg"), format!("Layer {x}")),
+            };
+            let svg = svg::render_layer(&title, &matrix_map.layout, legends);
+            if let Err(e) = svg::write_if_changed(&dir.join(file), &svg) {
+                return syn::Error::new(svg_span, e).to_compile_error().into();
+            }
+        }
     }
 
     let layers_tokens = layers
@@ -247,31 +523,10 @@ pub fn keymap(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
             let layer_tokens = layer
                 .iter()
                 .map(|row| {
-                    let row_tokens = row.chunks(matrix_rows).map(|keys| {
-                        // quote! {
-                        //     #(
-                        //         ::qmk::key!(#key)
-                        //     ),*
-                        // }
-                        let key_tokens = keys.iter().map(|key| {
-                            if key.to_token_stream().to_string().starts_with("CS_") {
-                                quote! {
-                                    #key
-                                }
-                            } else {
-                                quote! {
-                                    ::qmk::key!(#key)
-                                }
-                            }
-                        });
-
-                        quote! {
-                            #(#key_tokens),*
-                        }
-                    });
+                    let key_tokens = row.iter().map(|key| key_tokens(key.as_ref(), "KC_NO"));
                     quote! {
                         [
-                            #(#row_tokens),*
+                            #(#key_tokens),*
                         ]
                     }
                 })
@@ -291,11 +546,701 @@ pub fn keymap(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
         ]
     };
 
+    // `encoder_map` stores the clockwise keycode first, `ENCODER_CCW_CW` swaps them around
+    let encoder_map = has_encoders.then(|| {
+        let encoder_layers = encoder_layers.iter().map(|turns| {
+            let turns = turns.iter().map(|turn| {
+                let (ccw, cw) = match turn {
+                    Some((ccw, cw)) => (Some(ccw), Some(cw)),
+                    None => (None, None),
+                };
+                let ccw = key_tokens(ccw, "KC_TRNS");
+                let cw = key_tokens(cw, "KC_TRNS");
+                quote! { [#cw, #ccw] }
+            });
+            quote! { [#(#turns),*] }
+        });
+        quote! {
+            #[unsafe(no_mangle)]
+            #[allow(non_upper_case_globals)]
+            static encoder_map: [[[u16; 2]; #num_encoders]; #num_layers] = [#(#encoder_layers),*];
+        }
+    });
+
+    let size_mismatch = format!(
+        "{keeb} has a {matrix_rows}x{matrix_cols} matrix, but the firmware is built with a different MATRIX_ROWS x MATRIX_COLS"
+    );
+    let layer_enum = named.then(|| {
+        let variants = layer_names
+            .iter()
+            .map(|name| Ident::new(&keymap::to_camel_case(&name.to_string()), name.span()))
+            .collect::<Vec<_>>();
+        quote! {
+            /// The layers of the keymap, in order.
+            #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+            #[repr(u8)]
+            pub enum Layer {
+                #(#variants),*
+            }
+
+            #[allow(dead_code)]
+            impl Layer {
+                pub const ALL: [Self; #num_layers] = [#(Self::#variants),*];
+
+                pub const fn index(self) -> u8 {
+                    self as u8
+                }
+
+                /// The name the layer has in `keymap!`.
+                pub const fn name(self) -> &'static str {
+                    match self {
+                        #(Self::#variants => #layer_name_strings),*
+                    }
+                }
+            }
+        }
+    });
+
+    let num_layers_u8 = num_layers as u8;
     let output = quote! {
+        #json_dependency
+
+        #(#warnings)*
+
+        #layer_enum
+
+        #[allow(dead_code)]
+        pub const NUM_LAYERS: u8 = #num_layers_u8;
+
         #[unsafe(no_mangle)]
         #[allow(non_upper_case_globals)]
         static keymaps: [[[u16; #matrix_cols]; #matrix_rows]; #num_layers] = #layers;
+
+        #encoder_map
+
+        // only a firmware build knows the size its C side uses
+        const _: () = assert!(
+            match ::qmk::sys::MATRIX_SIZE {
+                Some((rows, cols)) => rows as usize == #matrix_rows && cols as usize == #matrix_cols,
+                None => true,
+            },
+            #size_mismatch
+        );
     };
 
     output.into()
 }
+
+/// Every keycode name `qmk::keys` has, to check keys against. Empty if QMK's tables can't be read.
+fn known_keycode_names() -> Vec<String> {
+    let keycode_spec = keycodes::load_spec().unwrap_or_default();
+    // `qmk::keys` has the shifted names from keymap_us.h too
+    let extra_names = keycodes::load_extra_names("us").unwrap_or_default();
+    keycode_spec
+        .into_iter()
+        .flat_map(|keycode| std::iter::once(keycode.key).chain(keycode.aliases))
+        .chain(extra_names)
+        .collect()
+}
+
+/// A warning from `keymap!` at `span`. Proc macros can't warn on stable, but using a deprecated
+/// item does, with its note as the message.
+fn warning(span: Span, message: &str) -> proc_macro2::TokenStream {
+    let lint = Ident::new("keymap_lint", span);
+    quote_spanned! {span=>
+        const _: () = {
+            #[deprecated(note = #message)]
+            #[allow(non_camel_case_types)]
+            struct #lint;
+            let _ = #lint;
+        };
+    }
+}
+
+/// The label of every keycode name that has one, like `Enter` for `KC_ENT`, to draw keys with.
+fn keycode_labels() -> HashMap<String, String> {
+    let keycode_spec = keycodes::load_spec().unwrap_or_default();
+    let extra_labels = keycodes::load_extra_labels("us").unwrap_or_default();
+    keycode_spec
+        .into_iter()
+        .filter_map(|keycode| Some((keycode.label?, keycode.key, keycode.aliases)))
+        .flat_map(|(label, key, aliases)| {
+            std::iter::once(key)
+                .chain(aliases)
+                .map(move |name| (name, label.clone()))
+        })
+        .chain(extra_labels)
+        .collect()
+}
+
+/// Resolves layer names in `key` and checks it's a keycode QMK knows.
+fn check_key(key: &mut Expr, layer_names: &[&str], keycode_names: &[&str]) -> syn::Result<()> {
+    if !layer_names.is_empty() {
+        keymap::resolve_layer_name(key, layer_names)?;
+    }
+    // without the table there's nothing to suggest from, so leave unknown names to rustc
+    if !keycode_names.is_empty() {
+        keymap::check_keycode(key, keycode_names)?;
+    }
+    Ok(())
+}
+
+/// The `u16` for a key in `keymap!`, or for `default` when there's no key there. A key named by
+/// path, like `CustomKeycode::Reset`, has to be a `qmk::custom_keycode::KeymapKey` to be cast.
+fn key_tokens(key: Option<&Expr>, default: &str) -> proc_macro2::TokenStream {
+    match key {
+        Some(Expr::Path(path)) if path.path.get_ident().is_none() => {
+            quote! { ::qmk::key!(::qmk::custom_keycode::keymap_key(#path)) }
+        }
+        Some(key) => quote! { ::qmk::key!(#key) },
+        None => {
+            let default = Ident::new(default, Span::call_site());
+            quote! { ::qmk::key!(#default) }
+        }
+    }
+}
+
+/// # Keycode table
+///
+/// Expands to two statics generated from QMK's `data/constants/keycodes/*.hjson`:
+/// `KEYCODE_TABLE`, every named keycode as `(keycode, name, label)` sorted by keycode, and
+/// `KEYCODE_NAMES`, every name and alias as `(name, keycode)` sorted by name.
+/// ```rust
+/// use qmk_macro::keycode_table;
+///
+/// keycode_table!();
+/// ```
+#[proc_macro]
+pub fn keycode_table(_input: proc_macro::TokenStream) -> proc_macro::TokenStream {
+    let spec = match keycodes::load_spec() {
+        Ok(spec) => spec,
+        Err(e) => {
+            return syn::Error::new(Span::call_site(), e)
+                .to_compile_error()
+                .into();
+        }
+    };
+
+    let by_code = spec.iter().map(|keycode| {
+        let code = keycode.code;
+        let key = &keycode.key;
+        let label = keycode.label.as_deref().unwrap_or_default();
+        quote! { (#code, #key, #label) }
+    });
+
+    let mut names = spec
+        .iter()
+        .flat_map(|keycode| {
+            std::iter::once(&keycode.key)
+                .chain(keycode.aliases.iter())
+                .map(|name| (name.as_str(), keycode.code))
+        })
+        .collect::<Vec<_>>();
+    names.sort();
+    names.dedup_by_key(|(name, _)| *name);
+    let by_name = names.iter().map(|(name, code)| quote! { (#name, #code) });
+
+    let output = quote! {
+        /// Every named keycode as `(keycode, name, label)`, sorted by keycode.
+        pub static KEYCODE_TABLE: &[(u16, &str, &str)] = &[#(#by_code),*];
+        /// Every keycode name and alias as `(name, keycode)`, sorted by name.
+        pub static KEYCODE_NAMES: &[(&str, u16)] = &[#(#by_name),*];
+    };
+
+    output.into()
+}
+
+/// # LED config
+///
+/// Expands to a `qmk::rgb_matrix::LedConfig` built from the keyboard's `rgb_matrix.layout`,
+/// searching `keyboard.json` and `info.json` from the given folder up to the keyboard's root.
+/// ```ignore
+/// use qmk::{led_config, rgb_matrix::LedConfig};
+///
+/// static LED_CONFIG: LedConfig = led_config!("sofle/rev1");
+/// ```
+#[proc_macro]
+pub fn led_config(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
+    let keeb = parse_macro_input!(input as LitStr);
+    let layout = match led_config::load_led_layout(&keeb.value()) {
+        Ok(layout) => layout,
+        Err(e) => return syn::Error::new(keeb.span(), e).to_compile_error().into(),
+    };
+
+    let leds = layout.iter().map(|led| {
+        let matrix = match led.matrix {
+            Some([row, col]) => quote! { Some((#row, #col)) },
+            None => quote! { None },
+        };
+        let (x, y, flags) = (led.x, led.y, led.flags);
+        quote! {
+            ::qmk::rgb_matrix::Led {
+                matrix: #matrix,
+                x: #x,
+                y: #y,
+                flags: ::qmk::rgb_matrix::LedFlags::from_bits_retain(#flags),
+            }
+        }
+    });
+
+    quote! {
+        ::qmk::rgb_matrix::LedConfig {
+            leds: &[#(#leds),*],
+        }
+    }
+    .into()
+}
+
+/// # Song
+///
+/// Expands to a `qmk::audio::Song` from note notation: whitespace-separated pitches like `C4`,
+/// `F#3` or `Bb5` (`R` for a rest), each optionally followed by `/` and a length, `w`, `h`, `q`,
+/// `e`, `s`, `t` or `b` (breve) with `.` for dotted, or a number of QMK duration units.
+/// ```ignore
+/// use qmk::{audio::Song, song};
+///
+/// static STARTUP: Song = song!("E6/e A6/e E7/q.");
+/// ```
+#[proc_macro]
+pub fn song(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
+    let notation = parse_macro_input!(input as LitStr);
+    let notes = match song::parse_song(&notation.value()) {
+        Ok(notes) => notes,
+        Err(e) => {
+            return syn::Error::new(notation.span(), e)
+                .to_compile_error()
+                .into();
+        }
+    };
+
+    let notes = notes.iter().map(|[frequency, duration]| {
+        let frequency = proc_macro2::Literal::f32_suffixed(*frequency);
+        let duration = proc_macro2::Literal::f32_suffixed(*duration);
+        quote! { [#frequency, #duration] }
+    });
+
+    quote! {
+        ::qmk::audio::Song::new(&[#(#notes),*])
+    }
+    .into()
+}
+
+/// # Combos
+///
+/// Generates QMK's `key_combos` table: each `[keys] => keycode` sends `keycode` when all of
+/// `keys` are pressed together. Needs `COMBO_ENABLE = yes` in `rules.mk`, which the glue
+/// generator adds.
+/// ```ignore
+/// use qmk::combos;
+///
+/// combos! {
+///     [KC_J, KC_K] => KC_ESC,
+///     [KC_D, KC_F, KC_G] => KC_CAPS,
+/// }
+/// ```
+#[proc_macro]
+pub fn combos(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
+    let combos = parse_macro_input!(input as combos::Combos);
+    let keycode_names = known_keycode_names();
+    let keycode_names = keycode_names.iter().map(String::as_str).collect::<Vec<_>>();
+
+    let mut errors: Option<syn::Error> = None;
+    let mut error = |e: syn::Error| match errors.as_mut() {
+        Some(errors) => errors.combine(e),
+        None => errors = Some(e),
+    };
+
+    let mut key_lists = vec![];
+    let mut entries = vec![];
+    for (i, mut combo) in combos.combos.into_iter().enumerate() {
+        if combo.keys.len() < 2 {
+            error(syn::Error::new(
+                combo.span,
+                "A combo needs at least two keys",
+            ));
+        }
+        for key in combo.keys.iter_mut().chain([&mut combo.keycode]) {
+            if let Err(e) = check_key(key, &[], &keycode_names) {
+                error(e);
+            }
+        }
+
+        let keys_name = Ident::new(&format!("COMBO_{i}_KEYS"), Span::call_site());
+        let len = combo.keys.len() + 1;
+        let keys = combo.keys.iter().map(|key| key_tokens(Some(key), "KC_NO"));
+        key_lists.push(quote! {
+            static #keys_name: [u16; #len] = [#(#keys,)* ::qmk::combo::COMBO_END];
+        });
+        let keycode = key_tokens(Some(&combo.keycode), "KC_NO");
+        entries.push(quote! { ::qmk::combo::combo(&#keys_name, #keycode) });
+    }
+
+    if let Some(errors) = errors {
+        return errors.to_compile_error().into();
+    }
+
+    let num_combos = entries.len();
+    quote! {
+        const _: () = {
+            #(#key_lists)*
+
+            #[unsafe(no_mangle)]
+            #[allow(non_upper_case_globals)]
+            static mut key_combos: [::qmk::combo::Combo; #num_combos] = [#(#entries),*];
+        };
+    }
+    .into()
+}
+
+/// # Key overrides
+///
+/// Generates QMK's `key_overrides` table. `mods + trigger => replacement` sends `replacement`
+/// instead of `trigger` while `mods` are held, anything else is used as a
+/// `qmk::key_override::KeyOverride` as-is. Needs `KEY_OVERRIDE_ENABLE = yes` in `rules.mk`, which
+/// the glue generator adds.
+/// ```ignore
+/// use qmk::{key, key_overrides, key_override::KeyOverride, mods::Mods};
+///
+/// key_overrides! {
+///     Mods::SHIFT + KC_BSPC => KC_DEL,
+///     Mods::CTRL.union(Mods::SHIFT) + KC_ESC => KC_GRV,
+///     KeyOverride::new(Mods::GUI, key!(KC_H), key!(KC_LEFT)).layers(1 << 1),
+/// }
+/// ```
+/// Since the table is a static, the modifiers are combined with `union` rather than `|`.
+#[proc_macro]
+pub fn key_overrides(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
+    let overrides = parse_macro_input!(input as key_overrides::KeyOverrides);
+    let keycode_names = known_keycode_names();
+    let keycode_names = keycode_names.iter().map(String::as_str).collect::<Vec<_>>();
+
+    let mut errors: Option<syn::Error> = None;
+    let mut error = |e: syn::Error| match errors.as_mut() {
+        Some(errors) => errors.combine(e),
+        None => errors = Some(e),
+    };
+
+    let mut entries = vec![];
+    for key_override in overrides.overrides {
+        match key_override {
+            key_overrides::KeyOverride::Basic {
+                mods,
+                mut trigger,
+                mut replacement,
+            } => {
+                for key in [&mut trigger, &mut replacement] {
+                    if let Err(e) = check_key(key, &[], &keycode_names) {
+                        error(e);
+                    }
+                }
+                let trigger = key_tokens(Some(&trigger), "KC_NO");
+                let replacement = key_tokens(Some(&replacement), "KC_NO");
+                entries.push(quote! {
+                    &::qmk::key_override::KeyOverride::new(#mods, #trigger, #replacement)
+                });
+            }
+            key_overrides::KeyOverride::Full(expr) => entries.push(quote! { &#expr }),
+        }
+    }
+
+    if let Some(errors) = errors {
+        return errors.to_compile_error().into();
+    }
+
+    let num_overrides = entries.len();
+    quote! {
+        const _: () = {
+            #[unsafe(no_mangle)]
+            #[allow(non_upper_case_globals)]
+            static key_overrides: [&::qmk::key_override::KeyOverride; #num_overrides] =
+                [#(#entries),*];
+        };
+    }
+    .into()
+}
+
+/// # Tap dance
+///
+/// Generates QMK's `tap_dance_actions` table and a `TapDance` enum with a variant per dance, for
+/// `td!` in the keymap. A dance is either `double(once, twice)`, or `advanced` with closures
+/// taking `&mut qmk::tap_dance::TapDanceState` for any of `each_tap`, `finished` and `reset`.
+/// Needs `TAP_DANCE_ENABLE = yes` in `rules.mk`, which the glue generator adds.
+/// ```ignore
+/// use qmk::{
+///     keyboard::Keyboard,
+///     keys::{KC_END, KC_HOME},
+///     tap_dance,
+/// };
+///
+/// tap_dance! {
+///     EscCaps: double(KC_ESC, KC_CAPS),
+///     HomeEnd: advanced {
+///         finished: |state| {
+///             let key = if state.count > 1 { KC_END } else { KC_HOME };
+///             Keyboard::send_key(key as u16);
+///         },
+///     },
+/// }
+///
+/// const ESC_CAPS: u16 = qmk::td!(TapDance::EscCaps);
+/// ```
+#[proc_macro]
+pub fn tap_dance(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
+    let dances = parse_macro_input!(input as tap_dance::TapDances);
+    let keycode_names = known_keycode_names();
+    let keycode_names = keycode_names.iter().map(String::as_str).collect::<Vec<_>>();
+
+    let mut errors: Option<syn::Error> = None;
+    let mut error = |e: syn::Error| match errors.as_mut() {
+        Some(errors) => errors.combine(e),
+        None => errors = Some(e),
+    };
+
+    let mut names: Vec<Ident> = vec![];
+    let mut items = vec![];
+    let mut entries = vec![];
+    for (i, dance) in dances.dances.into_iter().enumerate() {
+        if names.contains(&dance.name) {
+            error(syn::Error::new(
+                dance.name.span(),
+                format!("Tap dance `{}` is defined twice", dance.name),
+            ));
+        }
+        names.push(dance.name);
+
+        match dance.kind {
+            tap_dance::TapDanceKind::Double(mut once, mut twice) => {
+                for key in [&mut once, &mut twice] {
+                    if let Err(e) = check_key(key, &[], &keycode_names) {
+                        error(e);
+                    }
+                }
+                let pair = Ident::new(&format!("TAP_DANCE_{i}_PAIR"), Span::call_site());
+                let once = key_tokens(Some(&once), "KC_NO");
+                let twice = key_tokens(Some(&twice), "KC_NO");
+                items.push(quote! {
+                    static #pair: ::qmk::tap_dance::TapDancePair =
+                        ::qmk::tap_dance::TapDancePair { kc1: #once, kc2: #twice };
+                });
+                entries.push(quote! { ::qmk::tap_dance::double(&#pair) });
+            }
+            tap_dance::TapDanceKind::Advanced {
+                each_tap,
+                finished,
+                reset,
+            } => {
+                // QMK wants `extern "C"` functions, so each closure gets one to call it
+                let callbacks = [
+                    ("each_tap", each_tap),
+                    ("finished", finished),
+                    ("reset", reset),
+                ]
+                .map(|(field, closure)| {
+                    let closure = closure?;
+                    let function = Ident::new(&format!("tap_dance_{i}_{field}"), Span::call_site());
+                    items.push(quote! {
+                        unsafe extern "C" fn #function(
+                            state: *mut ::qmk::tap_dance::TapDanceState,
+                            _user_data: *mut ::core::ffi::c_void,
+                        ) {
+                            let callback: fn(&mut ::qmk::tap_dance::TapDanceState) = #closure;
+                            callback(unsafe { &mut *state });
+                        }
+                    });
+                    Some(function)
+                })
+                .map(|function| match function {
+                    Some(function) => quote! { Some(#function) },
+                    None => quote! { None },
+                });
+                let [each_tap, finished, reset] = callbacks;
+                entries.push(quote! { ::qmk::tap_dance::advanced(#each_tap, #finished, #reset) });
+            }
+        }
+    }
+
+    if let Some(errors) = errors {
+        return errors.to_compile_error().into();
+    }
+
+    let num_dances = entries.len();
+    quote! {
+        /// The tap dances, in `tap_dance_actions` order. `td!` turns one into its keycode.
+        #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+        #[repr(u8)]
+        #[allow(dead_code)]
+        pub enum TapDance {
+            #(#names),*
+        }
+
+        const _: () = {
+            #(#items)*
+
+            #[unsafe(no_mangle)]
+            #[allow(non_upper_case_globals)]
+            static mut tap_dance_actions: [::qmk::tap_dance::TapDanceAction; #num_dances] =
+                [#(#entries),*];
+        };
+    }
+    .into()
+}
+
+/// # Custom keycodes
+///
+/// Allocates a `QK_USER_n` keycode to each name, in order, as a `CustomKeycode` enum to use in
+/// `keymap!` like `CustomKeycode::Reset`. Each keycode is bound to either a `sequence` of keycodes
+/// tapped one after another when it's pressed, or a closure taking `&qmk::KeyRecord` for every
+/// press and release, returning whether QMK should carry on processing the key.
+///
+/// This generates `process_record_user`, which runs the bindings and then, for keys they don't
+/// stop, the `fallback` function if there is one, so the keymap doesn't define the callback
+/// itself:
+/// ```ignore
+/// use qmk::{KeyRecord, custom_keycode::is_pressed, custom_keycodes, info};
+///
+/// custom_keycodes! {
+///     fallback: process_record,
+///     Reset: sequence [KC_ENTER, KC_DOWN, KC_C],
+///     Hello: |record| {
+///         if is_pressed(record) {
+///             info!("Hello");
+///         }
+///         false
+///     },
+/// }
+///
+/// fn process_record(keycode: u16, record: &KeyRecord) -> bool {
+///     true
+/// }
+/// ```
+#[proc_macro]
+pub fn custom_keycodes(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
+    let keycodes = parse_macro_input!(input as custom_keycodes::CustomKeycodes);
+    let keycode_names = known_keycode_names();
+    let keycode_names = keycode_names.iter().map(String::as_str).collect::<Vec<_>>();
+
+    let mut errors: Option<syn::Error> = None;
+    let mut error = |e: syn::Error| match errors.as_mut() {
+        Some(errors) => errors.combine(e),
+        None => errors = Some(e),
+    };
+
+    if keycodes.keycodes.is_empty() {
+        error(syn::Error::new(
+            Span::call_site(),
+            "`custom_keycodes!` needs at least one keycode",
+        ));
+    }
+
+    let mut names: Vec<Ident> = vec![];
+    let mut values = vec![];
+    let mut arms = vec![];
+    for (i, keycode) in keycodes.keycodes.into_iter().enumerate() {
+        // QMK has 32 keycodes set aside for users
+        if i == 32 {
+            error(syn::Error::new(
+                keycode.name.span(),
+                "There are only 32 `QK_USER_n` keycodes to give out",
+            ));
+        }
+        if names.contains(&keycode.name) {
+            error(syn::Error::new(
+                keycode.name.span(),
+                format!("Custom keycode `{}` is defined twice", keycode.name),
+            ));
+        }
+
+        let name = &keycode.name;
+        let value = Ident::new(&format!("QK_USER_{i}"), Span::call_site());
+        values.push(quote! { ::qmk::keys::#value as u16 });
+        match keycode.action {
+            custom_keycodes::CustomKeycodeAction::Sequence(mut keys) => {
+                for key in keys.iter_mut() {
+                    if let Err(e) = check_key(key, &[], &keycode_names) {
+                        error(e);
+                    }
+                }
+                let keys = keys.iter().map(|key| key_tokens(Some(key), "KC_NO"));
+                arms.push(quote! {
+                    Self::#name => {
+                        if ::qmk::custom_keycode::is_pressed(record) {
+                            const KEYS: &[u16] = &[#(#keys),*];
+                            ::qmk::custom_keycode::send_sequence(KEYS);
+                        }
+                        true
+                    }
+                });
+            }
+            custom_keycodes::CustomKeycodeAction::Handler(closure) => {
+                arms.push(quote! {
+                    Self::#name => {
+                        let handler: fn(&::qmk::KeyRecord) -> bool = #closure;
+                        handler(record)
+                    }
+                });
+            }
+        }
+        names.push(keycode.name);
+    }
+
+    if let Some(errors) = errors {
+        return errors.to_compile_error().into();
+    }
+
+    let fallback = match keycodes.fallback {
+        Some(fallback) => quote! {
+            let fallback: fn(u16, &::qmk::KeyRecord) -> bool = #fallback;
+            fallback(keycode, record)
+        },
+        None => quote! { true },
+    };
+    // the build script writes the C side of the callback from the same signature
+    let signature = custom_keycodes::PROCESS_RECORD_USER
+        .parse::<proc_macro2::TokenStream>()
+        .unwrap();
+
+    let num_keycodes = names.len();
+    quote! {
+        /// The custom keycodes, from `QK_USER_0` on. `keymap!` takes them as they are.
+        #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+        #[repr(u16)]
+        #[allow(dead_code)]
+        pub enum CustomKeycode {
+            #(#names = #values),*
+        }
+
+        impl ::qmk::custom_keycode::KeymapKey for CustomKeycode {}
+
+        #[allow(dead_code)]
+        impl CustomKeycode {
+            /// Every custom keycode, in `QK_USER_n` order.
+            pub const ALL: [Self; #num_keycodes] = [#(Self::#names),*];
+
+            pub const fn raw(self) -> u16 {
+                self as u16
+            }
+
+            pub fn from_raw(keycode: u16) -> Option<Self> {
+                Self::ALL.into_iter().find(|custom| custom.raw() == keycode)
+            }
+
+            /// Runs what `keycode` is bound to and returns whether QMK should carry on processing
+            /// it, or `None` when it isn't a custom keycode.
+            pub fn process(keycode: u16, record: &::qmk::KeyRecord) -> Option<bool> {
+                Some(match Self::from_raw(keycode)? {
+                    #(#arms)*
+                })
+            }
+        }
+
+        #[::qmk::qmk_callback(#signature)]
+        fn process_record_user(keycode: u16, record: *const ::qmk::KeyRecord) -> bool {
+            let record = unsafe { &*record };
+            if CustomKeycode::process(keycode, record) == Some(false) {
+                return false;
+            }
+            #fallback
+        }
+    }
+    .into()
+}
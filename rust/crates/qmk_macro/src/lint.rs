@@ -0,0 +1,283 @@
+use proc_macro2::Span;
+use syn::{Expr, Lit, Token, parse_quote, punctuated::Punctuated, spanned::Spanned};
+
+use crate::keymap::LAYER_MACROS;
+
+/// A layer of `keymap!` as the lints see it.
+pub struct LintLayer {
+    /// How the layer is named in messages, like ``layer 2 (`game`)``.
+    pub label: String,
+    /// Where problems with the whole layer are reported.
+    pub span: Span,
+    /// The keys in layout order, then the encoders, with layer names already replaced by indices.
+    pub keys: Vec<Expr>,
+}
+
+impl LintLayer {
+    /// Adds layer `x`'s encoder turns after its keys, so a `_______` turn falls through to the
+    /// turn in the same place. A layer without encoders is transparent there, as in QMK's
+    /// `encoder_map`, except layer 0, which has nothing below it.
+    pub fn push_encoders(&mut self, x: usize, turns: &[Option<(Expr, Expr)>]) {
+        let missing: Expr = if x == 0 {
+            parse_quote!(XXXXXXX)
+        } else {
+            parse_quote!(_______)
+        };
+        for turn in turns {
+            let (ccw, cw) = turn.clone().unwrap_or((missing.clone(), missing.clone()));
+            self.keys.extend([ccw, cw]);
+        }
+    }
+}
+
+/// A key from `mo!`, `to!` or another layer macro.
+struct LayerSwitch {
+    kind: String,
+    /// `None` when the layer isn't a number, like a constant, so it could be any layer.
+    target: Option<usize>,
+}
+
+/// Checks how the layers connect: every layer should be reachable from layer 0, layer 0 has
+/// nothing below for `_______` to fall through to, and a layer switched to with `to!` or `tg!`
+/// needs a key that leaves it again. Returns where each problem is and what it is.
+pub fn lint(layers: &[LintLayer]) -> Vec<(Span, String)> {
+    let mut problems = Vec::new();
+
+    if let Some(base) = layers.first() {
+        for key in base.keys.iter().filter(|key| is_transparent(key)) {
+            problems.push((
+                key.span(),
+                String::from(
+                    "`_______` on layer 0 has no layer below it to fall through to, use `XXXXXXX` for a key that does nothing",
+                ),
+            ));
+        }
+    }
+
+    let switches = layers
+        .iter()
+        .map(|layer| {
+            layer
+                .keys
+                .iter()
+                .filter_map(layer_switch)
+                .collect::<Vec<_>>()
+        })
+        .collect::<Vec<_>>();
+
+    // a switch to an unknown layer could go anywhere, so only check when every target is known
+    if switches
+        .iter()
+        .flatten()
+        .all(|switch| switch.target.is_some())
+    {
+        let mut reachable = vec![false; layers.len()];
+        let mut queue = vec![0];
+        while let Some(x) = queue.pop() {
+            if x >= layers.len() || reachable[x] {
+                continue;
+            }
+            reachable[x] = true;
+            queue.extend(switches[x].iter().filter_map(|switch| switch.target));
+        }
+        for (layer, _) in layers
+            .iter()
+            .zip(reachable)
+            .filter(|(_, reachable)| !reachable)
+        {
+            problems.push((
+                layer.span,
+                format!(
+                    "Nothing switches to {}, so it can't be reached from layer 0",
+                    layer.label
+                ),
+            ));
+        }
+    }
+
+    for (x, layer) in layers.iter().enumerate().skip(1) {
+        let Some(entry) = switches
+            .iter()
+            .enumerate()
+            .filter(|(from, _)| *from != x)
+            .flat_map(|(_, switches)| switches)
+            .find(|switch| {
+                switch.target == Some(x) && (switch.kind == "to" || switch.kind == "tg")
+            })
+        else {
+            continue;
+        };
+        let has_exit = (0..layer.keys.len()).any(|i| {
+            resolve_switched_to(layers, x, i)
+                .and_then(layer_switch)
+                .is_some_and(|switch| leaves(&switch, x))
+        });
+        if !has_exit {
+            problems.push((
+                layer.span,
+                format!(
+                    "{} is switched to with `{}!` but has no key that leaves it",
+                    capitalise(&layer.label),
+                    entry.kind
+                ),
+            ));
+        }
+    }
+
+    problems
+}
+
+/// Whether `switch` gets off layer `x` once it's been switched to: going to another layer,
+/// or toggling `x` off.
+fn leaves(switch: &LayerSwitch, x: usize) -> bool {
+    match switch.kind.as_str() {
+        "to" | "df" | "pdf" => switch.target != Some(x),
+        "tg" | "tt" => switch.target.is_none_or(|target| target == x),
+        _ => false,
+    }
+}
+
+/// The key at `i` on layer `x` once it's been switched to with `to!` or `tg!`. A `_______` falls
+/// through to layer 0, the only layer below that's sure to still be on: `to!` turns the others
+/// off, and whatever was on before a `tg!` could be anything.
+fn resolve_switched_to(layers: &[LintLayer], x: usize, i: usize) -> Option<&Expr> {
+    let key = layers[x].keys.get(i)?;
+    if is_transparent(key) {
+        layers[0].keys.get(i)
+    } else {
+        Some(key)
+    }
+}
+
+fn is_transparent(key: &Expr) -> bool {
+    let Expr::Path(path) = key else {
+        return false;
+    };
+    path.path.segments.last().is_some_and(|segment| {
+        segment.ident == "_______"
+            || segment.ident == "KC_TRNS"
+            || segment.ident == "KC_TRANSPARENT"
+    })
+}
+
+fn layer_switch(key: &Expr) -> Option<LayerSwitch> {
+    let Expr::Macro(mac) = key else {
+        return None;
+    };
+    let kind = mac.mac.path.segments.last()?.ident.to_string();
+    if !LAYER_MACROS.contains(&kind.as_str()) {
+        return None;
+    }
+    let args = mac
+        .mac
+        .parse_body_with(Punctuated::<Expr, Token![,]>::parse_terminated)
+        .ok()?;
+    let target = match args.first()? {
+        Expr::Lit(lit) => match &lit.lit {
+            Lit::Int(int) => int.base10_parse().ok(),
+            _ => None,
+        },
+        _ => None,
+    };
+    Some(LayerSwitch { kind, target })
+}
+
+fn capitalise(text: &str) -> String {
+    let mut chars = text.chars();
+    chars
+        .next()
+        .map(|first| first.to_uppercase().chain(chars).collect())
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn layers(layers: &[&str]) -> Vec<LintLayer> {
+        layers
+            .iter()
+            .enumerate()
+            .map(|(x, keys)| LintLayer {
+                label: format!("layer {x}"),
+                span: Span::call_site(),
+                keys: syn::parse_str::<syn::ExprArray>(&format!("[{keys}]"))
+                    .unwrap()
+                    .elems
+                    .into_iter()
+                    .collect(),
+            })
+            .collect()
+    }
+
+    fn messages(layers: &[LintLayer]) -> Vec<String> {
+        lint(layers)
+            .into_iter()
+            .map(|(_, message)| message)
+            .collect()
+    }
+
+    #[test]
+    fn connected_layers_pass() {
+        let layers = layers(&[
+            "KC_A, mo!(1u8), to!(2u8)",
+            "_______, _______, KC_B",
+            "to!(0u8), KC_C, KC_D",
+        ]);
+        assert!(messages(&layers).is_empty());
+    }
+
+    #[test]
+    fn finds_unreachable_layers() {
+        let layers = layers(&["KC_A, mo!(1u8)", "KC_B, KC_C", "KC_D, KC_E"]);
+        assert_eq!(
+            messages(&layers),
+            ["Nothing switches to layer 2, so it can't be reached from layer 0"]
+        );
+
+        // a constant could be any layer
+        let layers = self::layers(&["KC_A, mo!(LAYER)", "KC_B, KC_C"]);
+        assert!(messages(&layers).is_empty());
+    }
+
+    #[test]
+    fn finds_transparent_base_keys() {
+        let layers = layers(&["KC_A, _______"]);
+        assert_eq!(messages(&layers).len(), 1);
+    }
+
+    #[test]
+    fn finds_dead_ends() {
+        let layers = layers(&["KC_A, to!(1u8)", "KC_B, KC_C"]);
+        assert_eq!(
+            messages(&layers),
+            ["Layer 1 is switched to with `to!` but has no key that leaves it"]
+        );
+
+        // the `tg!` on layer 0 shows through and toggles layer 1 back off
+        let layers = self::layers(&["KC_A, tg!(1u8)", "KC_B, _______"]);
+        assert!(messages(&layers).is_empty());
+    }
+
+    #[test]
+    fn switched_to_layers_fall_through_to_layer_0() {
+        // layer 1 isn't on once `to!(2u8)` has run, so its `to!(0u8)` doesn't show through
+        let layers = layers(&[
+            "KC_A, mo!(1u8), to!(2u8)",
+            "to!(0u8), _______, _______",
+            "_______, KC_C, KC_D",
+        ]);
+        assert_eq!(
+            messages(&layers),
+            ["Layer 2 is switched to with `to!` but has no key that leaves it"]
+        );
+    }
+
+    #[test]
+    fn layers_without_encoders_fall_through_to_their_turns() {
+        let mut layers = layers(&["KC_A, to!(1u8)", "KC_B, KC_C"]);
+        layers[0].push_encoders(0, &[Some((parse_quote!(KC_VOLD), parse_quote!(to!(0u8))))]);
+        layers[1].push_encoders(1, &[None]);
+        assert!(messages(&layers).is_empty());
+    }
+}
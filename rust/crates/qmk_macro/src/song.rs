@@ -0,0 +1,100 @@
+/// Reads `song!` notation into QMK's `{frequency, duration}` pairs.
+///
+/// Notes are separated by whitespace. Each is a pitch, `C4`, `F#3` or `Bb5` (or `R` for a rest),
+/// optionally followed by `/` and a length: `b` (breve), `w`, `h`, `q`, `e`, `s` or `t`
+/// (thirty-second), with a `.` for dotted, or a number in QMK's units where 64 is a whole note.
+/// The length defaults to a quarter note.
+pub fn parse_song(notation: &str) -> Result<Vec<[f32; 2]>, String> {
+    notation
+        .split_whitespace()
+        .map(|note| {
+            parse_note(note).ok_or_else(|| {
+                format!(
+                    "can't read note `{note}`, expected something like `C#4/q`, `Bb3/e.` or `R/h`"
+                )
+            })
+        })
+        .collect()
+}
+
+fn parse_note(note: &str) -> Option<[f32; 2]> {
+    let (pitch, length) = note.split_once('/').unwrap_or((note, "q"));
+    Some([frequency(pitch)?, duration(length)?])
+}
+
+fn frequency(pitch: &str) -> Option<f32> {
+    let mut chars = pitch.chars();
+    let semitone: i32 = match chars.next()?.to_ascii_uppercase() {
+        'R' if pitch.len() == 1 => return Some(0.0),
+        'C' => 0,
+        'D' => 2,
+        'E' => 4,
+        'F' => 5,
+        'G' => 7,
+        'A' => 9,
+        'B' => 11,
+        _ => return None,
+    };
+
+    let rest = chars.as_str();
+    let (accidental, octave) = if let Some(octave) = rest.strip_prefix('#') {
+        (1, octave)
+    } else if let Some(octave) = rest.strip_prefix('b') {
+        (-1, octave)
+    } else {
+        (0, rest)
+    };
+    let octave = octave
+        .parse::<i32>()
+        .ok()
+        .filter(|octave| (0..=8).contains(octave))?;
+
+    // MIDI note number, where A4 (69) is 440Hz
+    let midi = 12 * (octave + 1) + semitone + accidental;
+    let frequency = 440.0 * 2f64.powf((midi - 69) as f64 / 12.0);
+    // musical_notes.h rounds to two places, match it so songs sound the same as C ones
+    Some(((frequency * 100.0).round() / 100.0) as f32)
+}
+
+fn duration(length: &str) -> Option<f32> {
+    if let Ok(units) = length.parse::<u16>() {
+        return Some(units as f32);
+    }
+
+    let (length, dotted) = match length.strip_suffix('.') {
+        Some(length) => (length, true),
+        None => (length, false),
+    };
+    let units = match length {
+        "b" => 128,
+        "w" => 64,
+        "h" => 32,
+        "q" => 16,
+        "e" => 8,
+        "s" => 4,
+        "t" => 2,
+        _ => return None,
+    };
+    Some(if dotted { units + units / 2 } else { units } as f32)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn matches_musical_notes_h() {
+        assert_eq!(
+            parse_song("C4 A4/e. Db4/w R/3 B8/t").unwrap(),
+            vec![
+                [261.63, 16.0],
+                [440.0, 12.0],
+                [277.18, 64.0],
+                [0.0, 3.0],
+                [7902.13, 2.0]
+            ]
+        );
+        assert!(parse_song("H4").is_err());
+        assert!(parse_song("C4/x").is_err());
+    }
+}
@@ -0,0 +1,299 @@
+use std::collections::HashMap;
+use std::fmt::Write;
+use std::fs;
+use std::path::Path;
+
+use quote::ToTokens;
+use syn::{BinOp, Expr, Token, punctuated::Punctuated};
+
+use crate::keymap::MatrixMapping;
+
+/// The size of a 1u key in the SVG.
+const UNIT: f32 = 60.0;
+const GAP: f32 = 4.0;
+const MARGIN: f32 = 10.0;
+const TITLE: f32 = 30.0;
+const FONT_SIZE: f32 = 13.0;
+
+/// What's drawn on a key.
+pub enum Legend {
+    /// The lines of text on the key.
+    Key(Vec<String>),
+    /// `_______`, drawn faded with a triangle.
+    Transparent,
+    /// `XXXXXXX`, drawn faded and blank.
+    None,
+}
+
+/// The legend for a key in `keymap!`. Keycodes get their label from QMK's tables, like `Enter`
+/// for `KC_ENT`, and the layer macros show their name and arguments, like `MO` over `lower`.
+pub fn legend(key: &Expr, labels: &HashMap<String, String>) -> Legend {
+    match key {
+        Expr::Path(path) => {
+            let name = path
+                .path
+                .segments
+                .last()
+                .map(|segment| segment.ident.to_string())
+                .unwrap_or_default();
+            match name.as_str() {
+                "_______" | "KC_TRNS" | "KC_TRANSPARENT" => Legend::Transparent,
+                "XXXXXXX" | "KC_NO" => Legend::None,
+                _ => Legend::Key(
+                    inline_legend(key, labels)
+                        .split(' ')
+                        .map(String::from)
+                        .collect(),
+                ),
+            }
+        }
+        Expr::Macro(mac) => {
+            let name = mac
+                .mac
+                .path
+                .segments
+                .last()
+                .map(|segment| segment.ident.to_string())
+                .unwrap_or_default();
+            let Ok(args) = mac
+                .mac
+                .parse_body_with(Punctuated::<Expr, Token![,]>::parse_terminated)
+            else {
+                return Legend::Key(vec![key.to_token_stream().to_string()]);
+            };
+            if name == "key" && args.len() == 1 {
+                return legend(&args[0], labels);
+            }
+            let args = args
+                .iter()
+                .map(|arg| inline_legend(arg, labels))
+                .collect::<Vec<_>>();
+            Legend::Key(vec![name.to_uppercase(), args.join(",")])
+        }
+        _ => Legend::Key(vec![inline_legend(key, labels)]),
+    }
+}
+
+/// A legend on one line, for the arguments of a macro.
+fn inline_legend(key: &Expr, labels: &HashMap<String, String>) -> String {
+    match key {
+        Expr::Path(path) => {
+            let name = path
+                .path
+                .segments
+                .last()
+                .map(|segment| segment.ident.to_string())
+                .unwrap_or_default();
+            if let Some(label) = labels.get(&name) {
+                return label.clone();
+            }
+            ["KC_", "MOD_"]
+                .iter()
+                .find_map(|prefix| name.strip_prefix(prefix))
+                .unwrap_or(&name)
+                .to_string()
+        }
+        Expr::Macro(mac) => {
+            let name = mac
+                .mac
+                .path
+                .segments
+                .last()
+                .map(|segment| segment.ident.to_string())
+                .unwrap_or_default();
+            let args = mac
+                .mac
+                .parse_body_with(Punctuated::<Expr, Token![,]>::parse_terminated)
+                .map(|args| {
+                    args.iter()
+                        .map(|arg| inline_legend(arg, labels))
+                        .collect::<Vec<_>>()
+                        .join(",")
+                })
+                .unwrap_or_else(|_| mac.mac.tokens.to_string());
+            format!("{}({args})", name.to_uppercase())
+        }
+        Expr::Binary(binary) if matches!(binary.op, BinOp::BitOr(_)) => format!(
+            "{}|{}",
+            inline_legend(&binary.left, labels),
+            inline_legend(&binary.right, labels)
+        ),
+        Expr::Paren(paren) => inline_legend(&paren.expr, labels),
+        Expr::Cast(cast) => inline_legend(&cast.expr, labels),
+        _ => key.to_token_stream().to_string(),
+    }
+}
+
+/// Draws a layer as an SVG, each key where the layout puts it with the legend at the same index.
+pub fn render_layer(title: &str, layout: &[MatrixMapping], legends: &[Legend]) -> String {
+    // the corners of every key, turned the way the key is, to find how big the drawing is
+    let corners = layout
+        .iter()
+        .flat_map(|key| {
+            [(0.0, 0.0), (key.w, 0.0), (0.0, key.h), (key.w, key.h)]
+                .map(|(dx, dy)| rotate(key, key.x + dx, key.y + dy))
+        })
+        .collect::<Vec<_>>();
+    let min_x = corners.iter().map(|c| c.0).fold(0.0, f32::min);
+    let min_y = corners.iter().map(|c| c.1).fold(0.0, f32::min);
+    let max_x = corners.iter().map(|c| c.0).fold(0.0, f32::max);
+    let max_y = corners.iter().map(|c| c.1).fold(0.0, f32::max);
+    let width = (max_x - min_x) * UNIT + MARGIN * 2.0;
+    let height = (max_y - min_y) * UNIT + MARGIN * 2.0 + TITLE;
+
+    let mut svg = String::new();
+    let _ = writeln!(
+        svg,
+        r#"<svg xmlns="http://www.w3.org/2000/svg" width="{width}" height="{height}" viewBox="0 0 {width} {height}">"#
+    );
+    svg += "<style>\n";
+    svg += "rect { fill: #f4f4f4; stroke: #888; stroke-width: 1; }\n";
+    svg += ".faded rect { fill: #fff; stroke: #ccc; stroke-dasharray: 4 2; }\n";
+    svg += "text { font-family: sans-serif; fill: #222; text-anchor: middle; dominant-baseline: central; }\n";
+    svg += ".faded text { fill: #aaa; }\n";
+    svg += ".title { font-size: 16px; font-weight: bold; text-anchor: start; }\n";
+    svg += "</style>\n";
+    let _ = writeln!(
+        svg,
+        r#"<text class="title" x="{MARGIN}" y="{}">{}</text>"#,
+        MARGIN + TITLE / 2.0,
+        escape(title)
+    );
+    let _ = writeln!(
+        svg,
+        r#"<g transform="translate({} {})">"#,
+        MARGIN - min_x * UNIT,
+        MARGIN + TITLE - min_y * UNIT
+    );
+
+    for (i, key) in layout.iter().enumerate() {
+        let (lines, class) = match legends.get(i).unwrap_or(&Legend::None) {
+            Legend::Key(lines) => (lines.clone(), ""),
+            Legend::Transparent => (vec![String::from("▽")], r#" class="faded""#),
+            Legend::None => (Vec::new(), r#" class="faded""#),
+        };
+        let transform = if key.r == 0.0 {
+            String::new()
+        } else {
+            format!(
+                r#" transform="rotate({} {} {})""#,
+                key.r,
+                key.rx * UNIT,
+                key.ry * UNIT
+            )
+        };
+        let (x, y) = (key.x * UNIT, key.y * UNIT);
+        let (w, h) = (key.w * UNIT, key.h * UNIT);
+        let _ = writeln!(svg, "<g{class}{transform}>");
+        let _ = writeln!(
+            svg,
+            r#"<rect x="{}" y="{}" width="{}" height="{}" rx="6"/>"#,
+            x + GAP / 2.0,
+            y + GAP / 2.0,
+            w - GAP,
+            h - GAP
+        );
+
+        // shrink long legends to fit the key
+        let longest = lines
+            .iter()
+            .map(|line| line.chars().count())
+            .max()
+            .unwrap_or(0) as f32;
+        let size = FONT_SIZE
+            .min((w - GAP * 3.0) / (longest * 0.6))
+            .min((h - GAP * 3.0) / (lines.len() as f32 * 1.2))
+            .max(6.0);
+        let first = y + h / 2.0 - (lines.len() as f32 - 1.0) * size * 0.6;
+        for (n, line) in lines.iter().enumerate() {
+            let _ = writeln!(
+                svg,
+                r#"<text x="{:.1}" y="{:.1}" font-size="{size:.1}">{}</text>"#,
+                x + w / 2.0,
+                first + n as f32 * size * 1.2,
+                escape(line)
+            );
+        }
+        svg += "</g>\n";
+    }
+    svg += "</g>\n</svg>\n";
+    svg
+}
+
+/// Where `(x, y)` ends up after turning `key` around its rotation origin.
+fn rotate(key: &MatrixMapping, x: f32, y: f32) -> (f32, f32) {
+    let (sin, cos) = key.r.to_radians().sin_cos();
+    let (dx, dy) = (x - key.rx, y - key.ry);
+    (key.rx + dx * cos - dy * sin, key.ry + dx * sin + dy * cos)
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+/// Writes `contents` to `path` unless it's already there, so the files only change with the
+/// keymap.
+pub fn write_if_changed(path: &Path, contents: &str) -> Result<(), String> {
+    if fs::read_to_string(path).is_ok_and(|existing| existing == contents) {
+        return Ok(());
+    }
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir).map_err(|e| format!("Failed to create {}: {e}", dir.display()))?;
+    }
+    fs::write(path, contents).map_err(|e| format!("Failed to write {}: {e}", path.display()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn lines(legend: Legend) -> Vec<String> {
+        match legend {
+            Legend::Key(lines) => lines,
+            Legend::Transparent => vec![String::from("transparent")],
+            Legend::None => Vec::new(),
+        }
+    }
+
+    #[test]
+    fn legends_use_labels() {
+        let labels = HashMap::from([
+            (String::from("KC_ENT"), String::from("Enter")),
+            (String::from("KC_LSFT"), String::from("Left Shift")),
+            (String::from("KC_SPC"), String::from("Spacebar")),
+        ]);
+        let legend = |key: Expr| lines(legend(&key, &labels));
+
+        assert_eq!(legend(syn::parse_quote!(KC_ENT)), ["Enter"]);
+        assert_eq!(legend(syn::parse_quote!(KC_LSFT)), ["Left", "Shift"]);
+        assert_eq!(legend(syn::parse_quote!(KC_F20)), ["F20"]);
+        assert_eq!(legend(syn::parse_quote!(CustomKeycode::Reset)), ["Reset"]);
+        assert_eq!(legend(syn::parse_quote!(mo!(lower))), ["MO", "lower"]);
+        assert_eq!(
+            legend(syn::parse_quote!(lt!(lower, KC_SPC))),
+            ["LT", "lower,Spacebar"]
+        );
+        assert_eq!(legend(syn::parse_quote!(_______)), ["transparent"]);
+        assert!(legend(syn::parse_quote!(XXXXXXX)).is_empty());
+    }
+
+    #[test]
+    fn escapes_legends() {
+        let layout = [MatrixMapping {
+            matrix: [0, 0],
+            x: 0.0,
+            y: 0.0,
+            w: 1.0,
+            h: 1.0,
+            r: 0.0,
+            rx: 0.0,
+            ry: 0.0,
+        }];
+        let svg = render_layer("a & b", &layout, &[Legend::Key(vec![String::from("<")])]);
+        assert!(svg.contains(">a &amp; b</text>"));
+        assert!(svg.contains(">&lt;</text>"));
+    }
+}
@@ -0,0 +1,96 @@
+use syn::{Expr, Ident, Token, braced, parenthesized, parse::Parse, punctuated::Punctuated};
+
+/// The body of `tap_dance!`, one named dance per comma.
+pub struct TapDances {
+    pub dances: Vec<TapDance>,
+}
+
+impl Parse for TapDances {
+    fn parse(input: syn::parse::ParseStream) -> syn::Result<Self> {
+        let dances = Punctuated::<TapDance, Token![,]>::parse_terminated(input)?;
+        Ok(TapDances {
+            dances: dances.into_iter().collect(),
+        })
+    }
+}
+
+/// `EscCaps: double(KC_ESC, KC_CAPS)` or `Shift: advanced { finished: |state| .. }`
+pub struct TapDance {
+    /// The dance's variant in the generated `TapDance` enum.
+    pub name: Ident,
+    pub kind: TapDanceKind,
+}
+
+// only lives while the macro expands, so the size doesn't matter
+#[allow(clippy::large_enum_variant)]
+pub enum TapDanceKind {
+    /// One keycode when tapped once, the other when tapped twice.
+    Double(Expr, Expr),
+    /// Closures taking `&mut TapDanceState`, run on each tap, when the dance finishes and when
+    /// it resets.
+    Advanced {
+        each_tap: Option<Expr>,
+        finished: Option<Expr>,
+        reset: Option<Expr>,
+    },
+}
+
+impl Parse for TapDance {
+    fn parse(input: syn::parse::ParseStream) -> syn::Result<Self> {
+        let name = input.parse::<Ident>()?;
+        input.parse::<Token![:]>()?;
+        let kind = input.parse::<Ident>()?;
+
+        let kind = if kind == "double" {
+            let content;
+            parenthesized!(content in input);
+            let keys = Punctuated::<Expr, Token![,]>::parse_terminated(&content)?;
+            let [once, twice] = <[Expr; 2]>::try_from(keys.into_iter().collect::<Vec<_>>())
+                .map_err(|_| syn::Error::new(kind.span(), "`double` takes two keycodes"))?;
+            TapDanceKind::Double(once, twice)
+        } else if kind == "advanced" {
+            let content;
+            braced!(content in input);
+            let (mut each_tap, mut finished, mut reset) = (None, None, None);
+            while !content.is_empty() {
+                let field = content.parse::<Ident>()?;
+                content.parse::<Token![:]>()?;
+                let callback = content.parse::<Expr>()?;
+                let slot = match field.to_string().as_str() {
+                    "each_tap" => &mut each_tap,
+                    "finished" => &mut finished,
+                    "reset" => &mut reset,
+                    _ => {
+                        return Err(syn::Error::new(
+                            field.span(),
+                            format!(
+                                "Unknown callback `{field}`, expected `each_tap`, `finished` or `reset`"
+                            ),
+                        ));
+                    }
+                };
+                if slot.replace(callback).is_some() {
+                    return Err(syn::Error::new(
+                        field.span(),
+                        format!("`{field}` is given twice"),
+                    ));
+                }
+                if !content.is_empty() {
+                    content.parse::<Token![,]>()?;
+                }
+            }
+            TapDanceKind::Advanced {
+                each_tap,
+                finished,
+                reset,
+            }
+        } else {
+            return Err(syn::Error::new(
+                kind.span(),
+                format!("Unknown tap dance `{kind}`, expected `double(..)` or `advanced {{ .. }}`"),
+            ));
+        };
+
+        Ok(TapDance { name, kind })
+    }
+}
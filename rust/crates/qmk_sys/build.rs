@@ -1,5 +1,5 @@
 use bindgen::Formatter;
-use std::{env, path::PathBuf};
+use std::{env, fs, path::PathBuf};
 
 const HEADER_PATHS: &[&str] = &[
     "../../../quantum/quantum_keycodes.h",
@@ -9,14 +9,75 @@ const HEADER_PATHS: &[&str] = &[
     "../../../drivers/oled/oled_driver.h",
     "../../../quantum/logging/sendchar.h",
     "../../../quantum/rgblight/rgblight.h",
+    "../../../quantum/rgb_matrix/rgb_matrix.h",
+    "../../../quantum/audio/audio.h",
+    "../../../quantum/pointing_device/pointing_device.h",
     "../../../quantum/split_common/transactions.h",
     "../../../quantum/eeconfig.h",
+    "../../../quantum/os_detection.h",
+    "../../../quantum/process_keycode/process_combo.h",
+    "../../../quantum/process_keycode/process_tap_dance.h",
     "../../../quantum/keymap_extras/keymap_us.h",
 ];
 
+// stand-ins so the headers parse when there's no firmware build to take the matrix size from
+const PLACEHOLDER_MATRIX_ROWS: u8 = 10;
+const PLACEHOLDER_MATRIX_COLS: u8 = 7;
+
+/// The value of `name` the firmware is compiled with: a `-D` in `cflags`, or else the first
+/// `#define` in the headers it force-includes, like the `info_config.h` QMK generates from the
+/// keyboard's `keyboard.json`.
+fn firmware_define(cflags: &str, name: &str) -> Option<u8> {
+    let parse = |value: &str, source: &str| {
+        value
+            .trim()
+            .parse::<u8>()
+            .unwrap_or_else(|_| panic!("{name} in {source} isn't a number: {value}"))
+    };
+
+    let flags = cflags.split_whitespace().collect::<Vec<_>>();
+    let define = flags.iter().find_map(|flag| {
+        flag.strip_prefix("-D")?
+            .strip_prefix(name)?
+            .strip_prefix('=')
+            .map(|value| parse(value, "BINDGEN_CFLAGS"))
+    });
+    if define.is_some() {
+        return define;
+    }
+
+    flags
+        .windows(2)
+        .filter(|pair| pair[0] == "-include")
+        .find_map(|pair| {
+            // make runs from QMK's root, cargo from this crate
+            let path = if pair[1].starts_with('/') {
+                pair[1].to_string()
+            } else {
+                format!("../../../{}", pair[1])
+            };
+            println!("cargo:rerun-if-changed={path}");
+            let header = fs::read_to_string(&path).ok()?;
+            // QMK writes them as `#    define MATRIX_ROWS 10`
+            header.lines().find_map(|line| {
+                let mut words = line.trim_start().strip_prefix('#')?.split_whitespace();
+                (words.next() == Some("define") && words.next() == Some(name))
+                    .then(|| parse(&words.collect::<Vec<_>>().join(" "), &path))
+            })
+        })
+}
+
 fn main() {
     // bindgen will pass -D from BINDGEN_EXTRA_CLANG_ARGS to clang
+    println!("cargo:rerun-if-changed=build.rs");
+    println!("cargo:rerun-if-env-changed=BINDGEN_CFLAGS");
+    println!("cargo:rerun-if-env-changed=BINDGEN_INCLUDE");
     let cflags = std::env::var("BINDGEN_CFLAGS").unwrap_or_default();
+    // `keymap!` checks its keymaps against the firmware's matrix, when there's a firmware build
+    let matrix_size =
+        firmware_define(&cflags, "MATRIX_ROWS").zip(firmware_define(&cflags, "MATRIX_COLS"));
+    let (matrix_rows, matrix_cols) =
+        matrix_size.unwrap_or((PLACEHOLDER_MATRIX_ROWS, PLACEHOLDER_MATRIX_COLS));
     let include_directories = std::env::var("BINDGEN_INCLUDE").unwrap_or_else(|_| "-Iplatforms/chibios/converters/promicro_to_rp2040_ce -Ikeyboards/sofle/keymaps/nulls_keymap -Iusers/nulls_keymap -Ikeyboards/. -Ikeyboards/. -Ikeyboards/. -Ikeyboards/sofle -Ikeyboards/sofle/rev1 -I./platforms/chibios/boards/QMK_PM2040/configs -I. -Itmk_core -Iquantum -Iquantum/keymap_extras -Iquantum/process_keycode -Iquantum/sequencer -Idrivers -Iquantum/painter -Iquantum/unicode -Idrivers/painter/oled_panel -Idrivers/painter/sh1106 -Idrivers/painter/generic -Iplatforms/chibios/drivers/eeprom -Idrivers/eeprom -Iplatforms/chibios/drivers/wear_leveling -Idrivers/wear_leveling -Iquantum/wear_leveling -Iquantum/split_common -Idrivers/oled -Iplatforms/chibios/drivers/encoder -Idrivers/encoder -Iplatforms/chibios/drivers/vendor/RP/RP2040 -I.build/obj_sofle_rev1_nulls_keymap_elite_pi/src -Iquantum/logging -Ilib/printf/src -Ilib/printf/src/printf -Idrivers/painter/comms -Idrivers/painter/comms -Ilib/fnv -Iquantum/bootmagic/ -Iquantum/send_string/ -Itmk_core/protocol -Iplatforms -Iplatforms/chibios -Iplatforms/chibios/drivers -Itmk_core/protocol -Itmk_core/protocol/chibios -Itmk_core/protocol/chibios/lufa_utils -I./lib/chibios/os/license -I./platforms/chibios/boards/QMK_PM2040/configs -I./platforms/chibios/boards/common/configs -I./platforms/chibios/boards/QMK_PM2040/configs -I./platforms/chibios/boards/QMK_PM2040/configs -I./lib/chibios/os/common/portability/GCC -I./lib/chibios/os/common/startup/ARMCMx/compilers/GCC -I./lib/chibios/os/common/startup/ARMCMx/devices/RP2040 -I./lib/chibios/os/common/ext/ARM/CMSIS/Core/Include -I./lib/chibios/os/common/ext/RP/RP2040 -I./lib/chibios/os/rt/include -I./lib/chibios/os/common/portability/GCC -I./lib/chibios/os/common/ports/ARM-common -I./lib/chibios/os/common/ports/ARMv6-M-RP2 -I./lib/chibios/os/hal/osal/rt-nil -I./lib/chibios/os/oslib/include -I./lib/chibios/os/hal/include -I./lib/chibios/os/hal/ports/common/ARMCMx -I./lib/chibios/os/hal/ports/RP/RP2040 -I./lib/chibios/os/hal/ports/RP/LLD/DMAv1 -I./lib/chibios/os/hal/ports/RP/LLD/GPIOv1 -I./lib/chibios/os/hal/ports/RP/LLD/SPIv1 -I./lib/chibios/os/hal/ports/RP/LLD/TIMERv1 -I./lib/chibios/os/hal/ports/RP/LLD/UARTv1 -I./lib/chibios/os/hal/ports/RP/LLD/RTCv1 -I./lib/chibios/os/hal/ports/RP/LLD/WDGv1 -I./lib/chibios-contrib/os/hal/ports/RP/LLD/I2Cv1 -I./lib/chibios-contrib/os/hal/ports/RP/LLD/PWMv1 -I./lib/chibios-contrib/os/hal/ports/RP/LLD/ADCv1 -I./lib/chibios-contrib/os/hal/ports/RP/LLD/USBDv1 -I./lib/chibios/os/hal/boards/RP_PICO_RP2040 -I./lib/chibios/os/hal/lib/streams -I./lib/chibios/os/various -I. -Itmk_core -Iquantum -Iquantum/keymap_extras -Iquantum/process_keycode -Iquantum/sequencer -Idrivers -Iquantum/painter -Iquantum/unicode -Idrivers/painter/oled_panel -Idrivers/painter/sh1106 -Idrivers/painter/generic -Iplatforms/chibios/drivers/eeprom -Idrivers/eeprom -Iplatforms/chibios/drivers/wear_leveling -Idrivers/wear_leveling -Iquantum/wear_leveling -Iquantum/split_common -Idrivers/oled -Iplatforms/chibios/drivers/encoder -Idrivers/encoder -Iplatforms/chibios/drivers/vendor/RP/RP2040 -I./lib/chibios//os/various/pico_bindings/dumb/include -I./lib/pico-sdk/src/common/pico_base/include -I./lib/pico-sdk/src/rp2_common/pico_platform/include -I./lib/pico-sdk/src/rp2_common/hardware_base/include -I./lib/pico-sdk/src/rp2_common/hardware_clocks/include -I./lib/pico-sdk/src/rp2_common/hardware_claim/include -I./lib/pico-sdk/src/rp2_common/hardware_flash/include -I./lib/pico-sdk/src/rp2_common/hardware_gpio/include -I./lib/pico-sdk/src/rp2_common/hardware_irq/include -I./lib/pico-sdk/src/rp2_common/hardware_pll/include -I./lib/pico-sdk/src/rp2_common/hardware_pio/include -I./lib/pico-sdk/src/rp2_common/hardware_sync/include -I./lib/pico-sdk/src/rp2_common/hardware_timer/include -I./lib/pico-sdk/src/rp2_common/hardware_resets/include -I./lib/pico-sdk/src/rp2_common/hardware_watchdog/include -I./lib/pico-sdk/src/rp2_common/hardware_xosc/include -I./lib/pico-sdk/src/rp2040/hardware_regs/include -I./lib/pico-sdk/src/rp2040/hardware_structs/include -I./lib/pico-sdk/src/boards/include -I./lib/pico-sdk/src/rp2_common/pico_bootrom/include -Iplatforms/chibios/vendors/RP -I./lib/pico-sdk/src/common/pico_base/include -I./lib/pico-sdk/src/rp2_common/pico_platfrom/include -I./lib/pico-sdk/src/rp2_common/hardware_divider/include -Ikeyboards/. -Ikeyboards/. -Ikeyboards/. -Ikeyboards/sofle -Ikeyboards/sofle/rev1 -I./platforms/chibios/boards/QMK_PM2040/configs".to_string());
     let include_directories = include_directories
         .split(" ")
@@ -55,8 +116,8 @@ fn main() {
         .clang_arg("-I/usr/lib/picolibc/riscv64-unknown-elf/include")
         .clang_arg("-D NULLPTR_BINDGEN")
         .clang_arg("-D DEFERRED_EXEC_ENABLE")
-        .clang_arg("-D MATRIX_ROWS=10")
-        .clang_arg("-D MATRIX_COLS=7")
+        .clang_arg(format!("-D MATRIX_ROWS={matrix_rows}"))
+        .clang_arg(format!("-D MATRIX_COLS={matrix_cols}"))
         .clang_arg("-D RGB_MATRIX_LED_COUNT=35")
         .clang_arg("-D EEPROM_TEST_HARNESS")
         .clang_arg("-D ENCODER_A_PINS={}")
@@ -82,4 +143,17 @@ fn main() {
     bindings
         .write_to_file(out_path.join("bindings.rs"))
         .expect("Couldn't write bindings!");
+
+    // defines only given on the command line don't come through bindgen
+    let matrix_size = match matrix_size {
+        Some((rows, cols)) => format!("Some(({rows}, {cols}))"),
+        None => String::from("None"),
+    };
+    fs::write(
+        out_path.join("matrix.rs"),
+        format!(
+            "/// The firmware's `(MATRIX_ROWS, MATRIX_COLS)`, or `None` when built outside QMK's make.\npub const MATRIX_SIZE: Option<(u8, u8)> = {matrix_size};\n"
+        ),
+    )
+    .expect("Couldn't write matrix size!");
 }
@@ -2,3 +2,4 @@
 #![allow(warnings)]
 
 include!(concat!(env!("OUT_DIR"), "/bindings.rs"));
+include!(concat!(env!("OUT_DIR"), "/matrix.rs"));